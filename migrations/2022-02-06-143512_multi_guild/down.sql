@@ -0,0 +1,24 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE config
+DROP CONSTRAINT config_pkey,
+DROP guild_id,
+ADD PRIMARY KEY (name);
+
+ALTER TABLE training_bosses
+DROP guild_id,
+ADD UNIQUE (repr),
+ADD UNIQUE (wing, position);
+
+ALTER TABLE tiers
+DROP guild_id,
+ADD UNIQUE (name);
+
+DROP INDEX roles_active_constraint;
+ALTER TABLE roles
+DROP guild_id;
+CREATE UNIQUE INDEX roles_active_constraint ON roles(repr) WHERE active;
+
+ALTER TABLE trainings
+DROP guild_id;
+
+DROP TABLE guilds;
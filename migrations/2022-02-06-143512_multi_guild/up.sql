@@ -0,0 +1,43 @@
+-- Your SQL goes here
+CREATE TABLE guilds (
+	id BIGINT PRIMARY KEY
+);
+
+-- Everything created before belongs to a placeholder guild. It is taken
+-- over by MAIN_GUILD_ID on the next start of the bot
+INSERT INTO guilds (id) VALUES (0);
+
+ALTER TABLE trainings
+ADD guild_id BIGINT NOT NULL DEFAULT 0 REFERENCES guilds(id) ON UPDATE CASCADE;
+ALTER TABLE trainings
+ALTER guild_id DROP DEFAULT;
+
+ALTER TABLE roles
+ADD guild_id BIGINT NOT NULL DEFAULT 0 REFERENCES guilds(id) ON UPDATE CASCADE;
+ALTER TABLE roles
+ALTER guild_id DROP DEFAULT;
+DROP INDEX roles_active_constraint;
+CREATE UNIQUE INDEX roles_active_constraint ON roles(guild_id, repr) WHERE active;
+
+ALTER TABLE tiers
+ADD guild_id BIGINT NOT NULL DEFAULT 0 REFERENCES guilds(id) ON UPDATE CASCADE;
+ALTER TABLE tiers
+ALTER guild_id DROP DEFAULT,
+DROP CONSTRAINT tiers_name_key,
+ADD UNIQUE (guild_id, name);
+
+ALTER TABLE training_bosses
+ADD guild_id BIGINT NOT NULL DEFAULT 0 REFERENCES guilds(id) ON UPDATE CASCADE;
+ALTER TABLE training_bosses
+ALTER guild_id DROP DEFAULT,
+DROP CONSTRAINT training_bosses_repr_key,
+DROP CONSTRAINT training_bosses_wing_position_key,
+ADD UNIQUE (guild_id, repr),
+ADD UNIQUE (guild_id, wing, position);
+
+ALTER TABLE config
+ADD guild_id BIGINT NOT NULL DEFAULT 0 REFERENCES guilds(id) ON UPDATE CASCADE;
+ALTER TABLE config
+ALTER guild_id DROP DEFAULT,
+DROP CONSTRAINT config_pkey,
+ADD PRIMARY KEY (guild_id, name);
@@ -0,0 +1,5 @@
+-- Unicode emojis can not be represented by an id
+UPDATE roles SET emoji = '0' WHERE emoji !~ '^[0-9]+$';
+UPDATE training_bosses SET emoji = '0' WHERE emoji !~ '^[0-9]+$';
+ALTER TABLE roles ALTER COLUMN emoji TYPE BIGINT USING emoji::BIGINT;
+ALTER TABLE training_bosses ALTER COLUMN emoji TYPE BIGINT USING emoji::BIGINT;
@@ -0,0 +1,3 @@
+-- Custom emojis keep their id, unicode emojis are stored as they are
+ALTER TABLE roles ALTER COLUMN emoji TYPE TEXT USING emoji::TEXT;
+ALTER TABLE training_bosses ALTER COLUMN emoji TYPE TEXT USING emoji::TEXT;
@@ -0,0 +1,2 @@
+DROP TABLE training_requirements;
+ALTER TABLE roles DROP COLUMN category;
@@ -0,0 +1,14 @@
+ALTER TABLE roles ADD COLUMN category TEXT;
+
+-- Either a single role or all roles of a category
+CREATE TABLE training_requirements (
+	id SERIAL PRIMARY KEY,
+	training_id INTEGER NOT NULL REFERENCES trainings(id) ON DELETE CASCADE,
+	role_id INTEGER REFERENCES roles(id) ON DELETE CASCADE,
+	category TEXT,
+	min_count INTEGER NOT NULL DEFAULT 0,
+	max_count INTEGER,
+	CHECK ((role_id IS NULL) <> (category IS NULL)),
+	CHECK (min_count >= 0),
+	CHECK (max_count IS NULL OR max_count >= min_count)
+);
@@ -0,0 +1 @@
+ALTER TABLE trainings DROP COLUMN max_participants;
@@ -0,0 +1,2 @@
+-- Sign-ups past the limit are waitlisted in the order they signed up
+ALTER TABLE trainings ADD COLUMN max_participants INTEGER CHECK (max_participants > 0);
@@ -0,0 +1,4 @@
+DROP TRIGGER touch_signup ON signup_roles;
+DROP FUNCTION touch_signup_by_role();
+DROP TRIGGER set_updated_at ON signups;
+ALTER TABLE signups DROP COLUMN created_at, DROP COLUMN updated_at;
@@ -0,0 +1,18 @@
+-- Existing sign-ups get the time of the migration. Their ids still reflect the order
+ALTER TABLE signups
+	ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+	ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+
+SELECT diesel_manage_updated_at('signups');
+
+-- Changing the roles counts as a change of the sign-up
+CREATE FUNCTION touch_signup_by_role() RETURNS trigger AS $$
+BEGIN
+	UPDATE signups SET updated_at = current_timestamp
+		WHERE id = COALESCE(NEW.signup_id, OLD.signup_id);
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER touch_signup AFTER INSERT OR DELETE ON signup_roles
+	FOR EACH ROW EXECUTE PROCEDURE touch_signup_by_role();
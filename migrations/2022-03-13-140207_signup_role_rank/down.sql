@@ -0,0 +1 @@
+ALTER TABLE signup_roles DROP COLUMN rank;
@@ -0,0 +1,2 @@
+-- 1 is the main role, higher ranks are alternates. Existing sign-ups only have main roles
+ALTER TABLE signup_roles ADD COLUMN rank SMALLINT NOT NULL DEFAULT 1 CHECK (rank > 0);
@@ -0,0 +1,2 @@
+DROP TABLE user_default_roles;
+ALTER TABLE users DROP COLUMN default_comment;
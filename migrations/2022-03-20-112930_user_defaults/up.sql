@@ -0,0 +1,9 @@
+ALTER TABLE users ADD COLUMN default_comment TEXT;
+
+-- Roles are per guild, so are the defaults. Ranked like signup_roles
+CREATE TABLE user_default_roles (
+	user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+	rank SMALLINT NOT NULL CHECK (rank > 0),
+	PRIMARY KEY(user_id, role_id)
+);
@@ -0,0 +1,3 @@
+-- Members of a tier also qualify for every tier below it.
+-- The parent is the next higher tier
+ALTER TABLE tiers ADD COLUMN parent_id INTEGER REFERENCES tiers(id) ON DELETE SET NULL CHECK (parent_id <> id);
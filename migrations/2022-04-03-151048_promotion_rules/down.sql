@@ -0,0 +1,3 @@
+DROP TABLE promotions;
+DROP TABLE promotion_rules;
+DROP TYPE promotion_state;
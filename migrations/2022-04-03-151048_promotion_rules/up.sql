@@ -0,0 +1,24 @@
+CREATE TYPE promotion_state AS ENUM ('pending', 'approved', 'denied');
+
+-- Members that attended enough finished trainings of a tier, optionally with
+-- a main role, are promoted to one of the discord roles of the target tier
+CREATE TABLE promotion_rules (
+	id SERIAL PRIMARY KEY,
+	tier_id INTEGER NOT NULL,
+	discord_role_id BIGINT NOT NULL,
+	from_tier_id INTEGER NOT NULL REFERENCES tiers(id) ON DELETE CASCADE,
+	role_id INTEGER REFERENCES roles(id) ON DELETE CASCADE,
+	min_count INTEGER NOT NULL CHECK (min_count > 0),
+	automatic BOOLEAN NOT NULL DEFAULT false,
+	FOREIGN KEY (tier_id, discord_role_id) REFERENCES tier_mappings(tier_id, discord_role_id) ON DELETE CASCADE
+);
+
+-- A member is only promoted or queued once per rule
+CREATE TABLE promotions (
+	id SERIAL PRIMARY KEY,
+	rule_id INTEGER NOT NULL REFERENCES promotion_rules(id) ON DELETE CASCADE,
+	user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	state promotion_state NOT NULL DEFAULT 'pending',
+	created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+	UNIQUE (rule_id, user_id)
+);
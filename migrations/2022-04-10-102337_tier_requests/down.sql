@@ -0,0 +1 @@
+DROP TABLE tier_requests;
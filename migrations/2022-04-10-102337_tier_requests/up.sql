@@ -0,0 +1,16 @@
+-- Members asking for a tier themselves. Decided on like promotions
+CREATE TABLE tier_requests (
+	id SERIAL PRIMARY KEY,
+	user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	tier_id INTEGER NOT NULL REFERENCES tiers(id) ON DELETE CASCADE,
+	justification TEXT,
+	logs TEXT,
+	state promotion_state NOT NULL DEFAULT 'pending',
+	created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+	decided_at TIMESTAMP,
+	-- Discord id of the squadmaker
+	decided_by BIGINT
+);
+
+-- Only one open request per tier
+CREATE UNIQUE INDEX tier_requests_pending ON tier_requests(user_id, tier_id) WHERE state = 'pending';
@@ -0,0 +1 @@
+DROP TABLE boss_completions;
@@ -0,0 +1,10 @@
+-- Recorded for every attendee once a training finishes. The history is kept
+-- when the training is deleted
+CREATE TABLE boss_completions (
+	id SERIAL PRIMARY KEY,
+	user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	training_boss_id INTEGER NOT NULL REFERENCES training_bosses(id) ON DELETE CASCADE,
+	training_id INTEGER REFERENCES trainings(id) ON DELETE SET NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+	UNIQUE (user_id, training_boss_id, training_id)
+);
@@ -0,0 +1,16 @@
+ALTER TABLE training_bosses DROP CONSTRAINT training_bosses_guild_id_category_position_key;
+
+-- Keep the categories apart so the old constraint holds
+UPDATE training_bosses SET category_number = category_number + 100
+    WHERE category = 'strike';
+UPDATE training_bosses SET category_number = category_number + 200
+    WHERE category = 'fractal';
+UPDATE training_bosses SET category_number = category_number + 300
+    WHERE category = 'other';
+
+ALTER TABLE training_bosses RENAME COLUMN category_number TO wing;
+ALTER TABLE training_bosses DROP COLUMN category;
+ALTER TABLE training_bosses ADD CONSTRAINT training_bosses_guild_id_wing_position_key
+    UNIQUE (guild_id, wing, position);
+
+DROP TYPE boss_category;
@@ -0,0 +1,13 @@
+CREATE TYPE boss_category AS ENUM ('raid', 'strike', 'fractal', 'other');
+
+ALTER TABLE training_bosses ADD COLUMN category boss_category NOT NULL DEFAULT 'raid';
+ALTER TABLE training_bosses RENAME COLUMN wing TO category_number;
+
+-- Strikes used to be squeezed into wing 101 (Icebrood Saga) and 102 (End of Dragons)
+UPDATE training_bosses SET category = 'strike', category_number = category_number - 100
+    WHERE category_number > 100;
+
+ALTER TABLE training_bosses ALTER COLUMN category DROP DEFAULT;
+ALTER TABLE training_bosses DROP CONSTRAINT training_bosses_guild_id_wing_position_key;
+ALTER TABLE training_bosses ADD CONSTRAINT training_bosses_guild_id_category_position_key
+    UNIQUE (guild_id, category, category_number, position);
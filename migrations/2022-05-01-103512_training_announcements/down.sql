@@ -0,0 +1,3 @@
+ALTER TABLE trainings
+	DROP COLUMN announcement_message_id,
+	DROP COLUMN announcement_channel_id;
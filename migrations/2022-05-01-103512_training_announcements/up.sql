@@ -0,0 +1,5 @@
+-- The roster announcement posted once a training closes. Kept so later roster
+-- changes edit it instead of posting a new one
+ALTER TABLE trainings
+	ADD COLUMN announcement_channel_id BIGINT,
+	ADD COLUMN announcement_message_id BIGINT;
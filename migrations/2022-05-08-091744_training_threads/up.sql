@@ -0,0 +1,2 @@
+-- Discussion thread created once a training opens
+ALTER TABLE trainings ADD COLUMN thread_id BIGINT;
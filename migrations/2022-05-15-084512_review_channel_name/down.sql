@@ -0,0 +1 @@
+UPDATE config SET name = 'promotion_channel_id' WHERE name = 'review_channel_id';
@@ -0,0 +1,9 @@
+-- The promotion channel became the review channel once tier requests were
+-- posted there as well. A review channel set in the meantime wins
+UPDATE config SET name = 'review_channel_id'
+WHERE name = 'promotion_channel_id'
+AND NOT EXISTS (
+	SELECT 1 FROM config c
+	WHERE c.guild_id = config.guild_id AND c.name = 'review_channel_id'
+);
+DELETE FROM config WHERE name = 'promotion_channel_id';
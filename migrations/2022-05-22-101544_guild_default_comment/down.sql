@@ -0,0 +1,9 @@
+ALTER TABLE users ADD COLUMN default_comment TEXT;
+-- Only one of the guild comments fits
+UPDATE users SET default_comment = (
+	SELECT comment FROM user_defaults d
+	WHERE d.user_id = users.id AND d.comment IS NOT NULL
+	ORDER BY d.guild_id
+	LIMIT 1
+);
+DROP TABLE user_defaults;
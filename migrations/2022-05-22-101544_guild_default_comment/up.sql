@@ -0,0 +1,17 @@
+-- The default comment belongs to a guild, like the default roles
+CREATE TABLE user_defaults (
+	user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	guild_id BIGINT NOT NULL REFERENCES guilds(id) ON UPDATE CASCADE ON DELETE CASCADE,
+	comment TEXT,
+	PRIMARY KEY(user_id, guild_id)
+);
+
+-- Kept for every guild the user has default roles in
+INSERT INTO user_defaults (user_id, guild_id, comment)
+SELECT DISTINCT u.id, r.guild_id, u.default_comment
+FROM users u
+JOIN user_default_roles d ON d.user_id = u.id
+JOIN roles r ON r.id = d.role_id
+WHERE u.default_comment IS NOT NULL;
+
+ALTER TABLE users DROP COLUMN default_comment;
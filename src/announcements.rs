@@ -0,0 +1,260 @@
+//! # announcements
+//! Posts the final roster once a training closes. Selected participants are pinged with
+//! the role they play, so nobody has to post the roster by hand. The message is kept and
+//! edited whenever the roster changes afterwards
+
+use crate::{
+    db::{self, DBPoolProvider},
+    discord::{DiscordFacade, MessageContent},
+    embeds::{self, CrossroadsEmbeds},
+    roster::{Roster, RosterEntry},
+};
+use anyhow::Result;
+use serenity::{
+    builder::CreateEmbed,
+    model::{
+        id::{ChannelId, UserId},
+        mention::Mention,
+    },
+};
+use serenity_tools::builder::CreateEmbedExt;
+use tracing::warn;
+
+/// Discord allows 2000 characters of text per message
+const CONTENT_LIMIT: usize = 2000;
+
+fn mention(entry: &RosterEntry) -> String {
+    Mention::from(UserId::from(entry.user.discord_id())).to_string()
+}
+
+/// One line per role with everyone that plays it as their main role. Lines that
+/// do not fit anymore are summarized
+fn content(training: &db::Training, roster: &Roster) -> String {
+    let selected = roster
+        .entries
+        .iter()
+        .filter(|e| !e.waitlisted)
+        .collect::<Vec<_>>();
+
+    let mut lines = Vec::new();
+    for role in &roster.roles {
+        let players = selected
+            .iter()
+            .filter(|e| e.role_ids.first() == Some(&role.id))
+            .map(|e| mention(e))
+            .collect::<Vec<_>>();
+        if !players.is_empty() {
+            lines.push((
+                players.len(),
+                format!("{} **{}**: {}", role.emoji, role.title, players.join(" ")),
+            ));
+        }
+    }
+    let without_role = selected
+        .iter()
+        .filter(|e| match e.role_ids.first() {
+            Some(id) => !roster.roles.iter().any(|r| r.id == *id),
+            None => true,
+        })
+        .map(|e| mention(e))
+        .collect::<Vec<_>>();
+    if !without_role.is_empty() {
+        lines.push((
+            without_role.len(),
+            format!("**No role**: {}", without_role.join(" ")),
+        ));
+    }
+
+    let mut text = format!(
+        "**{}** | <t:{}:F>",
+        training.title,
+        training.date.timestamp()
+    );
+    let mut left = selected.len();
+    for (count, line) in lines {
+        // Reserves room for the summary below
+        if text.chars().count() + line.chars().count() + 40 > CONTENT_LIMIT {
+            text.push_str(&format!("\n… and {} more", left));
+            break;
+        }
+        text.push('\n');
+        text.push_str(&line);
+        left -= count;
+    }
+    text
+}
+
+/// The announcement of the training with its current roster
+pub(crate) fn message(
+    training: &db::Training,
+    roster: &Roster,
+    bosses: &[db::TrainingBoss],
+) -> MessageContent {
+    let selected = roster.entries.iter().filter(|e| !e.waitlisted).count();
+    let waitlist = roster
+        .entries
+        .iter()
+        .filter(|e| e.waitlisted)
+        .map(|e| e.user.gw2_id.clone())
+        .collect::<Vec<_>>();
+
+    let mut emb = CreateEmbed::xdefault();
+    emb.title(format!("Final roster: {}", training.title));
+    emb.description(format!("{} participants", selected));
+    let (a, b, c) = embeds::field_training_date(training);
+    emb.field(a, b, c);
+    embeds::embed_add_bosses(&mut emb, bosses, embeds::boss_link);
+    if !waitlist.is_empty() {
+        emb.fields_chunked_fmt(&waitlist, |w| w.clone(), "⏳ Waitlist", false, 20);
+    }
+
+    MessageContent {
+        content: content(training, roster),
+        embeds: vec![emb],
+        ..Default::default()
+    }
+}
+
+/// Posts the roster to the channel, or edits the earlier announcement of the training.
+/// Does nothing and returns false if it was never announced and no channel is set
+pub(crate) async fn announce(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    training: &db::Training,
+    channel_id: Option<ChannelId>,
+) -> Result<bool> {
+    let roster = Roster::load(ctx, training).await?;
+    let mut bosses = training.all_training_bosses(ctx).await?;
+    bosses.sort_by_key(|b| b.sort_key());
+    let msg = message(training, &roster, &bosses);
+
+    if let Some((channel, message_id)) = training.announcement() {
+        match ctx.edit_message(channel, message_id, msg.clone()).await {
+            Ok(()) => return Ok(true),
+            // Most likely deleted by hand. A new one is posted instead
+            Err(e) => warn!("Failed to edit announcement of {}: {}", training.id, e),
+        }
+    }
+
+    let channel = match channel_id {
+        Some(c) => c,
+        None => return Ok(false),
+    };
+    let message_id = ctx.send_message(channel, msg).await?;
+    training
+        .set_announcement(ctx, Some((channel, message_id)))
+        .await?;
+    Ok(true)
+}
+
+/// Brings the announcement of the training up to date after its roster changed.
+/// Mentions in edits do not ping, so the newly selected users are mentioned in a
+/// follow-up message. Trainings that were never announced are left alone
+pub(crate) async fn refresh(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    training: &db::Training,
+    newly_selected: &[db::User],
+) -> Result<()> {
+    // The caller might hold a copy from before the training was announced
+    let training = db::Training::by_id(ctx, training.guild(), training.id).await?;
+    if training.announcement().is_none() {
+        return Ok(());
+    }
+    announce(ctx, &training, None).await?;
+
+    // announce might have posted a new one if the old one was gone
+    let training = db::Training::by_id(ctx, training.guild(), training.id).await?;
+    if let (Some((channel, _)), false) = (training.announcement(), newly_selected.is_empty()) {
+        let mentions = newly_selected
+            .iter()
+            .map(|u| Mention::from(UserId::from(u.discord_id())).to_string())
+            .collect::<Vec<_>>();
+        let msg = MessageContent {
+            content: format!(
+                "{} moved up from the waitlist of **{}**",
+                mentions.join(" "),
+                training.title
+            ),
+            ..Default::default()
+        };
+        ctx.send_message(channel, msg).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        discord::mock::DiscordCall,
+        testing::{self, TestContext, GUILD_ID},
+    };
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn announcement_is_posted_once_and_then_edited() {
+        let ctx = TestContext::new();
+        let tank = testing::role(&ctx).await;
+        let training = testing::training(&ctx, db::TrainingState::Closed, None, &[&tank]).await;
+        let training = training.set_max_participants(&ctx, Some(1)).await.unwrap();
+        let (first, second) = (
+            testing::user(&ctx, 2101).await,
+            testing::user(&ctx, 2102).await,
+        );
+        for user in [&first, &second] {
+            let signup = db::Signup::insert(&ctx, user, &training).await.unwrap();
+            signup.add_role(&ctx, &tank, 1).await.unwrap();
+        }
+
+        assert!(!announce(&ctx, &training, None).await.unwrap());
+        assert!(ctx.discord.calls().is_empty());
+
+        let channel = ChannelId(4001);
+        assert!(announce(&ctx, &training, Some(channel)).await.unwrap());
+        let training = db::Training::by_id(&ctx, GUILD_ID, training.id)
+            .await
+            .unwrap();
+        let (announced_in, message_id) = training.announcement().unwrap();
+        assert_eq!(announced_in, channel);
+
+        let posted = match &ctx.discord.calls()[..] {
+            [DiscordCall::SendMessage(c, content)] if *c == channel => content.content.clone(),
+            calls => panic!("unexpected calls: {:?}", calls),
+        };
+        assert!(posted.contains(&format!("<@{}>", first.discord_id)));
+        // Waitlisted users are not pinged
+        assert!(!posted.contains(&format!("<@{}>", second.discord_id)));
+
+        db::Signup::by_user_and_training(&ctx, &first, &training)
+            .await
+            .unwrap()
+            .remove(&ctx)
+            .await
+            .unwrap();
+        refresh(&ctx, &training, std::slice::from_ref(&second))
+            .await
+            .unwrap();
+
+        let edited = ctx.discord.last_edit(channel, message_id).unwrap();
+        assert!(edited
+            .content
+            .contains(&format!("<@{}>", second.discord_id)));
+        // The edit does not ping, the follow-up does
+        match ctx.discord.calls().last() {
+            Some(DiscordCall::SendMessage(c, follow_up)) if *c == channel => assert!(follow_up
+                .content
+                .contains(&format!("<@{}>", second.discord_id))),
+            call => panic!("unexpected call: {:?}", call),
+        }
+
+        // Nobody to mention, nothing to follow up with
+        refresh(&ctx, &training, &[]).await.unwrap();
+        assert_eq!(
+            ctx.discord
+                .calls()
+                .iter()
+                .filter(|c| matches!(c, DiscordCall::SendMessage(..)))
+                .count(),
+            2
+        );
+    }
+}
@@ -1,24 +1,304 @@
-use crate::db::DBPool;
+use crate::db::{self, DBPool, DBPoolProvider};
 use crate::signup_board::SignupBoard;
+use anyhow::{Context as ErrContext, Result};
 use serenity::{model::prelude::*, prelude::*};
-use std::sync::Arc;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tokio::sync::mpsc;
 
+/// Values every guild provides during the setup
+#[derive(Clone)]
 pub struct ConfigValues {
-    pub main_guild_id: GuildId,
     pub admin_role_id: RoleId,
     pub squadmaker_role_id: RoleId,
     pub emoji_guild_id: GuildId,
 }
 
+pub static ADMIN_ROLE_NAME: &str = "admin_role_id";
+pub static SQUADMAKER_ROLE_NAME: &str = "squadmaker_role_id";
+pub static EMOJI_GUILD_NAME: &str = "emoji_guild_id";
+
+async fn load_config_id(ctx: &impl DBPoolProvider, guild_id: GuildId, name: &str) -> Result<u64> {
+    db::Config::load(ctx, guild_id, name.to_string())
+        .await
+        .with_context(|| format!("{} not set", name))?
+        .value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Failed to parse {}", name))
+}
+
+impl ConfigValues {
+    pub async fn load_from_db(ctx: &impl DBPoolProvider, guild_id: GuildId) -> Result<Self> {
+        Ok(ConfigValues {
+            admin_role_id: RoleId::from(load_config_id(ctx, guild_id, ADMIN_ROLE_NAME).await?),
+            squadmaker_role_id: RoleId::from(
+                load_config_id(ctx, guild_id, SQUADMAKER_ROLE_NAME).await?,
+            ),
+            emoji_guild_id: GuildId::from(load_config_id(ctx, guild_id, EMOJI_GUILD_NAME).await?),
+        })
+    }
+
+    pub async fn save_to_db(&self, ctx: &impl DBPoolProvider, guild_id: GuildId) -> Result<()> {
+        let values = [
+            (ADMIN_ROLE_NAME, self.admin_role_id.to_string()),
+            (SQUADMAKER_ROLE_NAME, self.squadmaker_role_id.to_string()),
+            (EMOJI_GUILD_NAME, self.emoji_guild_id.to_string()),
+        ];
+        for (name, value) in values {
+            db::Config {
+                name: name.to_string(),
+                value,
+            }
+            .save(ctx, guild_id)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// The values of a guild that went through the setup
+    pub async fn get(ctx: &Context, guild_id: GuildId) -> Result<Arc<Self>> {
+        ctx.data
+            .read()
+            .await
+            .get::<ConfigValuesData>()
+            .unwrap()
+            .read()
+            .await
+            .get(&guild_id)
+            .cloned()
+            .context("This server is not set up yet. Use /setup first")
+    }
+
+    /// Changes the values kept in memory. Does not save them
+    async fn update(ctx: &Context, guild_id: GuildId, f: impl FnOnce(&mut ConfigValues)) {
+        let values = ctx
+            .data
+            .read()
+            .await
+            .get::<ConfigValuesData>()
+            .unwrap()
+            .clone();
+        let mut values = values.write().await;
+        if let Some(v) = values.get_mut(&guild_id) {
+            f(Arc::make_mut(v));
+        }
+    }
+}
+
 pub static INFO_LOG_NAME: &str = "log_channel_id";
+pub static REVIEW_CHANNEL_NAME: &str = "review_channel_id";
+/// The review channel was saved under this name before tier requests existed
+static PROMOTION_CHANNEL_NAME: &str = "promotion_channel_id";
+pub static ANNOUNCEMENT_CHANNEL_NAME: &str = "announcement_channel_id";
+pub static DISCUSSION_CHANNEL_NAME: &str = "discussion_channel_id";
+
+/// Settings that can be changed at runtime with /config set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    AdminRole,
+    SquadmakerRole,
+    EmojiGuild,
+    LogChannel,
+    ReviewChannel,
+    AnnouncementChannel,
+    DiscussionChannel,
+}
+
+/// What kind of discord id a setting holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKind {
+    Role,
+    Guild,
+    Channel,
+    /// A text or forum channel threads are created in
+    ThreadParent,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 7] = [
+        Setting::AdminRole,
+        Setting::SquadmakerRole,
+        Setting::EmojiGuild,
+        Setting::LogChannel,
+        Setting::ReviewChannel,
+        Setting::AnnouncementChannel,
+        Setting::DiscussionChannel,
+    ];
+
+    /// The name used in /config set
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setting::AdminRole => "admin_role",
+            Setting::SquadmakerRole => "squadmaker_role",
+            Setting::EmojiGuild => "emoji_server",
+            Setting::LogChannel => "log_channel",
+            Setting::ReviewChannel => "review_channel",
+            Setting::AnnouncementChannel => "announcement_channel",
+            Setting::DiscussionChannel => "discussion_channel",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Setting::AdminRole => "The role that can configure the bot",
+            Setting::SquadmakerRole => "The role that can manage trainings",
+            Setting::EmojiGuild => "The server role and boss emojis are taken from",
+            Setting::LogChannel => "The channel all logs are posted to",
+            Setting::ReviewChannel => {
+                "The channel promotions and tier requests are posted to. Defaults to the log channel"
+            }
+            Setting::AnnouncementChannel => {
+                "The channel the final roster of closed trainings is posted to"
+            }
+            Setting::DiscussionChannel => {
+                "The text or forum channel opened trainings get a discussion thread in"
+            }
+        }
+    }
+
+    pub fn kind(&self) -> SettingKind {
+        match self {
+            Setting::AdminRole | Setting::SquadmakerRole => SettingKind::Role,
+            Setting::EmojiGuild => SettingKind::Guild,
+            Setting::LogChannel | Setting::ReviewChannel | Setting::AnnouncementChannel => {
+                SettingKind::Channel
+            }
+            Setting::DiscussionChannel => SettingKind::ThreadParent,
+        }
+    }
+
+    /// The setting saved under the name in the config table, if any. Also knows
+    /// the names from older exports
+    pub fn by_config_name(name: &str) -> Option<Setting> {
+        if name == PROMOTION_CHANNEL_NAME {
+            return Some(Setting::ReviewChannel);
+        }
+        Setting::ALL.into_iter().find(|s| s.config_name() == name)
+    }
+
+    /// The name in the config table
+    pub fn config_name(&self) -> &'static str {
+        match self {
+            Setting::AdminRole => ADMIN_ROLE_NAME,
+            Setting::SquadmakerRole => SQUADMAKER_ROLE_NAME,
+            Setting::EmojiGuild => EMOJI_GUILD_NAME,
+            Setting::LogChannel => INFO_LOG_NAME,
+            Setting::ReviewChannel => REVIEW_CHANNEL_NAME,
+            Setting::AnnouncementChannel => ANNOUNCEMENT_CHANNEL_NAME,
+            Setting::DiscussionChannel => DISCUSSION_CHANNEL_NAME,
+        }
+    }
+
+    /// The current value of the guild. None if it is not set
+    pub async fn get(&self, ctx: &Context, guild_id: GuildId) -> Option<u64> {
+        match self {
+            Setting::AdminRole => ConfigValues::get(ctx, guild_id)
+                .await
+                .ok()
+                .map(|v| v.admin_role_id.0),
+            Setting::SquadmakerRole => ConfigValues::get(ctx, guild_id)
+                .await
+                .ok()
+                .map(|v| v.squadmaker_role_id.0),
+            Setting::EmojiGuild => ConfigValues::get(ctx, guild_id)
+                .await
+                .ok()
+                .map(|v| v.emoji_guild_id.0),
+            Setting::LogChannel => {
+                let log_conf = ctx.data.read().await.get::<LogConfigData>()?.clone();
+                let log = log_conf.read().await.log.get(&guild_id).map(|c| c.0);
+                log
+            }
+            // Only needed when the state of trainings changes, so they are not kept in memory
+            Setting::ReviewChannel | Setting::AnnouncementChannel | Setting::DiscussionChannel => {
+                db::Config::load(ctx, guild_id, self.config_name().to_string())
+                    .await
+                    .ok()
+                    .and_then(|c| c.value.parse().ok())
+            }
+        }
+    }
+
+    /// Saves the already validated value and applies it without a restart
+    pub async fn set(&self, ctx: &Context, guild_id: GuildId, id: u64) -> Result<()> {
+        // Settings only exist for guilds that went through the setup
+        ConfigValues::get(ctx, guild_id).await?;
+
+        db::Config {
+            name: self.config_name().to_string(),
+            value: id.to_string(),
+        }
+        .save(ctx, guild_id)
+        .await?;
+
+        match self {
+            Setting::AdminRole => {
+                ConfigValues::update(ctx, guild_id, |v| v.admin_role_id = RoleId(id)).await
+            }
+            Setting::SquadmakerRole => {
+                ConfigValues::update(ctx, guild_id, |v| v.squadmaker_role_id = RoleId(id)).await
+            }
+            Setting::EmojiGuild => {
+                ConfigValues::update(ctx, guild_id, |v| v.emoji_guild_id = GuildId(id)).await
+            }
+            Setting::LogChannel => {
+                let log_conf = ctx
+                    .data
+                    .read()
+                    .await
+                    .get::<LogConfigData>()
+                    .unwrap()
+                    .clone();
+                log_conf.write().await.log.insert(guild_id, ChannelId(id));
+            }
+            Setting::ReviewChannel | Setting::AnnouncementChannel | Setting::DiscussionChannel => {}
+        }
+        Ok(())
+    }
+}
+
+/// The channel squadmakers decide on promotions and tier requests in
+pub async fn review_channel(ctx: &Context, guild_id: GuildId) -> Option<ChannelId> {
+    match Setting::ReviewChannel.get(ctx, guild_id).await {
+        Some(id) => Some(ChannelId(id)),
+        None => Setting::LogChannel.get(ctx, guild_id).await.map(ChannelId),
+    }
+}
+
+/// The channel the final roster of closed trainings is announced in
+pub async fn announcement_channel(ctx: &Context, guild_id: GuildId) -> Option<ChannelId> {
+    Setting::AnnouncementChannel
+        .get(ctx, guild_id)
+        .await
+        .map(ChannelId)
+}
+
+/// The channel discussion threads of trainings are created in. Can be a forum
+pub async fn discussion_channel(ctx: &Context, guild_id: GuildId) -> Option<ChannelId> {
+    Setting::DiscussionChannel
+        .get(ctx, guild_id)
+        .await
+        .map(ChannelId)
+}
+
+impl FromStr for Setting {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Setting::ALL
+            .into_iter()
+            .find(|setting| setting.name() == s)
+            .with_context(|| format!("Unknown setting: {}", s))
+    }
+}
 
 pub struct LogConfig {
-    pub log: Option<ChannelId>,
+    /// The log channel of each guild
+    pub log: HashMap<GuildId, ChannelId>,
 }
 
 pub struct ConfigValuesData;
 impl TypeMapKey for ConfigValuesData {
-    type Value = Arc<ConfigValues>;
+    type Value = Arc<RwLock<HashMap<GuildId, Arc<ConfigValues>>>>;
 }
 
 pub struct LogConfigData;
@@ -28,7 +308,13 @@ impl TypeMapKey for LogConfigData {
 
 pub struct SignupBoardData;
 impl TypeMapKey for SignupBoardData {
-    type Value = Arc<RwLock<SignupBoard>>;
+    type Value = Arc<RwLock<HashMap<GuildId, Arc<RwLock<SignupBoard>>>>>;
+}
+
+/// Notifies the signup board task that something shown on the board of the guild changed
+pub struct SignupBoardUpdateData;
+impl TypeMapKey for SignupBoardUpdateData {
+    type Value = mpsc::UnboundedSender<GuildId>;
 }
 
 pub struct DBPoolData;
@@ -5,15 +5,17 @@
 
 use crate::data::DBPoolData;
 use chrono::{NaiveDate, NaiveDateTime};
+use diesel::dsl::sql;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
 use diesel::result::QueryResult;
+use diesel::sql_types::{BigInt, Date};
+use itertools::Itertools;
+use serenity::async_trait;
 use serenity::client::Context;
-use serenity::model::{
-    id::{EmojiId, MessageId, UserId},
-    mention::Mention,
-};
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
 use tokio::task;
@@ -34,8 +36,8 @@ impl DBPool {
         DBPool(Pool::new(manager).unwrap())
     }
 
-    async fn load(ctx: &Context) -> Arc<Self> {
-        ctx.data.read().await.get::<DBPoolData>().unwrap().clone()
+    async fn load(ctx: &impl DBPoolProvider) -> Arc<Self> {
+        ctx.db_pool().await
     }
 
     fn conn(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
@@ -49,8 +51,82 @@ impl Default for DBPool {
     }
 }
 
+/// Anything the connection pool can be taken from. All queries accept this
+/// instead of the serenity Context so they can also be run without discord
+#[async_trait]
+pub trait DBPoolProvider: Send + Sync {
+    async fn db_pool(&self) -> Arc<DBPool>;
+}
+
+#[async_trait]
+impl DBPoolProvider for Context {
+    async fn db_pool(&self) -> Arc<DBPool> {
+        self.data.read().await.get::<DBPoolData>().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl DBPoolProvider for Arc<DBPool> {
+    async fn db_pool(&self) -> Arc<DBPool> {
+        self.clone()
+    }
+}
+
+// Runs every connection of the pool inside a transaction that is never
+// committed, so tests leave the database untouched
+#[cfg(test)]
+#[derive(Debug)]
+struct TestTransaction;
+
+#[cfg(test)]
+impl diesel::r2d2::CustomizeConnection<PgConnection, diesel::r2d2::Error> for TestTransaction {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.begin_test_transaction()
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+#[cfg(test)]
+impl DBPool {
+    /// A single connection pool for tests. Migrations are run once per
+    /// test binary, everything else is rolled back when the pool is dropped
+    pub fn test() -> Self {
+        static MIGRATIONS: std::sync::Once = std::sync::Once::new();
+
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        MIGRATIONS.call_once(|| {
+            let conn = PgConnection::establish(&database_url)
+                .unwrap_or_else(|_| panic!("Error connecting to {}", database_url));
+            crate::embedded_migrations::run(&conn).expect("Failed to run migrations");
+        });
+
+        let manager = ConnectionManager::<PgConnection>::new(database_url);
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_customizer(Box::new(TestTransaction))
+            .build(manager)
+            .unwrap();
+        DBPool(pool)
+    }
+}
+
 // Insert und Upsert
-async fn upsert_user(ctx: &Context, user: NewUser) -> QueryResult<User> {
+async fn insert_guild(ctx: &impl DBPoolProvider, g: Guild) -> QueryResult<Guild> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        diesel::insert_into(guilds::table)
+            .values(&g)
+            .on_conflict_do_nothing()
+            .execute(&conn)?;
+        guilds::table.find(g.id).first(&conn)
+    })
+    .await
+    .unwrap()
+}
+
+async fn upsert_user(ctx: &impl DBPoolProvider, user: NewUser) -> QueryResult<User> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(users::table)
@@ -64,7 +140,24 @@ async fn upsert_user(ctx: &Context, user: NewUser) -> QueryResult<User> {
     .unwrap()
 }
 
-async fn insert_training(ctx: &Context, t: NewTraining) -> QueryResult<Training> {
+async fn upsert_user_defaults(
+    ctx: &impl DBPoolProvider,
+    defaults: UserDefaults,
+) -> QueryResult<UserDefaults> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::insert_into(user_defaults::table)
+            .values(&defaults)
+            .on_conflict((user_defaults::user_id, user_defaults::guild_id))
+            .do_update()
+            .set(user_defaults::comment.eq(&defaults.comment))
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn insert_training(ctx: &impl DBPoolProvider, t: NewTraining) -> QueryResult<Training> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(trainings::table)
@@ -75,7 +168,381 @@ async fn insert_training(ctx: &Context, t: NewTraining) -> QueryResult<Training>
     .unwrap()
 }
 
-async fn insert_role(ctx: &Context, r: NewRole) -> QueryResult<Role> {
+async fn insert_training_drafts(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    drafts: Vec<TrainingDraft>,
+) -> QueryResult<Vec<Training>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        conn.transaction(|| {
+            let mut inserted = Vec::with_capacity(drafts.len());
+            for d in drafts {
+                let training: Training = diesel::insert_into(trainings::table)
+                    .values(&NewTraining {
+                        title: d.title,
+                        date: d.date,
+                        tier_id: d.tier_id,
+                        guild_id,
+                        max_participants: d.max_participants,
+                    })
+                    .get_result(&conn)?;
+
+                let roles = d
+                    .role_ids
+                    .into_iter()
+                    .map(|role_id| NewTrainingRole {
+                        training_id: training.id,
+                        role_id,
+                    })
+                    .collect::<Vec<_>>();
+                diesel::insert_into(training_roles::table)
+                    .values(&roles)
+                    .execute(&conn)?;
+
+                let bosses = d
+                    .boss_ids
+                    .into_iter()
+                    .map(|training_boss_id| TrainingBossMapping {
+                        training_id: training.id,
+                        training_boss_id,
+                    })
+                    .collect::<Vec<_>>();
+                diesel::insert_into(training_boss_mappings::table)
+                    .values(&bosses)
+                    .execute(&conn)?;
+
+                let requirements = d
+                    .requirements
+                    .into_iter()
+                    .map(|r| NewTrainingRequirement {
+                        training_id: training.id,
+                        role_id: r.role_id,
+                        category: r.category,
+                        min_count: r.min_count,
+                        max_count: r.max_count,
+                    })
+                    .collect::<Vec<_>>();
+                diesel::insert_into(training_requirements::table)
+                    .values(&requirements)
+                    .execute(&conn)?;
+
+                inserted.push(training);
+            }
+            Ok(inserted)
+        })
+    })
+    .await
+    .unwrap()
+}
+
+// Copies the training with its roles, bosses and requirements to the new date.
+// The given sign-ups are copied with their roles and comment
+async fn insert_training_clone(
+    ctx: &impl DBPoolProvider,
+    training_id: i32,
+    date: NaiveDateTime,
+    signup_ids: Vec<i32>,
+) -> QueryResult<Training> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        conn.transaction(|| {
+            let original: Training = trainings::table.find(training_id).first(&conn)?;
+            let training: Training = diesel::insert_into(trainings::table)
+                .values(&NewTraining {
+                    title: original.title,
+                    date,
+                    tier_id: original.tier_id,
+                    guild_id: original.guild_id,
+                    max_participants: original.max_participants,
+                })
+                .get_result(&conn)?;
+
+            let roles = training_roles::table
+                .filter(training_roles::training_id.eq(training_id))
+                .select(training_roles::role_id)
+                .load::<i32>(&conn)?
+                .into_iter()
+                .map(|role_id| NewTrainingRole {
+                    training_id: training.id,
+                    role_id,
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(training_roles::table)
+                .values(&roles)
+                .execute(&conn)?;
+
+            let bosses = training_boss_mappings::table
+                .filter(training_boss_mappings::training_id.eq(training_id))
+                .select(training_boss_mappings::training_boss_id)
+                .load::<i32>(&conn)?
+                .into_iter()
+                .map(|training_boss_id| TrainingBossMapping {
+                    training_id: training.id,
+                    training_boss_id,
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(training_boss_mappings::table)
+                .values(&bosses)
+                .execute(&conn)?;
+
+            let requirements = training_requirements::table
+                .filter(training_requirements::training_id.eq(training_id))
+                .load::<TrainingRequirement>(&conn)?
+                .into_iter()
+                .map(|r| NewTrainingRequirement {
+                    training_id: training.id,
+                    role_id: r.role_id,
+                    category: r.category,
+                    min_count: r.min_count,
+                    max_count: r.max_count,
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(training_requirements::table)
+                .values(&requirements)
+                .execute(&conn)?;
+
+            // Keeps the order they signed up in
+            let signups: Vec<Signup> = signups::table
+                .filter(signups::training_id.eq(training_id))
+                .filter(signups::id.eq_any(&signup_ids))
+                .order_by((signups::created_at, signups::id))
+                .load(&conn)?;
+            for s in signups {
+                let copy: Signup = diesel::insert_into(signups::table)
+                    .values(&NewSignup {
+                        user_id: s.user_id,
+                        training_id: training.id,
+                    })
+                    .get_result(&conn)?;
+                diesel::update(signups::table.find(copy.id))
+                    .set(signups::comment.eq(s.comment))
+                    .execute(&conn)?;
+                let roles = signup_roles::table
+                    .filter(signup_roles::signup_id.eq(s.id))
+                    .load::<SignupRole>(&conn)?
+                    .into_iter()
+                    .map(|r| NewSignupRole {
+                        signup_id: copy.id,
+                        role_id: r.role_id,
+                        rank: r.rank,
+                    })
+                    .collect::<Vec<_>>();
+                diesel::insert_into(signup_roles::table)
+                    .values(&roles)
+                    .execute(&conn)?;
+            }
+
+            Ok(training)
+        })
+    })
+    .await
+    .unwrap()
+}
+
+async fn apply_bot_configuration(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    conf: BotConfiguration,
+    replace: bool,
+) -> QueryResult<()> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        conn.transaction(|| {
+            // Roles are only deactivated since old signups still reference them
+            let current_roles: Vec<Role> = roles::table
+                .filter(roles::guild_id.eq(guild_id))
+                .filter(roles::active.eq(true))
+                .load(&conn)?;
+            for r in &conf.roles {
+                match current_roles.iter().find(|c| c.repr == r.repr) {
+                    Some(c) => {
+                        diesel::update(roles::table.find(c.id))
+                            .set((
+                                roles::title.eq(&r.title),
+                                roles::emoji.eq(&r.emoji),
+                                roles::priority.eq(r.priority),
+                                roles::category.eq(&r.category),
+                            ))
+                            .execute(&conn)?;
+                    }
+                    None => {
+                        diesel::insert_into(roles::table)
+                            .values(&NewRole {
+                                title: r.title.clone(),
+                                repr: r.repr.clone(),
+                                emoji: r.emoji.clone(),
+                                priority: Some(r.priority),
+                                guild_id,
+                                category: r.category.clone(),
+                            })
+                            .execute(&conn)?;
+                    }
+                }
+            }
+            if replace {
+                let keep = conf.roles.iter().map(|r| &r.repr).collect::<Vec<_>>();
+                diesel::update(
+                    roles::table
+                        .filter(roles::guild_id.eq(guild_id))
+                        .filter(roles::active.eq(true))
+                        .filter(roles::repr.ne_all(keep)),
+                )
+                .set(roles::active.eq(false))
+                .execute(&conn)?;
+            }
+
+            // Training bosses. Bosses still in use are kept, deleting them would
+            // empty boss pools and the completion history
+            let reprs = conf
+                .training_bosses
+                .iter()
+                .map(|b| &b.repr)
+                .collect::<Vec<_>>();
+            if replace {
+                let used = used_training_boss_ids(&conn, guild_id)?;
+                diesel::delete(
+                    training_bosses::table
+                        .filter(training_bosses::guild_id.eq(guild_id))
+                        .filter(training_bosses::repr.ne_all(&reprs))
+                        .filter(training_bosses::id.ne_all(used)),
+                )
+                .execute(&conn)?;
+            }
+            // Positions are unique per category. Moving the bosses of the file out
+            // of the way first lets them swap places
+            diesel::update(
+                training_bosses::table
+                    .filter(training_bosses::guild_id.eq(guild_id))
+                    .filter(training_bosses::repr.eq_any(&reprs)),
+            )
+            .set(training_bosses::position.eq(training_bosses::id * -1))
+            .execute(&conn)?;
+            for b in &conf.training_bosses {
+                // Old exports had strikes as wing 101 and 102
+                let (category, category_number) = match (b.category, b.category_number) {
+                    (BossCategory::Raid, n) if n > 100 => (BossCategory::Strike, n - 100),
+                    group => group,
+                };
+                let new_boss = NewTrainingBoss {
+                    repr: b.repr.clone(),
+                    name: b.name.clone(),
+                    category,
+                    category_number,
+                    position: b.position,
+                    emoji: b.emoji.clone(),
+                    url: b.url.clone(),
+                    guild_id,
+                };
+                diesel::insert_into(training_bosses::table)
+                    .values(&new_boss)
+                    .on_conflict((training_bosses::guild_id, training_bosses::repr))
+                    .do_update()
+                    .set((
+                        training_bosses::name.eq(&new_boss.name),
+                        training_bosses::category.eq(new_boss.category),
+                        training_bosses::category_number.eq(new_boss.category_number),
+                        training_bosses::position.eq(new_boss.position),
+                        training_bosses::emoji.eq(&new_boss.emoji),
+                        training_bosses::url.eq(&new_boss.url),
+                    ))
+                    .execute(&conn)?;
+            }
+
+            // Tiers and their discord roles
+            if replace {
+                // Same as deleting the tiers by hand, their trainings are opened
+                // to everyone
+                let names = conf.tiers.iter().map(|t| &t.name).collect::<Vec<_>>();
+                let removed: Vec<i32> = tiers::table
+                    .filter(tiers::guild_id.eq(guild_id))
+                    .filter(tiers::name.ne_all(names))
+                    .select(tiers::id)
+                    .load(&conn)?;
+                remove_tiers(&conn, &removed, None)?;
+            }
+            for t in &conf.tiers {
+                diesel::insert_into(tiers::table)
+                    .values(&NewTier {
+                        name: t.name.clone(),
+                        guild_id,
+                    })
+                    .on_conflict_do_nothing()
+                    .execute(&conn)?;
+            }
+
+            let all_tiers: Vec<Tier> = tiers::table
+                .filter(tiers::guild_id.eq(guild_id))
+                .load(&conn)?;
+            // Parents can only be set once every tier of the file exists
+            for t in &conf.tiers {
+                let tier = all_tiers
+                    .iter()
+                    .find(|a| a.name == t.name)
+                    .ok_or(diesel::NotFound)?;
+                let parent_id = match &t.parent {
+                    Some(parent) => Some(
+                        all_tiers
+                            .iter()
+                            .find(|a| a.name == *parent)
+                            .ok_or(diesel::NotFound)?
+                            .id,
+                    ),
+                    None => None,
+                };
+                diesel::update(tiers::table.find(tier.id))
+                    .set(tiers::parent_id.eq(parent_id))
+                    .execute(&conn)?;
+            }
+            let mut mappings: Vec<NewTierMapping> = Vec::with_capacity(conf.tier_mappings.len());
+            for m in &conf.tier_mappings {
+                let tier = all_tiers
+                    .iter()
+                    .find(|t| t.name == m.tier)
+                    .ok_or(diesel::NotFound)?;
+                mappings.push(NewTierMapping {
+                    tier_id: tier.id,
+                    discord_role_id: m.discord_role_id,
+                });
+            }
+            if replace {
+                let tier_ids = all_tiers.iter().map(|t| t.id).collect::<Vec<_>>();
+                diesel::delete(
+                    tier_mappings::table.filter(tier_mappings::tier_id.eq_any(tier_ids)),
+                )
+                .execute(&conn)?;
+            }
+            diesel::insert_into(tier_mappings::table)
+                .values(&mappings)
+                .on_conflict_do_nothing()
+                .execute(&conn)?;
+
+            // Config values are only ever added or updated. They can not
+            // be removed with a replace since the bot relies on them
+            for c in &conf.config {
+                diesel::insert_into(config::table)
+                    .values(&NewConfig {
+                        guild_id,
+                        name: &c.name,
+                        value: &c.value,
+                    })
+                    .on_conflict((config::guild_id, config::name))
+                    .do_update()
+                    .set(config::value.eq(&c.value))
+                    .execute(&conn)?;
+            }
+
+            Ok(())
+        })
+    })
+    .await
+    .unwrap()
+}
+
+async fn insert_role(ctx: &impl DBPoolProvider, r: NewRole) -> QueryResult<Role> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(roles::table)
@@ -86,7 +553,7 @@ async fn insert_role(ctx: &Context, r: NewRole) -> QueryResult<Role> {
     .unwrap()
 }
 
-async fn insert_tier(ctx: &Context, t: NewTier) -> QueryResult<Tier> {
+async fn insert_tier(ctx: &impl DBPoolProvider, t: NewTier) -> QueryResult<Tier> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(tiers::table)
@@ -97,7 +564,10 @@ async fn insert_tier(ctx: &Context, t: NewTier) -> QueryResult<Tier> {
     .unwrap()
 }
 
-async fn insert_training_role(ctx: &Context, tr: NewTrainingRole) -> QueryResult<TrainingRole> {
+async fn insert_training_role(
+    ctx: &impl DBPoolProvider,
+    tr: NewTrainingRole,
+) -> QueryResult<TrainingRole> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(training_roles::table)
@@ -109,7 +579,7 @@ async fn insert_training_role(ctx: &Context, tr: NewTrainingRole) -> QueryResult
 }
 
 async fn insert_training_boss_mapping(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     tbm: TrainingBossMapping,
 ) -> QueryResult<TrainingBossMapping> {
     let pool = DBPool::load(ctx).await;
@@ -122,7 +592,7 @@ async fn insert_training_boss_mapping(
     .unwrap()
 }
 
-async fn insert_signup(ctx: &Context, s: NewSignup) -> QueryResult<Signup> {
+async fn insert_signup(ctx: &impl DBPoolProvider, s: NewSignup) -> QueryResult<Signup> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(signups::table)
@@ -133,7 +603,10 @@ async fn insert_signup(ctx: &Context, s: NewSignup) -> QueryResult<Signup> {
     .unwrap()
 }
 
-async fn insert_signup_role(ctx: &Context, sr: NewSignupRole) -> QueryResult<SignupRole> {
+async fn insert_signup_role(
+    ctx: &impl DBPoolProvider,
+    sr: NewSignupRole,
+) -> QueryResult<SignupRole> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(signup_roles::table)
@@ -144,7 +617,10 @@ async fn insert_signup_role(ctx: &Context, sr: NewSignupRole) -> QueryResult<Sig
     .unwrap()
 }
 
-async fn insert_tier_mapping(ctx: &Context, tm: NewTierMapping) -> QueryResult<TierMapping> {
+async fn insert_tier_mapping(
+    ctx: &impl DBPoolProvider,
+    tm: NewTierMapping,
+) -> QueryResult<TierMapping> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(tier_mappings::table)
@@ -155,7 +631,89 @@ async fn insert_tier_mapping(ctx: &Context, tm: NewTierMapping) -> QueryResult<T
     .unwrap()
 }
 
-async fn insert_training_boss(ctx: &Context, tb: NewTrainingBoss) -> QueryResult<TrainingBoss> {
+async fn insert_promotion_rule(
+    ctx: &impl DBPoolProvider,
+    rule: NewPromotionRule,
+) -> QueryResult<PromotionRule> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::insert_into(promotion_rules::table)
+            .values(&rule)
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn insert_promotion(
+    ctx: &impl DBPoolProvider,
+    promotion: NewPromotion,
+) -> QueryResult<Promotion> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::insert_into(promotions::table)
+            .values(&promotion)
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn insert_tier_request(
+    ctx: &impl DBPoolProvider,
+    request: NewTierRequest,
+) -> QueryResult<TierRequest> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::insert_into(tier_requests::table)
+            .values(&request)
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+// Every attendee completed every boss of the training. Recording a training
+// again does not add anything
+async fn insert_boss_completions_by_training(
+    ctx: &impl DBPoolProvider,
+    training_id: i32,
+) -> QueryResult<usize> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+
+        let training: Training = trainings::table.find(training_id).first(&conn)?;
+        let signups: Vec<Signup> = signups::table
+            .filter(signups::training_id.eq(training_id))
+            .order_by((signups::created_at, signups::id))
+            .load(&conn)?;
+        let boss_ids: Vec<i32> = training_boss_mappings::table
+            .filter(training_boss_mappings::training_id.eq(training_id))
+            .select(training_boss_mappings::training_boss_id)
+            .load(&conn)?;
+
+        let completions = attendees(&training, &signups)
+            .cartesian_product(&boss_ids)
+            .map(|(s, b)| NewBossCompletion {
+                user_id: s.user_id,
+                training_boss_id: *b,
+                training_id,
+            })
+            .collect::<Vec<_>>();
+        diesel::insert_into(boss_completions::table)
+            .values(&completions)
+            .on_conflict_do_nothing()
+            .execute(&conn)
+    })
+    .await
+    .unwrap()
+}
+
+async fn insert_training_boss(
+    ctx: &impl DBPoolProvider,
+    tb: NewTrainingBoss,
+) -> QueryResult<TrainingBoss> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(training_bosses::table)
@@ -166,29 +724,68 @@ async fn insert_training_boss(ctx: &Context, tb: NewTrainingBoss) -> QueryResult
     .unwrap()
 }
 
-async fn upsert_config(ctx: &Context, conf: Config) -> QueryResult<Config> {
+async fn upsert_config(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    conf: Config,
+) -> QueryResult<Config> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::insert_into(config::table)
-            .values(&conf)
-            .on_conflict(config::name)
+            .values(&NewConfig {
+                guild_id,
+                name: &conf.name,
+                value: &conf.value,
+            })
+            .on_conflict((config::guild_id, config::name))
             .do_update()
             .set(config::value.eq(&conf.value))
+            .returning((config::name, config::value))
             .get_result(&pool.conn())
     })
     .await
     .unwrap()
 }
 
+// Replaces the default roles of the user in the guild
+async fn replace_user_default_roles(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+    guild_id: i64,
+    defaults: Vec<UserDefaultRole>,
+) -> QueryResult<()> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        conn.transaction(|| {
+            let guild_roles = roles::table
+                .filter(roles::guild_id.eq(guild_id))
+                .select(roles::id);
+            diesel::delete(
+                user_default_roles::table
+                    .filter(user_default_roles::user_id.eq(user_id))
+                    .filter(user_default_roles::role_id.eq_any(guild_roles)),
+            )
+            .execute(&conn)?;
+            diesel::insert_into(user_default_roles::table)
+                .values(&defaults)
+                .execute(&conn)?;
+            Ok(())
+        })
+    })
+    .await
+    .unwrap()
+}
+
 // Delete
-async fn delete_user_by_id(ctx: &Context, id: i32) -> QueryResult<usize> {
+async fn delete_user_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<usize> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || diesel::delete(users::table.find(id)).execute(&pool.conn()))
         .await
         .unwrap()
 }
 
-async fn delete_signup_roles_by_signup(ctx: &Context, id: i32) -> QueryResult<usize> {
+async fn delete_signup_roles_by_signup(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<usize> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::delete(signup_roles::table.filter(signup_roles::signup_id.eq(id)))
@@ -198,22 +795,78 @@ async fn delete_signup_roles_by_signup(ctx: &Context, id: i32) -> QueryResult<us
     .unwrap()
 }
 
-async fn delete_signup_by_id(ctx: &Context, id: i32) -> QueryResult<usize> {
+async fn delete_signups_by_user_and_guild(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+    guild_id: i64,
+) -> QueryResult<usize> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let guild_trainings = trainings::table
+            .filter(trainings::guild_id.eq(guild_id))
+            .select(trainings::id);
+        diesel::delete(
+            signups::table
+                .filter(signups::user_id.eq(user_id))
+                .filter(signups::training_id.eq_any(guild_trainings)),
+        )
+        .execute(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn delete_signup_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<usize> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || diesel::delete(signups::table.find(id)).execute(&pool.conn()))
         .await
         .unwrap()
 }
 
-async fn delete_tier_by_id(ctx: &Context, id: i32) -> QueryResult<usize> {
+// Trainings and discord roles reference the tier, so they are moved
+// and removed first
+fn remove_tiers(conn: &PgConnection, ids: &[i32], reassign_to: Option<i32>) -> QueryResult<usize> {
+    // Tiers below a removed one move up to the closest remaining tier above it,
+    // so members of the higher tiers still qualify for them
+    let removed: Vec<Tier> = tiers::table.filter(tiers::id.eq_any(ids)).load(conn)?;
+    for tier in &removed {
+        let new_parent = tier
+            .with_parents(&removed)
+            .last()
+            .and_then(|t| t.parent_id)
+            .filter(|id| !ids.contains(id));
+        diesel::update(
+            tiers::table
+                .filter(tiers::parent_id.eq(tier.id))
+                .filter(tiers::id.ne_all(ids)),
+        )
+        .set(tiers::parent_id.eq(new_parent))
+        .execute(conn)?;
+    }
+    diesel::update(trainings::table.filter(trainings::tier_id.eq_any(ids)))
+        .set(trainings::tier_id.eq(reassign_to))
+        .execute(conn)?;
+    diesel::delete(tier_mappings::table.filter(tier_mappings::tier_id.eq_any(ids)))
+        .execute(conn)?;
+    diesel::delete(tiers::table.filter(tiers::id.eq_any(ids))).execute(conn)
+}
+
+async fn delete_tier_by_id(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    reassign_to: Option<i32>,
+) -> QueryResult<usize> {
     let pool = DBPool::load(ctx).await;
-    task::spawn_blocking(move || diesel::delete(tiers::table.find(id)).execute(&pool.conn()))
-        .await
-        .unwrap()
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        conn.transaction(|| remove_tiers(&conn, &[id], reassign_to))
+    })
+    .await
+    .unwrap()
 }
 
 async fn delete_tier_mapping(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     tier_id: i32,
     discord_role_id: i64,
 ) -> QueryResult<usize> {
@@ -225,7 +878,23 @@ async fn delete_tier_mapping(
     .unwrap()
 }
 
-async fn delete_training_boss_by_id(ctx: &Context, id: i32) -> QueryResult<usize> {
+async fn delete_promotion_rule_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<usize> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::delete(promotion_rules::table.find(id)).execute(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn delete_promotion_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<usize> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || diesel::delete(promotions::table.find(id)).execute(&pool.conn()))
+        .await
+        .unwrap()
+}
+
+async fn delete_training_boss_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<usize> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::delete(training_bosses::table.find(id)).execute(&pool.conn())
@@ -235,14 +904,24 @@ async fn delete_training_boss_by_id(ctx: &Context, id: i32) -> QueryResult<usize
 }
 
 // Select
-async fn select_user_by_id(ctx: &Context, id: i32) -> QueryResult<User> {
+async fn select_guild_by_id(ctx: &impl DBPoolProvider, id: i64) -> QueryResult<Guild> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || guilds::table.find(id).first(&pool.conn()))
+        .await
+        .unwrap()
+}
+
+async fn select_user_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<User> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || users::table.find(id).first(&pool.conn()))
         .await
         .unwrap()
 }
 
-async fn select_user_by_discord_id(ctx: &Context, discord_id: u64) -> QueryResult<User> {
+async fn select_user_by_discord_id(
+    ctx: &impl DBPoolProvider,
+    discord_id: u64,
+) -> QueryResult<User> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         users::table
@@ -254,13 +933,15 @@ async fn select_user_by_discord_id(ctx: &Context, discord_id: u64) -> QueryResul
 }
 
 async fn select_users_with_signup_by_date(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
     date: NaiveDate,
 ) -> QueryResult<Vec<User>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = users::table.inner_join(signups::table.inner_join(trainings::table));
-        join.filter(trainings::date.ge(date.and_hms(0, 0, 0)))
+        join.filter(trainings::guild_id.eq(guild_id))
+            .filter(trainings::date.ge(date.and_hms(0, 0, 0)))
             .filter(trainings::date.le(date.and_hms(23, 59, 59)))
             .select(users::all_columns)
             .load(&pool.conn())
@@ -269,7 +950,10 @@ async fn select_users_with_signup_by_date(
     .unwrap()
 }
 
-async fn select_all_signups_by_user(ctx: &Context, user_id: i32) -> QueryResult<Vec<Signup>> {
+async fn select_all_signups_by_user(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+) -> QueryResult<Vec<Signup>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = signups::table
@@ -284,7 +968,7 @@ async fn select_all_signups_by_user(ctx: &Context, user_id: i32) -> QueryResult<
 }
 
 async fn select_joined_active_trainings_by_user(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     user_id: i32,
 ) -> QueryResult<Vec<Training>> {
     let pool = DBPool::load(ctx).await;
@@ -307,7 +991,7 @@ async fn select_joined_active_trainings_by_user(
 }
 
 async fn select_active_signups_trainings_by_user(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     user_id: i32,
 ) -> QueryResult<Vec<(Signup, Training)>> {
     let pool = DBPool::load(ctx).await;
@@ -329,7 +1013,10 @@ async fn select_active_signups_trainings_by_user(
     .unwrap()
 }
 
-async fn select_active_signups_by_user(ctx: &Context, user_id: i32) -> QueryResult<Vec<Signup>> {
+async fn select_active_signups_by_user(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+) -> QueryResult<Vec<Signup>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = signups::table
@@ -349,7 +1036,10 @@ async fn select_active_signups_by_user(ctx: &Context, user_id: i32) -> QueryResu
     .unwrap()
 }
 
-async fn select_open_signups_by_user(ctx: &Context, user_id: i32) -> QueryResult<Vec<Signup>> {
+async fn select_open_signups_by_user(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+) -> QueryResult<Vec<Signup>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = signups::table
@@ -364,15 +1054,31 @@ async fn select_open_signups_by_user(ctx: &Context, user_id: i32) -> QueryResult
     .unwrap()
 }
 
-async fn select_training_by_id(ctx: &Context, id: i32) -> QueryResult<Training> {
+async fn select_training_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<Training> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || trainings::table.find(id).first(&pool.conn()))
         .await
         .unwrap()
 }
 
+async fn select_training_by_guild_and_id(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    id: i32,
+) -> QueryResult<Training> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        trainings::table
+            .find(id)
+            .filter(trainings::guild_id.eq(guild_id))
+            .first(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
 async fn select_trainings_by_state(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     state: TrainingState,
 ) -> QueryResult<Vec<Training>> {
     let pool = DBPool::load(ctx).await;
@@ -386,7 +1092,7 @@ async fn select_trainings_by_state(
 }
 
 async fn select_training_by_id_and_state(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     id: i32,
     state: TrainingState,
 ) -> QueryResult<Training> {
@@ -401,10 +1107,14 @@ async fn select_training_by_id_and_state(
     .unwrap()
 }
 
-async fn select_active_trainings(ctx: &Context) -> QueryResult<Vec<Training>> {
+async fn select_active_trainings(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+) -> QueryResult<Vec<Training>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         trainings::table
+            .filter(trainings::guild_id.eq(guild_id))
             .filter(
                 trainings::state
                     .eq(TrainingState::Open)
@@ -417,7 +1127,10 @@ async fn select_active_trainings(ctx: &Context) -> QueryResult<Vec<Training>> {
     .unwrap()
 }
 
-async fn select_trainings_by_tier(ctx: &Context, id: i32) -> QueryResult<Vec<Training>> {
+async fn select_trainings_by_tier(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+) -> QueryResult<Vec<Training>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = trainings::table.inner_join(tiers::table);
@@ -429,10 +1142,15 @@ async fn select_trainings_by_tier(ctx: &Context, id: i32) -> QueryResult<Vec<Tra
     .unwrap()
 }
 
-async fn select_trainings_by_date(ctx: &Context, date: NaiveDate) -> QueryResult<Vec<Training>> {
+async fn select_trainings_by_date(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    date: NaiveDate,
+) -> QueryResult<Vec<Training>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         trainings::table
+            .filter(trainings::guild_id.eq(guild_id))
             .filter(trainings::date.ge(date.and_hms(0, 0, 0)))
             .filter(trainings::date.le(date.and_hms(23, 59, 59)))
             .select(trainings::all_columns)
@@ -442,23 +1160,58 @@ async fn select_trainings_by_date(ctx: &Context, date: NaiveDate) -> QueryResult
     .unwrap()
 }
 
-async fn select_signups_by_training(ctx: &Context, id: i32) -> QueryResult<Vec<Signup>> {
+// In the order they signed up
+async fn select_signups_by_training(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+) -> QueryResult<Vec<Signup>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = signups::table.inner_join(trainings::table);
         join.filter(trainings::id.eq(id))
             .select(signups::all_columns)
+            .order_by((signups::created_at, signups::id))
             .load(&pool.conn())
     })
     .await
     .unwrap()
 }
 
-async fn select_signups_by_date(ctx: &Context, date: NaiveDate) -> QueryResult<Vec<Signup>> {
+// Sign-ups past the limit of the training, in the order they signed up
+async fn select_waitlist_by_training(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+) -> QueryResult<Vec<Signup>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        let max: Option<i32> = trainings::table
+            .find(id)
+            .select(trainings::max_participants)
+            .get_result(&conn)?;
+        match max {
+            None => Ok(Vec::new()),
+            Some(max) => signups::table
+                .filter(signups::training_id.eq(id))
+                .order_by((signups::created_at, signups::id))
+                .offset(max as i64)
+                .load(&conn),
+        }
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_signups_by_date(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    date: NaiveDate,
+) -> QueryResult<Vec<Signup>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = signups::table.inner_join(trainings::table);
-        join.filter(trainings::date.ge(date.and_hms(0, 0, 0)))
+        join.filter(trainings::guild_id.eq(guild_id))
+            .filter(trainings::date.ge(date.and_hms(0, 0, 0)))
             .filter(trainings::date.le(date.and_hms(23, 59, 59)))
             .select(signups::all_columns)
             .load(&pool.conn())
@@ -468,7 +1221,7 @@ async fn select_signups_by_date(ctx: &Context, date: NaiveDate) -> QueryResult<V
 }
 
 async fn select_signup_by_user_and_training(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     user_id: i32,
     training_id: i32,
 ) -> QueryResult<Signup> {
@@ -484,7 +1237,7 @@ async fn select_signup_by_user_and_training(
 }
 
 async fn select_signup_by_discord_user_and_training(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     discord_id: i64,
     training_id: i32,
 ) -> QueryResult<Signup> {
@@ -500,24 +1253,33 @@ async fn select_signup_by_discord_user_and_training(
     .unwrap()
 }
 
-async fn select_all_tiers(ctx: &Context) -> QueryResult<Vec<Tier>> {
+async fn select_all_tiers(ctx: &impl DBPoolProvider, guild_id: i64) -> QueryResult<Vec<Tier>> {
     let pool = DBPool::load(ctx).await;
-    task::spawn_blocking(move || tiers::table.load(&pool.conn()))
-        .await
-        .unwrap()
+    task::spawn_blocking(move || {
+        tiers::table
+            .filter(tiers::guild_id.eq(guild_id))
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
 }
 
-async fn select_tier_by_id(ctx: &Context, id: i32) -> QueryResult<Tier> {
+async fn select_tier_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<Tier> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || tiers::table.find(id).first(&pool.conn()))
         .await
         .unwrap()
 }
 
-async fn select_tier_by_name(ctx: &Context, name: String) -> QueryResult<Tier> {
+async fn select_tier_by_name(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    name: String,
+) -> QueryResult<Tier> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         tiers::table
+            .filter(tiers::guild_id.eq(guild_id))
             .filter(tiers::name.eq(name))
             .first(&pool.conn())
     })
@@ -525,7 +1287,10 @@ async fn select_tier_by_name(ctx: &Context, name: String) -> QueryResult<Tier> {
     .unwrap()
 }
 
-async fn select_tier_mappings_by_tier(ctx: &Context, id: i32) -> QueryResult<Vec<TierMapping>> {
+async fn select_tier_mappings_by_tier(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+) -> QueryResult<Vec<TierMapping>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = tier_mappings::table.inner_join(tiers::table);
@@ -537,8 +1302,23 @@ async fn select_tier_mappings_by_tier(ctx: &Context, id: i32) -> QueryResult<Vec
     .unwrap()
 }
 
+async fn select_all_tier_mappings_with_tier(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+) -> QueryResult<Vec<(TierMapping, Tier)>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        tier_mappings::table
+            .inner_join(tiers::table)
+            .filter(tiers::guild_id.eq(guild_id))
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
 async fn select_tier_mappings_by_tier_and_discord_role(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     tier_id: i32,
     discord_id: i64,
 ) -> QueryResult<TierMapping> {
@@ -555,7 +1335,7 @@ async fn select_tier_mappings_by_tier_and_discord_role(
 }
 
 async fn select_training_roles_by_training(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     id: i32,
 ) -> QueryResult<Vec<TrainingRole>> {
     let pool = DBPool::load(ctx).await;
@@ -569,10 +1349,15 @@ async fn select_training_roles_by_training(
     .unwrap()
 }
 
-async fn select_roles_by_active(ctx: &Context, active: bool) -> QueryResult<Vec<Role>> {
+async fn select_roles_by_active(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    active: bool,
+) -> QueryResult<Vec<Role>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         roles::table
+            .filter(roles::guild_id.eq(guild_id))
             .filter(roles::active.eq(active))
             .order_by(roles::priority.desc())
             .then_order_by(roles::title)
@@ -582,22 +1367,32 @@ async fn select_roles_by_active(ctx: &Context, active: bool) -> QueryResult<Vec<
     .unwrap()
 }
 
-async fn select_active_role_by_emoji(ctx: &Context, emoji_id: i64) -> QueryResult<Role> {
+async fn select_active_role_by_emoji(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    emoji: Emoji,
+) -> QueryResult<Role> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         roles::table
+            .filter(roles::guild_id.eq(guild_id))
             .filter(roles::active.eq(true))
-            .filter(roles::emoji.eq(emoji_id))
+            .filter(roles::emoji.eq(emoji))
             .first(&pool.conn())
     })
     .await
     .unwrap()
 }
 
-async fn select_active_role_by_repr(ctx: &Context, repr: String) -> QueryResult<Role> {
+async fn select_active_role_by_repr(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    repr: String,
+) -> QueryResult<Role> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         roles::table
+            .filter(roles::guild_id.eq(guild_id))
             .filter(roles::active.eq(true))
             .filter(roles::repr.eq(repr))
             .first(&pool.conn())
@@ -606,7 +1401,40 @@ async fn select_active_role_by_repr(ctx: &Context, repr: String) -> QueryResult<
     .unwrap()
 }
 
-async fn select_roles_by_training(ctx: &Context, id: i32) -> QueryResult<Vec<Role>> {
+// The active role or else the one deactivated last
+async fn select_latest_role_by_repr(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    repr: String,
+) -> QueryResult<Role> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        roles::table
+            .filter(roles::guild_id.eq(guild_id))
+            .filter(roles::repr.eq(repr))
+            .order((roles::active.desc(), roles::id.desc()))
+            .first(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_requirements_by_training(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+) -> QueryResult<Vec<TrainingRequirement>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        training_requirements::table
+            .filter(training_requirements::training_id.eq(id))
+            .order_by(training_requirements::id)
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_roles_by_training(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<Vec<Role>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = training_roles::table
@@ -622,7 +1450,10 @@ async fn select_roles_by_training(ctx: &Context, id: i32) -> QueryResult<Vec<Rol
     .unwrap()
 }
 
-async fn select_active_roles_by_training(ctx: &Context, id: i32) -> QueryResult<Vec<Role>> {
+async fn select_active_roles_by_training(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+) -> QueryResult<Vec<Role>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = training_roles::table
@@ -639,7 +1470,7 @@ async fn select_active_roles_by_training(ctx: &Context, id: i32) -> QueryResult<
     .unwrap()
 }
 
-async fn select_roles_by_signup(ctx: &Context, id: i32) -> QueryResult<Vec<Role>> {
+async fn select_roles_by_signup(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<Vec<Role>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         let join = signup_roles::table
@@ -647,7 +1478,8 @@ async fn select_roles_by_signup(ctx: &Context, id: i32) -> QueryResult<Vec<Role>
             .inner_join(roles::table);
         join.filter(signups::id.eq(id))
             .select(roles::all_columns)
-            .order_by(roles::priority.desc())
+            .order_by(signup_roles::rank)
+            .then_order_by(roles::priority.desc())
             .then_order_by(roles::title)
             .load(&pool.conn())
     })
@@ -655,77 +1487,664 @@ async fn select_roles_by_signup(ctx: &Context, id: i32) -> QueryResult<Vec<Role>
     .unwrap()
 }
 
-async fn select_config_by_name(ctx: &Context, name: String) -> QueryResult<Config> {
+// Only active roles, ordered by rank
+async fn select_default_roles_by_user(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+    guild_id: i64,
+) -> QueryResult<Vec<Role>> {
     let pool = DBPool::load(ctx).await;
-    task::spawn_blocking(move || config::table.find(name).first(&pool.conn()))
-        .await
-        .unwrap()
+    task::spawn_blocking(move || {
+        user_default_roles::table
+            .inner_join(roles::table)
+            .filter(user_default_roles::user_id.eq(user_id))
+            .filter(roles::guild_id.eq(guild_id))
+            .filter(roles::active.eq(true))
+            .select(roles::all_columns)
+            .order_by(user_default_roles::rank)
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
 }
 
-async fn select_all_training_bosses(ctx: &Context) -> QueryResult<Vec<TrainingBoss>> {
+async fn select_user_defaults(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+    guild_id: i64,
+) -> QueryResult<Option<UserDefaults>> {
     let pool = DBPool::load(ctx).await;
-    task::spawn_blocking(move || training_bosses::table.load(&pool.conn()))
-        .await
-        .unwrap()
+    task::spawn_blocking(move || {
+        user_defaults::table
+            .find((user_id, guild_id))
+            .first(&pool.conn())
+            .optional()
+    })
+    .await
+    .unwrap()
 }
 
-async fn select_training_boss_by_repr(ctx: &Context, repr: String) -> QueryResult<TrainingBoss> {
+async fn select_config_by_name(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    name: String,
+) -> QueryResult<Config> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
-        training_bosses::table
-            .filter(training_bosses::repr.eq(repr))
+        config::table
+            .find((guild_id, name))
+            .select((config::name, config::value))
             .first(&pool.conn())
     })
     .await
     .unwrap()
 }
 
-async fn select_training_bosses_by_training(
-    ctx: &Context,
-    id: i32,
-) -> QueryResult<Vec<TrainingBoss>> {
+async fn select_all_config(ctx: &impl DBPoolProvider, guild_id: i64) -> QueryResult<Vec<Config>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
-        training_boss_mappings::table
-            .inner_join(trainings::table)
-            .inner_join(training_bosses::table)
-            .filter(trainings::id.eq(id))
-            .select(training_bosses::all_columns)
+        config::table
+            .filter(config::guild_id.eq(guild_id))
+            .select((config::name, config::value))
+            .order_by(config::name)
             .load(&pool.conn())
     })
     .await
     .unwrap()
 }
 
-// Count
-async fn count_trainings_by_state(ctx: &Context, state: TrainingState) -> QueryResult<i64> {
+async fn select_all_training_bosses(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+) -> QueryResult<Vec<TrainingBoss>> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
-        trainings::table
-            .filter(trainings::state.eq(state))
-            .count()
-            .get_result(&pool.conn())
+        training_bosses::table
+            .filter(training_bosses::guild_id.eq(guild_id))
+            .load(&pool.conn())
     })
     .await
     .unwrap()
 }
 
-async fn count_signups_by_training(ctx: &Context, training_id: i32) -> QueryResult<i64> {
+async fn select_training_boss_by_repr(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    repr: String,
+) -> QueryResult<TrainingBoss> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
-        signups::table
-            .filter(signups::training_id.eq(training_id))
-            .count()
+        training_bosses::table
+            .filter(training_bosses::guild_id.eq(guild_id))
+            .filter(training_bosses::repr.eq(repr))
+            .first(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_training_bosses_by_training(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+) -> QueryResult<Vec<TrainingBoss>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        training_boss_mappings::table
+            .inner_join(trainings::table)
+            .inner_join(training_bosses::table)
+            .filter(trainings::id.eq(id))
+            .select(training_bosses::all_columns)
+            // Same as TrainingBoss::sort_key, the embeds group by category
+            .order_by((
+                training_bosses::category,
+                training_bosses::category_number,
+                training_bosses::position,
+            ))
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_active_trainings_with_tier(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+) -> QueryResult<Vec<TrainingWithTier>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+
+        let trainings: Vec<(Training, Option<Tier>)> = trainings::table
+            .left_join(tiers::table)
+            .filter(trainings::guild_id.eq(guild_id))
+            .filter(
+                trainings::state
+                    .eq(TrainingState::Open)
+                    .or(trainings::state.eq(TrainingState::Closed))
+                    .or(trainings::state.eq(TrainingState::Started)),
+            )
+            .load(&conn)?;
+
+        // All tiers since a training also accepts the roles of the tiers above its own
+        let tiers: Vec<Tier> = tiers::table
+            .filter(tiers::guild_id.eq(guild_id))
+            .load(&conn)?;
+        let tier_ids: Vec<i32> = tiers.iter().map(|t| t.id).collect();
+        let mappings: Vec<TierMapping> = tier_mappings::table
+            .filter(tier_mappings::tier_id.eq_any(&tier_ids))
+            .load(&conn)?;
+
+        Ok(trainings
+            .into_iter()
+            .map(|(training, tier)| TrainingWithTier {
+                tier_mappings: tier
+                    .as_ref()
+                    .map(|t| t.inherited_mappings(&tiers, &mappings))
+                    .unwrap_or_default(),
+                training,
+                tier,
+            })
+            .collect())
+    })
+    .await
+    .unwrap()
+}
+
+// Loads everything the signup board needs with a fixed amount of queries,
+// independent of the number of active trainings
+async fn select_signup_board_overview(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+) -> QueryResult<SignupBoardOverview> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+
+        let trainings: Vec<(Training, Option<Tier>)> = trainings::table
+            .left_join(tiers::table)
+            .filter(trainings::guild_id.eq(guild_id))
+            .filter(
+                trainings::state
+                    .eq(TrainingState::Open)
+                    .or(trainings::state.eq(TrainingState::Closed))
+                    .or(trainings::state.eq(TrainingState::Started)),
+            )
+            .load(&conn)?;
+
+        if trainings.is_empty() {
+            return Ok(SignupBoardOverview::default());
+        }
+
+        let training_ids: Vec<i32> = trainings.iter().map(|(t, _)| t.id).collect();
+        let tiers: Vec<Tier> = tiers::table
+            .filter(tiers::guild_id.eq(guild_id))
+            .load(&conn)?;
+        let tier_ids: Vec<i32> = tiers.iter().map(|t| t.id).collect();
+
+        let signup_counts: HashMap<i32, i64> = signups::table
+            .filter(signups::training_id.eq_any(&training_ids))
+            .group_by(signups::training_id)
+            .select((signups::training_id, sql::<BigInt>("count(*)")))
+            .load::<(i32, i64)>(&conn)?
+            .into_iter()
+            .collect();
+
+        let mappings: Vec<TierMapping> = tier_mappings::table
+            .filter(tier_mappings::tier_id.eq_any(&tier_ids))
+            .load(&conn)?;
+
+        let bosses: Vec<(i32, TrainingBoss)> = training_boss_mappings::table
+            .inner_join(training_bosses::table)
+            .filter(training_boss_mappings::training_id.eq_any(&training_ids))
+            .select((
+                training_boss_mappings::training_id,
+                training_bosses::all_columns,
+            ))
+            .order((
+                training_bosses::category,
+                training_bosses::category_number,
+                training_bosses::position,
+            ))
+            .load(&conn)?;
+
+        let requirements: Vec<TrainingRequirement> = training_requirements::table
+            .filter(training_requirements::training_id.eq_any(&training_ids))
+            .order_by(training_requirements::id)
+            .load(&conn)?;
+
+        // Only needed to fill the composition
+        let (roles, signup_roles) = if requirements.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            let roles: Vec<(i32, Role)> = training_roles::table
+                .inner_join(roles::table)
+                .filter(training_roles::training_id.eq_any(&training_ids))
+                .select((training_roles::training_id, roles::all_columns))
+                .load(&conn)?;
+            // Sign-ups without roles are kept, they still take a spot before the waitlist
+            let signup_roles: Vec<(i32, i32, Option<i32>)> = signups::table
+                .left_join(signup_roles::table)
+                .filter(signups::training_id.eq_any(&training_ids))
+                .select((
+                    signups::training_id,
+                    signups::id,
+                    signup_roles::role_id.nullable(),
+                ))
+                .order_by((
+                    signups::training_id,
+                    signups::created_at,
+                    signups::id,
+                    signup_roles::rank,
+                ))
+                .load(&conn)?;
+            (roles, signup_roles)
+        };
+
+        // Counted over all trainings of the day, not only the active ones
+        let first_day = trainings.iter().map(|(t, _)| t.date.date()).min().unwrap();
+        let last_day = trainings.iter().map(|(t, _)| t.date.date()).max().unwrap();
+        let users_per_day: HashMap<NaiveDate, i64> = signups::table
+            .inner_join(trainings::table)
+            .filter(trainings::guild_id.eq(guild_id))
+            .filter(trainings::date.ge(first_day.and_hms(0, 0, 0)))
+            .filter(trainings::date.le(last_day.and_hms(23, 59, 59)))
+            .group_by(sql::<Date>("trainings.date::date"))
+            .select((
+                sql::<Date>("trainings.date::date"),
+                sql::<BigInt>("count(distinct signups.user_id)"),
+            ))
+            .load::<(NaiveDate, i64)>(&conn)?
+            .into_iter()
+            .collect();
+
+        let trainings = trainings
+            .into_iter()
+            .map(|(training, tier)| TrainingOverview {
+                signup_count: signup_counts.get(&training.id).copied().unwrap_or(0),
+                composition: {
+                    let requirements = requirements
+                        .iter()
+                        .filter(|r| r.training_id == training.id)
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    let roles = roles
+                        .iter()
+                        .filter(|(id, _)| *id == training.id)
+                        .map(|(_, r)| r.clone())
+                        .collect::<Vec<_>>();
+                    let signups = signup_roles
+                        .iter()
+                        .filter(|(id, _, _)| *id == training.id)
+                        .group_by(|(_, signup_id, _)| *signup_id)
+                        .into_iter()
+                        .map(|(_, s)| s.filter_map(|(_, _, role_id)| *role_id).collect())
+                        .take(training.max_participants.map_or(usize::MAX, |m| m as usize))
+                        .collect::<Vec<_>>();
+                    RequirementStatus::compute(&requirements, &roles, &signups)
+                },
+                tier_mappings: tier
+                    .as_ref()
+                    .map(|t| t.inherited_mappings(&tiers, &mappings))
+                    .unwrap_or_default(),
+                bosses: bosses
+                    .iter()
+                    .filter(|(id, _)| *id == training.id)
+                    .map(|(_, b)| b.clone())
+                    .collect(),
+                training,
+                tier,
+            })
+            .collect();
+
+        Ok(SignupBoardOverview {
+            trainings,
+            users_per_day,
+        })
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_promotion_rules_by_guild(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+) -> QueryResult<Vec<PromotionRule>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        promotion_rules::table
+            .inner_join(tiers::table.on(tiers::id.eq(promotion_rules::tier_id)))
+            .filter(tiers::guild_id.eq(guild_id))
+            .select(promotion_rules::all_columns)
+            .order_by(promotion_rules::id)
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_promotion_rule_by_guild_and_id(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    id: i32,
+) -> QueryResult<PromotionRule> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        promotion_rules::table
+            .inner_join(tiers::table.on(tiers::id.eq(promotion_rules::tier_id)))
+            .filter(tiers::guild_id.eq(guild_id))
+            .filter(promotion_rules::id.eq(id))
+            .select(promotion_rules::all_columns)
+            .first(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_promotion_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<Promotion> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || promotions::table.find(id).first(&pool.conn()))
+        .await
+        .unwrap()
+}
+
+async fn select_promotions_by_rule(
+    ctx: &impl DBPoolProvider,
+    rule_id: i32,
+) -> QueryResult<Vec<Promotion>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        promotions::table
+            .filter(promotions::rule_id.eq(rule_id))
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_tier_request_by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<TierRequest> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || tier_requests::table.find(id).first(&pool.conn()))
+        .await
+        .unwrap()
+}
+
+async fn select_tier_requests_by_user_and_guild(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+    guild_id: i64,
+) -> QueryResult<Vec<(TierRequest, Tier)>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        tier_requests::table
+            .inner_join(tiers::table)
+            .filter(tier_requests::user_id.eq(user_id))
+            .filter(tiers::guild_id.eq(guild_id))
+            .order_by(tier_requests::created_at.desc())
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_pending_tier_requests_by_tier(
+    ctx: &impl DBPoolProvider,
+    tier_id: i32,
+) -> QueryResult<Vec<(TierRequest, User)>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        tier_requests::table
+            .inner_join(users::table)
+            .filter(tier_requests::tier_id.eq(tier_id))
+            .filter(tier_requests::state.eq(PromotionState::Pending))
+            .order_by(tier_requests::created_at)
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+// The sign-ups of the training that are not waitlisted. The sign-ups have to
+// be ordered by (created_at, id)
+fn attendees<'a>(
+    training: &'a Training,
+    signups: &'a [Signup],
+) -> impl Iterator<Item = &'a Signup> + 'a {
+    let max = training
+        .max_participants
+        .map(|m| m as usize)
+        .unwrap_or(usize::MAX);
+    signups
+        .iter()
+        .filter(move |s| s.training_id == training.id)
+        .take(max)
+}
+
+// Bosses of the guild that trainings, boss preferences or the completion
+// history refer to. Deleting them would cascade into those
+fn used_training_boss_ids(conn: &PgConnection, guild_id: i64) -> QueryResult<Vec<i32>> {
+    let guild_bosses = training_bosses::table
+        .filter(training_bosses::guild_id.eq(guild_id))
+        .select(training_bosses::id);
+    let mut ids: Vec<i32> = training_boss_mappings::table
+        .filter(training_boss_mappings::training_boss_id.eq_any(guild_bosses))
+        .select(training_boss_mappings::training_boss_id)
+        .load(conn)?;
+    ids.extend(
+        signup_boss_preference_mappings::table
+            .filter(signup_boss_preference_mappings::training_boss_id.eq_any(guild_bosses))
+            .select(signup_boss_preference_mappings::training_boss_id)
+            .load::<i32>(conn)?,
+    );
+    ids.extend(
+        boss_completions::table
+            .filter(boss_completions::training_boss_id.eq_any(guild_bosses))
+            .select(boss_completions::training_boss_id)
+            .load::<i32>(conn)?,
+    );
+    ids.sort_unstable();
+    ids.dedup();
+    Ok(ids)
+}
+
+async fn select_used_training_bosses_by_guild(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+) -> QueryResult<Vec<TrainingBoss>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        let ids = used_training_boss_ids(&conn, guild_id)?;
+        training_bosses::table
+            .filter(training_bosses::id.eq_any(ids))
+            .load(&conn)
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_boss_progress_by_user_and_guild(
+    ctx: &impl DBPoolProvider,
+    user_id: i32,
+    guild_id: i64,
+) -> QueryResult<Vec<(TrainingBoss, i64)>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let completed: Vec<(i32, i64)> = boss_completions::table
+            .inner_join(training_bosses::table)
+            .filter(boss_completions::user_id.eq(user_id))
+            .filter(training_bosses::guild_id.eq(guild_id))
+            .group_by(boss_completions::training_boss_id)
+            .select((
+                boss_completions::training_boss_id,
+                sql::<BigInt>("count(*)"),
+            ))
+            .load(&pool.conn())?;
+        let bosses: Vec<TrainingBoss> = training_bosses::table
+            .filter(training_bosses::id.eq_any(completed.iter().map(|(id, _)| *id)))
+            .order((
+                training_bosses::category,
+                training_bosses::category_number,
+                training_bosses::position,
+            ))
+            .load(&pool.conn())?;
+        Ok(bosses
+            .into_iter()
+            .map(|b| {
+                let count = completed
+                    .iter()
+                    .find(|(id, _)| *id == b.id)
+                    .map_or(0, |(_, c)| *c);
+                (b, count)
+            })
+            .collect())
+    })
+    .await
+    .unwrap()
+}
+
+async fn select_user_ids_by_boss_completion(
+    ctx: &impl DBPoolProvider,
+    training_boss_id: i32,
+) -> QueryResult<Vec<i32>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        boss_completions::table
+            .filter(boss_completions::training_boss_id.eq(training_boss_id))
+            .select(boss_completions::user_id)
+            .distinct()
+            .load(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+// Attended means signed up for a finished training of the tier without being
+// waitlisted. With a role only sign-ups with it as main role are counted
+async fn select_attendance_by_tier(
+    ctx: &impl DBPoolProvider,
+    tier_id: i32,
+    role_id: Option<i32>,
+) -> QueryResult<Vec<(User, i64)>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+
+        let trainings: Vec<Training> = trainings::table
+            .filter(trainings::tier_id.eq(tier_id))
+            .filter(trainings::state.eq(TrainingState::Finished))
+            .load(&conn)?;
+        let training_ids: Vec<i32> = trainings.iter().map(|t| t.id).collect();
+
+        let signups: Vec<Signup> = signups::table
+            .filter(signups::training_id.eq_any(&training_ids))
+            .order_by((signups::created_at, signups::id))
+            .load(&conn)?;
+
+        let main_roles: Option<Vec<i32>> = match role_id {
+            Some(role_id) => Some(
+                signup_roles::table
+                    .inner_join(signups::table)
+                    .filter(signups::training_id.eq_any(&training_ids))
+                    .filter(signup_roles::role_id.eq(role_id))
+                    .filter(signup_roles::rank.eq(1))
+                    .select(signup_roles::signup_id)
+                    .load(&conn)?,
+            ),
+            None => None,
+        };
+
+        let mut counts: HashMap<i32, i64> = HashMap::new();
+        for training in &trainings {
+            let attended = attendees(training, &signups).filter(|s| match &main_roles {
+                Some(m) => m.contains(&s.id),
+                None => true,
+            });
+            for signup in attended {
+                *counts.entry(signup.user_id).or_default() += 1;
+            }
+        }
+
+        let user_ids: Vec<i32> = counts.keys().copied().collect();
+        let users: Vec<User> = users::table
+            .filter(users::id.eq_any(&user_ids))
+            .order_by(users::id)
+            .load(&conn)?;
+
+        Ok(users
+            .into_iter()
+            .map(|u| {
+                let count = counts[&u.id];
+                (u, count)
+            })
+            .collect())
+    })
+    .await
+    .unwrap()
+}
+
+// Count
+async fn count_trainings_by_state(
+    ctx: &impl DBPoolProvider,
+    state: TrainingState,
+) -> QueryResult<i64> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        trainings::table
+            .filter(trainings::state.eq(state))
+            .count()
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn count_signups_by_training(
+    ctx: &impl DBPoolProvider,
+    training_id: i32,
+) -> QueryResult<i64> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        signups::table
+            .filter(signups::training_id.eq(training_id))
+            .count()
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+// Sign-ups of the same training that signed up before this one
+async fn count_earlier_signups(
+    ctx: &impl DBPoolProvider,
+    training_id: i32,
+    signup_id: i32,
+    created_at: NaiveDateTime,
+) -> QueryResult<i64> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        signups::table
+            .filter(signups::training_id.eq(training_id))
+            .filter(
+                signups::created_at.lt(created_at).or(signups::created_at
+                    .eq(created_at)
+                    .and(signups::id.lt(signup_id))),
+            )
+            .count()
             .get_result(&pool.conn())
     })
     .await
     .unwrap()
 }
 
-async fn count_active_trainings_by_date(ctx: &Context, date: NaiveDate) -> QueryResult<i64> {
+async fn count_active_trainings_by_date(
+    ctx: &impl DBPoolProvider,
+    guild_id: i64,
+    date: NaiveDate,
+) -> QueryResult<i64> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         trainings::table
+            .filter(trainings::guild_id.eq(guild_id))
             .filter(trainings::date.ge(date.and_hms(0, 0, 0)))
             .filter(trainings::date.le(date.and_hms(23, 59, 59)))
             .filter(
@@ -742,8 +2161,20 @@ async fn count_active_trainings_by_date(ctx: &Context, date: NaiveDate) -> Query
 }
 
 // Update
+async fn update_guild_id(ctx: &impl DBPoolProvider, from: i64, to: i64) -> QueryResult<usize> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        // Everything referencing the guild follows with ON UPDATE CASCADE
+        diesel::update(guilds::table.find(from))
+            .set(guilds::id.eq(to))
+            .execute(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
 async fn update_training_state(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     id: i32,
     state: TrainingState,
 ) -> QueryResult<Training> {
@@ -758,7 +2189,7 @@ async fn update_training_state(
 }
 
 async fn update_training_tier(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     id: i32,
     tier_id: Option<i32>,
 ) -> QueryResult<Training> {
@@ -772,8 +2203,112 @@ async fn update_training_tier(
     .unwrap()
 }
 
+async fn update_tier_parent(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    parent_id: Option<i32>,
+) -> QueryResult<Tier> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::update(tiers::table.find(id))
+            .set(tiers::parent_id.eq(parent_id))
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn update_tier_name(ctx: &impl DBPoolProvider, id: i32, name: String) -> QueryResult<Tier> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::update(tiers::table.find(id))
+            .set(tiers::name.eq(name))
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+// Only changes a promotion that is still in the expected state. Two squadmakers
+// deciding at the same time can not both get through
+async fn update_promotion_state(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    from: PromotionState,
+    to: PromotionState,
+) -> QueryResult<Option<Promotion>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::update(
+            promotions::table
+                .find(id)
+                .filter(promotions::state.eq(from)),
+        )
+        .set(promotions::state.eq(to))
+        .get_result(&pool.conn())
+        .optional()
+    })
+    .await
+    .unwrap()
+}
+
+// Same as for promotions, only pending requests can be decided on. Reopening
+// clears the decision again
+async fn update_tier_request_decision(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    decision: Option<(PromotionState, i64)>,
+) -> QueryResult<Option<TierRequest>> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        let conn = pool.conn();
+        let pending = tier_requests::table
+            .find(id)
+            .filter(tier_requests::state.eq(PromotionState::Pending));
+        match decision {
+            Some((state, decided_by)) => diesel::update(pending)
+                .set((
+                    tier_requests::state.eq(state),
+                    tier_requests::decided_at.eq(diesel::dsl::now.nullable()),
+                    tier_requests::decided_by.eq(decided_by),
+                ))
+                .get_result(&conn)
+                .optional(),
+            None => diesel::update(
+                tier_requests::table
+                    .find(id)
+                    .filter(tier_requests::state.ne(PromotionState::Pending)),
+            )
+            .set((
+                tier_requests::state.eq(PromotionState::Pending),
+                tier_requests::decided_at.eq(None::<NaiveDateTime>),
+                tier_requests::decided_by.eq(None::<i64>),
+            ))
+            .get_result(&conn)
+            .optional(),
+        }
+    })
+    .await
+    .unwrap()
+}
+
+async fn update_training_max_participants(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    max: Option<i32>,
+) -> QueryResult<Training> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::update(trainings::table.find(id))
+            .set(trainings::max_participants.eq(max))
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
 async fn update_training_board_message(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     id: i32,
     msg_id: Option<i64>,
 ) -> QueryResult<Training> {
@@ -787,7 +2322,40 @@ async fn update_training_board_message(
     .unwrap()
 }
 
-async fn update_role_active(ctx: &Context, id: i32, active: bool) -> QueryResult<Role> {
+async fn update_training_announcement(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    announcement: Option<(i64, i64)>,
+) -> QueryResult<Training> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::update(trainings::table.find(id))
+            .set((
+                trainings::announcement_channel_id.eq(announcement.map(|(c, _)| c)),
+                trainings::announcement_message_id.eq(announcement.map(|(_, m)| m)),
+            ))
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn update_training_thread(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    thread_id: Option<i64>,
+) -> QueryResult<Training> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::update(trainings::table.find(id))
+            .set(trainings::thread_id.eq(thread_id))
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn update_role_active(ctx: &impl DBPoolProvider, id: i32, active: bool) -> QueryResult<Role> {
     let pool = DBPool::load(ctx).await;
     task::spawn_blocking(move || {
         diesel::update(roles::table.find(id))
@@ -798,8 +2366,38 @@ async fn update_role_active(ctx: &Context, id: i32, active: bool) -> QueryResult
     .unwrap()
 }
 
+async fn update_role(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    changes: RoleChanges,
+) -> QueryResult<Role> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::update(roles::table.find(id))
+            .set(&changes)
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
+async fn update_training_boss(
+    ctx: &impl DBPoolProvider,
+    id: i32,
+    changes: TrainingBossChanges,
+) -> QueryResult<TrainingBoss> {
+    let pool = DBPool::load(ctx).await;
+    task::spawn_blocking(move || {
+        diesel::update(training_bosses::table.find(id))
+            .set(&changes)
+            .get_result(&pool.conn())
+    })
+    .await
+    .unwrap()
+}
+
 async fn update_signup_comment(
-    ctx: &Context,
+    ctx: &impl DBPoolProvider,
     id: i32,
     comment: Option<String>,
 ) -> QueryResult<Signup> {
@@ -813,9 +2411,54 @@ async fn update_signup_comment(
     .unwrap()
 }
 
+/* --- Guild --- */
+/// The placeholder everything created before guilds existed belongs to
+const PLACEHOLDER_GUILD_ID: i64 = 0;
+
+impl Guild {
+    /// Adds the guild if it does not exist yet
+    pub async fn insert(ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Guild> {
+        insert_guild(
+            ctx,
+            Guild {
+                id: guild_id.0 as i64,
+            },
+        )
+        .await
+    }
+
+    pub async fn by_id(ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Guild> {
+        select_guild_by_id(ctx, guild_id.0 as i64).await
+    }
+
+    /// Whether anything created before guilds existed is still waiting to be claimed
+    pub async fn has_placeholder(ctx: &impl DBPoolProvider) -> QueryResult<bool> {
+        match select_guild_by_id(ctx, PLACEHOLDER_GUILD_ID).await {
+            Ok(_) => Ok(true),
+            Err(diesel::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Moves everything that was created before guilds existed to the guild.
+    /// The guild must not exist yet. Returns false if there was nothing left
+    /// to take over
+    pub async fn claim_placeholder(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<bool> {
+        let claimed = update_guild_id(ctx, PLACEHOLDER_GUILD_ID, guild_id.0 as i64).await?;
+        Ok(claimed > 0)
+    }
+}
+
 /* --- User --- */
 impl User {
-    pub async fn upsert(ctx: &Context, discord_id: u64, gw2_id: String) -> QueryResult<User> {
+    pub async fn upsert(
+        ctx: &impl DBPoolProvider,
+        discord_id: u64,
+        gw2_id: String,
+    ) -> QueryResult<User> {
         let user = NewUser {
             discord_id: discord_id as i64,
             gw2_id,
@@ -823,46 +2466,132 @@ impl User {
         upsert_user(ctx, user).await
     }
 
-    pub async fn delete(&self, ctx: &Context) -> QueryResult<usize> {
+    pub async fn delete(&self, ctx: &impl DBPoolProvider) -> QueryResult<usize> {
         delete_user_by_id(ctx, self.id).await
     }
 
-    pub async fn by_discord_id(ctx: &Context, id: UserId) -> QueryResult<User> {
+    pub async fn by_discord_id(ctx: &impl DBPoolProvider, id: UserId) -> QueryResult<User> {
         select_user_by_discord_id(ctx, *id.as_u64()).await
     }
 
-    pub async fn joined_active_trainings(&self, ctx: &Context) -> QueryResult<Vec<Training>> {
+    pub async fn joined_active_trainings(
+        &self,
+        ctx: &impl DBPoolProvider,
+    ) -> QueryResult<Vec<Training>> {
         select_joined_active_trainings_by_user(ctx, self.id).await
     }
 
     pub async fn active_signups_with_training(
         &self,
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
     ) -> QueryResult<Vec<(Signup, Training)>> {
         select_active_signups_trainings_by_user(ctx, self.id).await
     }
 
-    pub async fn active_signups(&self, ctx: &Context) -> QueryResult<Vec<Signup>> {
+    pub async fn active_signups(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Signup>> {
         select_active_signups_by_user(ctx, self.id).await
     }
 
-    pub async fn open_signups(&self, ctx: &Context) -> QueryResult<Vec<Signup>> {
+    pub async fn open_signups(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Signup>> {
         select_open_signups_by_user(ctx, self.id).await
     }
 
-    pub async fn all_signups(&self, ctx: &Context) -> QueryResult<Vec<Signup>> {
+    pub async fn all_signups(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Signup>> {
         select_all_signups_by_user(ctx, self.id).await
     }
 
-    pub async fn by_signed_up_and_date(ctx: &Context, date: NaiveDate) -> QueryResult<Vec<User>> {
-        select_users_with_signup_by_date(ctx, date).await
+    pub async fn by_signed_up_and_date(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        date: NaiveDate,
+    ) -> QueryResult<Vec<User>> {
+        select_users_with_signup_by_date(ctx, guild_id.0 as i64, date).await
+    }
+
+    /// Removes all signups for trainings of the guild
+    pub async fn remove_guild_signups(
+        &self,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<usize> {
+        delete_signups_by_user_and_guild(ctx, self.id, guild_id.0 as i64).await
+    }
+
+    /// The active default roles in the guild, main role first
+    pub async fn default_roles(
+        &self,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<Vec<Role>> {
+        select_default_roles_by_user(ctx, self.id, guild_id.0 as i64).await
+    }
+
+    /// Replaces the default roles in the guild. The roles are ordered by preference
+    pub async fn set_default_roles(
+        &self,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        roles: &[&Role],
+    ) -> QueryResult<()> {
+        let defaults = (1..)
+            .zip(roles)
+            .map(|(rank, r)| UserDefaultRole {
+                user_id: self.id,
+                role_id: r.id,
+                rank,
+            })
+            .collect();
+        replace_user_default_roles(ctx, self.id, guild_id.0 as i64, defaults).await
+    }
+
+    /// The bosses the user completed in the guild with how often, by category and position
+    pub async fn boss_progress(
+        &self,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<Vec<(TrainingBoss, i64)>> {
+        select_boss_progress_by_user_and_guild(ctx, self.id, guild_id.0 as i64).await
+    }
+
+    /// All tier requests of the user in the guild, newest first
+    pub async fn tier_requests(
+        &self,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<Vec<(TierRequest, Tier)>> {
+        select_tier_requests_by_user_and_guild(ctx, self.id, guild_id.0 as i64).await
+    }
+
+    /// Used together with the default roles for one-click sign-ups in the guild
+    pub async fn default_comment(
+        &self,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<Option<String>> {
+        let defaults = select_user_defaults(ctx, self.id, guild_id.0 as i64).await?;
+        Ok(defaults.and_then(|d| d.comment))
+    }
+
+    pub async fn set_default_comment(
+        &self,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        comment: Option<String>,
+    ) -> QueryResult<UserDefaults> {
+        let defaults = UserDefaults {
+            user_id: self.id,
+            guild_id: guild_id.0 as i64,
+            comment,
+        };
+        upsert_user_defaults(ctx, defaults).await
     }
 }
 
 /* -- Training -- */
 impl Training {
     pub async fn insert(
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
         title: String,
         date: NaiveDateTime,
         tier_id: Option<i32>,
@@ -871,66 +2600,143 @@ impl Training {
             title,
             date,
             tier_id,
+            guild_id: guild_id.0 as i64,
+            max_participants: None,
         };
         insert_training(ctx, t).await
     }
 
-    pub async fn by_state(ctx: &Context, state: TrainingState) -> QueryResult<Vec<Training>> {
+    /// Inserts all drafts including their roles and bosses in one transaction
+    pub async fn insert_drafts(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        drafts: Vec<TrainingDraft>,
+    ) -> QueryResult<Vec<Training>> {
+        insert_training_drafts(ctx, guild_id.0 as i64, drafts).await
+    }
+
+    /// A new training on the given date with the same setup as this one. The
+    /// given sign-ups are copied along with their roles
+    pub async fn clone_to(
+        &self,
+        ctx: &impl DBPoolProvider,
+        date: NaiveDateTime,
+        signup_ids: Vec<i32>,
+    ) -> QueryResult<Training> {
+        insert_training_clone(ctx, self.id, date, signup_ids).await
+    }
+
+    pub async fn by_state(
+        ctx: &impl DBPoolProvider,
+        state: TrainingState,
+    ) -> QueryResult<Vec<Training>> {
         select_trainings_by_state(ctx, state).await
     }
 
-    pub async fn all_active(ctx: &Context) -> QueryResult<Vec<Training>> {
-        select_active_trainings(ctx).await
+    pub async fn all_active(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<Vec<Training>> {
+        select_active_trainings(ctx, guild_id.0 as i64).await
     }
 
-    pub async fn amount_by_state(ctx: &Context, state: TrainingState) -> QueryResult<i64> {
+    pub async fn all_active_with_tier(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<Vec<TrainingWithTier>> {
+        select_active_trainings_with_tier(ctx, guild_id.0 as i64).await
+    }
+
+    pub async fn amount_by_state(
+        ctx: &impl DBPoolProvider,
+        state: TrainingState,
+    ) -> QueryResult<i64> {
         count_trainings_by_state(ctx, state).await
     }
 
-    pub async fn amount_active_by_day(ctx: &Context, date: NaiveDate) -> QueryResult<i64> {
-        count_active_trainings_by_date(ctx, date).await
+    pub async fn amount_active_by_day(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        date: NaiveDate,
+    ) -> QueryResult<i64> {
+        count_active_trainings_by_date(ctx, guild_id.0 as i64, date).await
     }
 
-    pub async fn get_signup_count(&self, ctx: &Context) -> QueryResult<i64> {
+    pub async fn get_signup_count(&self, ctx: &impl DBPoolProvider) -> QueryResult<i64> {
         count_signups_by_training(ctx, self.id).await
     }
 
-    pub async fn by_id(ctx: &Context, id: i32) -> QueryResult<Training> {
-        select_training_by_id(ctx, id).await
+    /// Loads the training only if it belongs to the guild
+    pub async fn by_id(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        id: i32,
+    ) -> QueryResult<Training> {
+        select_training_by_guild_and_id(ctx, guild_id.0 as i64, id).await
     }
 
     pub async fn by_id_and_state(
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
         id: i32,
         state: TrainingState,
     ) -> QueryResult<Training> {
         select_training_by_id_and_state(ctx, id, state).await
     }
 
-    pub async fn by_date(ctx: &Context, date: NaiveDate) -> QueryResult<Vec<Training>> {
-        select_trainings_by_date(ctx, date).await
+    pub async fn by_date(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        date: NaiveDate,
+    ) -> QueryResult<Vec<Training>> {
+        select_trainings_by_date(ctx, guild_id.0 as i64, date).await
     }
 
-    pub async fn set_state(self, ctx: &Context, state: TrainingState) -> QueryResult<Training> {
+    pub async fn set_state(
+        self,
+        ctx: &impl DBPoolProvider,
+        state: TrainingState,
+    ) -> QueryResult<Training> {
         update_training_state(ctx, self.id, state).await
     }
 
-    pub async fn get_tier(&self, ctx: &Context) -> Option<QueryResult<Tier>> {
+    pub async fn get_tier(&self, ctx: &impl DBPoolProvider) -> Option<QueryResult<Tier>> {
         match self.tier_id {
             None => None,
             Some(id) => Some(select_tier_by_id(ctx, id).await),
         }
     }
 
-    pub async fn set_tier(&self, ctx: &Context, tier_id: Option<i32>) -> QueryResult<Training> {
+    pub async fn set_tier(
+        &self,
+        ctx: &impl DBPoolProvider,
+        tier_id: Option<i32>,
+    ) -> QueryResult<Training> {
         update_training_tier(ctx, self.id, tier_id).await
     }
 
-    pub async fn get_signups(&self, ctx: &Context) -> QueryResult<Vec<Signup>> {
+    pub async fn get_signups(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Signup>> {
         select_signups_by_training(ctx, self.id).await
     }
 
-    pub async fn add_role(&self, ctx: &Context, role_id: i32) -> QueryResult<TrainingRole> {
+    pub async fn set_max_participants(
+        &self,
+        ctx: &impl DBPoolProvider,
+        max: Option<i32>,
+    ) -> QueryResult<Training> {
+        update_training_max_participants(ctx, self.id, max).await
+    }
+
+    /// The waitlisted sign-ups, first to be promoted first. Uses the current
+    /// limit in the database, not the one of this possibly outdated training
+    pub async fn waitlist(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Signup>> {
+        select_waitlist_by_training(ctx, self.id).await
+    }
+
+    pub async fn add_role(
+        &self,
+        ctx: &impl DBPoolProvider,
+        role_id: i32,
+    ) -> QueryResult<TrainingRole> {
         let training_role = NewTrainingRole {
             training_id: self.id,
             role_id,
@@ -940,7 +2746,7 @@ impl Training {
 
     pub async fn add_training_boss(
         &self,
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
         training_boss_id: i32,
     ) -> QueryResult<TrainingBossMapping> {
         let mapping = TrainingBossMapping {
@@ -951,34 +2757,93 @@ impl Training {
         insert_training_boss_mapping(ctx, mapping).await
     }
 
-    pub async fn get_training_roles(&self, ctx: &Context) -> QueryResult<Vec<TrainingRole>> {
+    pub async fn get_training_roles(
+        &self,
+        ctx: &impl DBPoolProvider,
+    ) -> QueryResult<Vec<TrainingRole>> {
         select_training_roles_by_training(ctx, self.id).await
     }
 
-    pub async fn all_roles(&self, ctx: &Context) -> QueryResult<Vec<Role>> {
+    pub async fn all_roles(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Role>> {
         select_roles_by_training(ctx, self.id).await
     }
 
-    pub async fn all_training_bosses(&self, ctx: &Context) -> QueryResult<Vec<TrainingBoss>> {
+    pub async fn all_requirements(
+        &self,
+        ctx: &impl DBPoolProvider,
+    ) -> QueryResult<Vec<TrainingRequirement>> {
+        select_requirements_by_training(ctx, self.id).await
+    }
+
+    /// Sorted by category, ready for embed_add_bosses
+    pub async fn all_training_bosses(
+        &self,
+        ctx: &impl DBPoolProvider,
+    ) -> QueryResult<Vec<TrainingBoss>> {
         select_training_bosses_by_training(ctx, self.id).await
     }
 
-    pub async fn active_roles(&self, ctx: &Context) -> QueryResult<Vec<Role>> {
+    /// Records the bosses of the training for everyone that was not waitlisted
+    pub async fn record_boss_completions(&self, ctx: &impl DBPoolProvider) -> QueryResult<usize> {
+        insert_boss_completions_by_training(ctx, self.id).await
+    }
+
+    pub async fn active_roles(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Role>> {
         select_active_roles_by_training(ctx, self.id).await
     }
 
-    pub async fn set_board_msg(&self, ctx: &Context, msg_id: Option<u64>) -> QueryResult<Training> {
+    pub async fn set_board_msg(
+        &self,
+        ctx: &impl DBPoolProvider,
+        msg_id: Option<u64>,
+    ) -> QueryResult<Training> {
         update_training_board_message(ctx, self.id, msg_id.map(|id| id as i64)).await
     }
 
     pub fn board_message(&self) -> Option<MessageId> {
         self.board_message_id.map(|id| MessageId::from(id as u64))
     }
+
+    pub async fn set_announcement(
+        &self,
+        ctx: &impl DBPoolProvider,
+        announcement: Option<(ChannelId, MessageId)>,
+    ) -> QueryResult<Training> {
+        let announcement = announcement.map(|(c, m)| (c.0 as i64, m.0 as i64));
+        update_training_announcement(ctx, self.id, announcement).await
+    }
+
+    pub async fn set_thread(
+        &self,
+        ctx: &impl DBPoolProvider,
+        thread_id: Option<ChannelId>,
+    ) -> QueryResult<Training> {
+        update_training_thread(ctx, self.id, thread_id.map(|id| id.0 as i64)).await
+    }
+
+    pub fn thread(&self) -> Option<ChannelId> {
+        self.thread_id.map(|id| ChannelId::from(id as u64))
+    }
+
+    pub fn announcement(&self) -> Option<(ChannelId, MessageId)> {
+        match (self.announcement_channel_id, self.announcement_message_id) {
+            (Some(c), Some(m)) => Some((ChannelId::from(c as u64), MessageId::from(m as u64))),
+            _ => None,
+        }
+    }
+
+    pub fn guild(&self) -> GuildId {
+        GuildId::from(self.guild_id as u64)
+    }
 }
 
 /* -- Signup -- */
 impl Signup {
-    pub async fn insert(ctx: &Context, user: &User, training: &Training) -> QueryResult<Self> {
+    pub async fn insert(
+        ctx: &impl DBPoolProvider,
+        user: &User,
+        training: &Training,
+    ) -> QueryResult<Self> {
         let new_signup = NewSignup {
             user_id: user.id,
             training_id: training.id,
@@ -986,40 +2851,48 @@ impl Signup {
         insert_signup(ctx, new_signup).await
     }
 
-    pub async fn add_role(&self, ctx: &Context, role: &Role) -> QueryResult<SignupRole> {
+    /// Adds the role with the users preference. Rank 1 is the main role
+    pub async fn add_role(
+        &self,
+        ctx: &impl DBPoolProvider,
+        role: &Role,
+        rank: i16,
+    ) -> QueryResult<SignupRole> {
         let sr = NewSignupRole {
             signup_id: self.id,
             role_id: role.id,
+            rank,
         };
         insert_signup_role(ctx, sr).await
     }
 
     pub async fn update_comment(
         &self,
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
         comment: Option<String>,
     ) -> QueryResult<Self> {
         update_signup_comment(ctx, self.id, comment).await
     }
 
-    pub async fn get_training(&self, ctx: &Context) -> QueryResult<Training> {
+    pub async fn get_training(&self, ctx: &impl DBPoolProvider) -> QueryResult<Training> {
         select_training_by_id(ctx, self.training_id).await
     }
 
-    pub async fn get_user(&self, ctx: &Context) -> QueryResult<User> {
+    pub async fn get_user(&self, ctx: &impl DBPoolProvider) -> QueryResult<User> {
         select_user_by_id(ctx, self.user_id).await
     }
 
-    pub async fn get_roles(&self, ctx: &Context) -> QueryResult<Vec<Role>> {
+    /// Ordered by the preference of the user, main role first
+    pub async fn get_roles(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Role>> {
         select_roles_by_signup(ctx, self.id).await
     }
 
-    pub async fn clear_roles(&self, ctx: &Context) -> QueryResult<usize> {
+    pub async fn clear_roles(&self, ctx: &impl DBPoolProvider) -> QueryResult<usize> {
         delete_signup_roles_by_signup(ctx, self.id).await
     }
 
     pub async fn by_user_and_training(
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
         u: &User,
         t: &Training,
     ) -> QueryResult<Signup> {
@@ -1027,18 +2900,37 @@ impl Signup {
     }
 
     pub async fn by_discord_user_and_training(
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
         u: &UserId,
         t: &Training,
     ) -> QueryResult<Signup> {
         select_signup_by_discord_user_and_training(ctx, *u.as_u64() as i64, t.id).await
     }
 
-    pub async fn by_date(ctx: &Context, date: NaiveDate) -> QueryResult<Vec<Signup>> {
-        select_signups_by_date(ctx, date).await
+    pub async fn by_date(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        date: NaiveDate,
+    ) -> QueryResult<Vec<Signup>> {
+        select_signups_by_date(ctx, guild_id.0 as i64, date).await
     }
 
-    pub async fn remove(self, ctx: &Context) -> QueryResult<usize> {
+    /// Position on the waitlist starting at 1. None if the sign-up participates
+    pub async fn waitlist_position(
+        &self,
+        ctx: &impl DBPoolProvider,
+        training: &Training,
+    ) -> QueryResult<Option<i64>> {
+        let max = match training.max_participants {
+            Some(max) => max as i64,
+            None => return Ok(None),
+        };
+        let earlier =
+            count_earlier_signups(ctx, self.training_id, self.id, self.created_at).await?;
+        Ok((earlier >= max).then(|| earlier - max + 1))
+    }
+
+    pub async fn remove(self, ctx: &impl DBPoolProvider) -> QueryResult<usize> {
         delete_signup_by_id(ctx, self.id).await
     }
 }
@@ -1047,71 +2939,106 @@ impl Signup {
 
 impl Role {
     pub async fn insert(
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
         title: String,
         repr: String,
-        emoji: u64,
+        emoji: Emoji,
         priority: Option<i16>,
+        category: Option<String>,
     ) -> QueryResult<Role> {
         let r = NewRole {
             title,
             repr,
-            emoji: emoji as i64,
+            emoji,
             priority,
+            guild_id: guild_id.0 as i64,
+            category,
         };
         insert_role(ctx, r).await
     }
 
     /// Deactivates the role but keeps it in database
-    pub async fn deactivate(self, ctx: &Context) -> QueryResult<Role> {
+    pub async fn deactivate(self, ctx: &impl DBPoolProvider) -> QueryResult<Role> {
         update_role_active(ctx, self.id, false).await
     }
 
-    /// Loads all active roles
-    pub async fn all_active(ctx: &Context) -> QueryResult<Vec<Role>> {
-        select_roles_by_active(ctx, true).await
+    /// Changes the given fields of the role. At least one has to be set
+    pub async fn edit(self, ctx: &impl DBPoolProvider, changes: RoleChanges) -> QueryResult<Role> {
+        update_role(ctx, self.id, changes).await
+    }
+
+    /// Loads the active role with the repr or else the one deactivated last
+    pub async fn by_repr_latest(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        repr: String,
+    ) -> QueryResult<Role> {
+        select_latest_role_by_repr(ctx, guild_id.0 as i64, repr).await
+    }
+
+    /// Loads all active roles of the guild
+    pub async fn all_active(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<Vec<Role>> {
+        select_roles_by_active(ctx, guild_id.0 as i64, true).await
     }
 
     /// Loads the current active role associated with provided emoji
-    pub async fn by_emoji(ctx: &Context, emoji: u64) -> QueryResult<Role> {
-        select_active_role_by_emoji(ctx, emoji as i64).await
+    pub async fn by_emoji(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        emoji: Emoji,
+    ) -> QueryResult<Role> {
+        select_active_role_by_emoji(ctx, guild_id.0 as i64, emoji).await
     }
 
     /// Loads the current active role with specified repr
-    pub async fn by_repr(ctx: &Context, repr: String) -> QueryResult<Role> {
-        select_active_role_by_repr(ctx, repr).await
+    pub async fn by_repr(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        repr: String,
+    ) -> QueryResult<Role> {
+        select_active_role_by_repr(ctx, guild_id.0 as i64, repr).await
     }
 }
 
 impl std::fmt::Display for Role {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} | {}",
-            Mention::from(EmojiId::from(self.emoji as u64)),
-            self.title
-        )
+        write!(f, "{} | {}", self.emoji, self.title)
     }
 }
 
 // --- Tier ---
 impl Tier {
-    pub async fn insert(ctx: &Context, name: String) -> QueryResult<Tier> {
-        let new_tier = NewTier { name };
+    pub async fn insert(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        name: String,
+    ) -> QueryResult<Tier> {
+        let new_tier = NewTier {
+            name,
+            guild_id: guild_id.0 as i64,
+        };
         insert_tier(ctx, new_tier).await
     }
 
-    pub async fn all(ctx: &Context) -> QueryResult<Vec<Tier>> {
-        select_all_tiers(ctx).await
+    pub async fn all(ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Vec<Tier>> {
+        select_all_tiers(ctx, guild_id.0 as i64).await
     }
 
-    pub async fn by_name(ctx: &Context, name: String) -> QueryResult<Tier> {
-        select_tier_by_name(ctx, name).await
+    pub async fn by_name(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        name: String,
+    ) -> QueryResult<Tier> {
+        select_tier_by_name(ctx, guild_id.0 as i64, name).await
     }
 
     pub async fn add_discord_role(
         &self,
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
         discord_id: u64,
     ) -> QueryResult<TierMapping> {
         let new_tier_mapping = NewTierMapping {
@@ -1121,78 +3048,379 @@ impl Tier {
         insert_tier_mapping(ctx, new_tier_mapping).await
     }
 
-    pub async fn delete(self, ctx: &Context) -> QueryResult<usize> {
-        delete_tier_by_id(ctx, self.id).await
+    /// Open requests for the tier with the user that asked for it, oldest first
+    pub async fn pending_requests(
+        &self,
+        ctx: &impl DBPoolProvider,
+    ) -> QueryResult<Vec<(TierRequest, User)>> {
+        select_pending_tier_requests_by_tier(ctx, self.id).await
+    }
+
+    /// Deletes the tier with its discord roles. Its trainings are moved to the
+    /// given tier or, with None, opened to everyone
+    pub async fn delete(
+        self,
+        ctx: &impl DBPoolProvider,
+        reassign_to: Option<&Tier>,
+    ) -> QueryResult<usize> {
+        delete_tier_by_id(ctx, self.id, reassign_to.map(|t| t.id)).await
     }
 
-    pub async fn get_discord_roles(&self, ctx: &Context) -> QueryResult<Vec<TierMapping>> {
+    /// Places the tier below the given one. None makes it a top tier
+    pub async fn set_parent(
+        self,
+        ctx: &impl DBPoolProvider,
+        parent_id: Option<i32>,
+    ) -> QueryResult<Tier> {
+        update_tier_parent(ctx, self.id, parent_id).await
+    }
+
+    pub async fn rename(self, ctx: &impl DBPoolProvider, name: String) -> QueryResult<Tier> {
+        update_tier_name(ctx, self.id, name).await
+    }
+
+    pub async fn get_discord_roles(
+        &self,
+        ctx: &impl DBPoolProvider,
+    ) -> QueryResult<Vec<TierMapping>> {
         select_tier_mappings_by_tier(ctx, self.id).await
     }
 
     pub async fn get_tier_mapping_by_discord_role(
         &self,
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
         role_id: u64,
     ) -> QueryResult<TierMapping> {
         select_tier_mappings_by_tier_and_discord_role(ctx, self.id, role_id as i64).await
     }
 
-    pub async fn get_trainings(&self, ctx: &Context) -> QueryResult<Vec<Training>> {
+    pub async fn get_trainings(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Training>> {
         select_trainings_by_tier(ctx, self.id).await
     }
 }
 
 // --- TierMapping ---
 impl TierMapping {
-    pub async fn delete(self, ctx: &Context) -> QueryResult<usize> {
+    /// The discord roles of all tiers of the guild
+    pub async fn all(ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Vec<Self>> {
+        Ok(select_all_tier_mappings_with_tier(ctx, guild_id.0 as i64)
+            .await?
+            .into_iter()
+            .map(|(m, _)| m)
+            .collect())
+    }
+
+    pub async fn delete(self, ctx: &impl DBPoolProvider) -> QueryResult<usize> {
         delete_tier_mapping(ctx, self.tier_id, self.discord_role_id).await
     }
 }
 
-// --- Config ---
-impl Config {
-    pub async fn load(ctx: &Context, name: String) -> QueryResult<Config> {
-        select_config_by_name(ctx, name).await
+// --- PromotionRule ---
+impl PromotionRule {
+    /// The discord role has to be mapped to the tier
+    pub async fn insert(
+        ctx: &impl DBPoolProvider,
+        mapping: &TierMapping,
+        from_tier: &Tier,
+        role: Option<&Role>,
+        min_count: i32,
+        automatic: bool,
+    ) -> QueryResult<PromotionRule> {
+        let rule = NewPromotionRule {
+            tier_id: mapping.tier_id,
+            discord_role_id: mapping.discord_role_id,
+            from_tier_id: from_tier.id,
+            role_id: role.map(|r| r.id),
+            min_count,
+            automatic,
+        };
+        insert_promotion_rule(ctx, rule).await
+    }
+
+    pub async fn all(ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Vec<Self>> {
+        select_promotion_rules_by_guild(ctx, guild_id.0 as i64).await
+    }
+
+    /// Loads the rule only if it belongs to the guild
+    pub async fn by_id(ctx: &impl DBPoolProvider, guild_id: GuildId, id: i32) -> QueryResult<Self> {
+        select_promotion_rule_by_guild_and_id(ctx, guild_id.0 as i64, id).await
+    }
+
+    pub async fn delete(self, ctx: &impl DBPoolProvider) -> QueryResult<usize> {
+        delete_promotion_rule_by_id(ctx, self.id).await
+    }
+
+    /// Every user that attended at least one training counting for the rule
+    pub async fn attendance(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<(User, i64)>> {
+        select_attendance_by_tier(ctx, self.from_tier_id, self.role_id).await
+    }
+
+    pub async fn promotions(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<Promotion>> {
+        select_promotions_by_rule(ctx, self.id).await
+    }
+}
+
+// --- Promotion ---
+impl Promotion {
+    pub async fn insert(
+        ctx: &impl DBPoolProvider,
+        rule: &PromotionRule,
+        user: &User,
+        state: PromotionState,
+    ) -> QueryResult<Promotion> {
+        let promotion = NewPromotion {
+            rule_id: rule.id,
+            user_id: user.id,
+            state,
+        };
+        insert_promotion(ctx, promotion).await
+    }
+
+    pub async fn by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<Promotion> {
+        select_promotion_by_id(ctx, id).await
+    }
+
+    /// Approves or denies the promotion. None if it was decided on already,
+    /// for example by another squadmaker at the same time
+    pub async fn decide(
+        &self,
+        ctx: &impl DBPoolProvider,
+        state: PromotionState,
+    ) -> QueryResult<Option<Promotion>> {
+        update_promotion_state(ctx, self.id, PromotionState::Pending, state).await
+    }
+
+    /// Makes an approved promotion pending again, when the role could not be assigned
+    pub async fn reopen(&self, ctx: &impl DBPoolProvider) -> QueryResult<Option<Promotion>> {
+        update_promotion_state(
+            ctx,
+            self.id,
+            PromotionState::Approved,
+            PromotionState::Pending,
+        )
+        .await
+    }
+
+    pub async fn user(&self, ctx: &impl DBPoolProvider) -> QueryResult<User> {
+        select_user_by_id(ctx, self.user_id).await
+    }
+
+    pub async fn delete(self, ctx: &impl DBPoolProvider) -> QueryResult<usize> {
+        delete_promotion_by_id(ctx, self.id).await
+    }
+}
+
+// --- TierRequest ---
+impl TierRequest {
+    pub async fn insert(
+        ctx: &impl DBPoolProvider,
+        user: &User,
+        tier: &Tier,
+        justification: Option<String>,
+        logs: Option<String>,
+    ) -> QueryResult<TierRequest> {
+        let request = NewTierRequest {
+            user_id: user.id,
+            tier_id: tier.id,
+            justification,
+            logs,
+        };
+        insert_tier_request(ctx, request).await
+    }
+
+    pub async fn by_id(ctx: &impl DBPoolProvider, id: i32) -> QueryResult<TierRequest> {
+        select_tier_request_by_id(ctx, id).await
     }
 
-    pub async fn save(self, ctx: &Context) -> QueryResult<Config> {
-        upsert_config(ctx, self).await
+    /// Saves the decision together with the squadmaker and the time. None if it
+    /// was decided on already, for example by another squadmaker at the same time
+    pub async fn decide(
+        &self,
+        ctx: &impl DBPoolProvider,
+        state: PromotionState,
+        decided_by: UserId,
+    ) -> QueryResult<Option<TierRequest>> {
+        update_tier_request_decision(ctx, self.id, Some((state, decided_by.0 as i64))).await
+    }
+
+    /// Makes a decided request pending again, when the role could not be assigned
+    pub async fn reopen(&self, ctx: &impl DBPoolProvider) -> QueryResult<Option<TierRequest>> {
+        update_tier_request_decision(ctx, self.id, None).await
+    }
+
+    pub async fn user(&self, ctx: &impl DBPoolProvider) -> QueryResult<User> {
+        select_user_by_id(ctx, self.user_id).await
+    }
+
+    pub async fn tier(&self, ctx: &impl DBPoolProvider) -> QueryResult<Tier> {
+        select_tier_by_id(ctx, self.tier_id).await
+    }
+}
+
+// --- Config ---
+impl Config {
+    pub async fn load(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        name: String,
+    ) -> QueryResult<Config> {
+        select_config_by_name(ctx, guild_id.0 as i64, name).await
+    }
+
+    pub async fn save(self, ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Config> {
+        upsert_config(ctx, guild_id.0 as i64, self).await
+    }
+}
+
+// --- SignupBoardOverview ---
+impl SignupBoardOverview {
+    pub async fn load(ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Self> {
+        select_signup_board_overview(ctx, guild_id.0 as i64).await
+    }
+}
+
+// --- BotConfiguration ---
+impl BotConfiguration {
+    /// Loads all active roles, bosses, tiers and config values of the guild
+    pub async fn load(ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Self> {
+        let guild_id = guild_id.0 as i64;
+        let roles = select_roles_by_active(ctx, guild_id, true)
+            .await?
+            .into_iter()
+            .map(|r| RoleConfig {
+                title: r.title,
+                repr: r.repr,
+                emoji: r.emoji,
+                priority: r.priority,
+                category: r.category,
+            })
+            .collect();
+
+        let mut bosses = select_all_training_bosses(ctx, guild_id).await?;
+        bosses.sort_by_key(|b| b.sort_key());
+        let training_bosses = bosses
+            .into_iter()
+            .map(|b| TrainingBossConfig {
+                repr: b.repr,
+                name: b.name,
+                category: b.category,
+                category_number: b.category_number,
+                position: b.position,
+                emoji: b.emoji,
+                url: b.url,
+            })
+            .collect();
+
+        let all_tiers = select_all_tiers(ctx, guild_id).await?;
+        let tiers = all_tiers
+            .iter()
+            .map(|t| TierConfig {
+                name: t.name.clone(),
+                parent: t
+                    .parent_id
+                    .and_then(|id| all_tiers.iter().find(|p| p.id == id))
+                    .map(|p| p.name.clone()),
+            })
+            .collect();
+
+        let tier_mappings = select_all_tier_mappings_with_tier(ctx, guild_id)
+            .await?
+            .into_iter()
+            .map(|(m, t)| TierMappingConfig {
+                tier: t.name,
+                discord_role_id: m.discord_role_id,
+            })
+            .collect();
+
+        let config = select_all_config(ctx, guild_id).await?;
+
+        Ok(BotConfiguration {
+            roles,
+            training_bosses,
+            tiers,
+            tier_mappings,
+            config,
+        })
+    }
+
+    /// Applies the configuration in a single transaction. With replace everything
+    /// not part of the configuration is removed, otherwise it is merged. Bosses
+    /// that are still in use are never removed and trainings of removed tiers are
+    /// opened to everyone
+    pub async fn apply(
+        self,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        replace: bool,
+    ) -> QueryResult<()> {
+        apply_bot_configuration(ctx, guild_id.0 as i64, self, replace).await
     }
 }
 
 impl TrainingBoss {
+    /// Bosses of the guild that trainings, sign-ups or completions refer to
+    pub async fn all_in_use(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+    ) -> QueryResult<Vec<Self>> {
+        select_used_training_bosses_by_guild(ctx, guild_id.0 as i64).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn insert(
-        ctx: &Context,
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
         name: String,
         repr: String,
-        wing: i32,
+        category: BossCategory,
+        category_number: i32,
         position: i32,
-        emoji: EmojiId,
+        emoji: Emoji,
         url: Option<Url>,
     ) -> QueryResult<Self> {
         let tb = NewTrainingBoss {
             name,
             repr,
-            wing,
+            category,
+            category_number,
             position,
-            emoji: emoji.0 as i64,
+            emoji,
             url: url.map(|u| u.to_string()),
+            guild_id: guild_id.0 as i64,
         };
 
         insert_training_boss(ctx, tb).await
     }
 
-    pub async fn all(ctx: &Context) -> QueryResult<Vec<Self>> {
-        select_all_training_bosses(ctx).await
+    pub async fn all(ctx: &impl DBPoolProvider, guild_id: GuildId) -> QueryResult<Vec<Self>> {
+        select_all_training_bosses(ctx, guild_id.0 as i64).await
     }
 
-    pub async fn by_repr(ctx: &Context, repr: String) -> QueryResult<Self> {
-        select_training_boss_by_repr(ctx, repr).await
+    pub async fn by_repr(
+        ctx: &impl DBPoolProvider,
+        guild_id: GuildId,
+        repr: String,
+    ) -> QueryResult<Self> {
+        select_training_boss_by_repr(ctx, guild_id.0 as i64, repr).await
     }
 
-    pub async fn delete(&self, ctx: &Context) -> QueryResult<usize> {
+    pub async fn delete(&self, ctx: &impl DBPoolProvider) -> QueryResult<usize> {
         delete_training_boss_by_id(ctx, self.id).await
     }
+
+    /// Changes the given fields of the boss. At least one has to be set
+    pub async fn edit(
+        self,
+        ctx: &impl DBPoolProvider,
+        changes: TrainingBossChanges,
+    ) -> QueryResult<TrainingBoss> {
+        update_training_boss(ctx, self.id, changes).await
+    }
+
+    /// Ids of all users that completed the boss in a training
+    pub async fn completed_by(&self, ctx: &impl DBPoolProvider) -> QueryResult<Vec<i32>> {
+        select_user_ids_by_boss_completion(ctx, self.id).await
+    }
 }
 
 impl std::fmt::Display for TrainingBoss {
@@ -1201,19 +3429,10 @@ impl std::fmt::Display for TrainingBoss {
             write!(
                 f,
                 "{} | {} | [{}]({})",
-                Mention::from(EmojiId::from(self.emoji as u64)),
-                self.repr,
-                self.name,
-                url
+                self.emoji, self.repr, self.name, url
             )
         } else {
-            write!(
-                f,
-                "{} | {} | {}",
-                Mention::from(EmojiId::from(self.emoji as u64)),
-                self.repr,
-                self.name
-            )
+            write!(f, "{} | {} | {}", self.emoji, self.repr, self.name)
         }
     }
 }
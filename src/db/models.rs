@@ -1,12 +1,26 @@
 use crate::db::schema::{
-    config, roles, signup_roles, signups, tier_mappings, tiers, training_boss_mappings,
-    training_bosses, training_roles, trainings, users,
+    boss_completions, config, guilds, promotion_rules, promotions, roles, signup_roles, signups,
+    tier_mappings, tier_requests, tiers, training_boss_mappings, training_bosses,
+    training_requirements, training_roles, trainings, user_default_roles, user_defaults, users,
+};
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::Pg,
+    serialize::{self, Output, ToSql},
+    sql_types::Text,
 };
 use diesel_derive_enum::DbEnum;
-use serde::Serialize;
-use std::{fmt, str};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serenity::model::{channel::ReactionType, id::EmojiId, mention::Mention};
+use std::{collections::HashMap, fmt, io::Write, str};
+
+use chrono::naive::{NaiveDate, NaiveDateTime};
 
-use chrono::naive::NaiveDateTime;
+#[derive(Identifiable, Queryable, Insertable, PartialEq, Debug, Clone)]
+#[table_name = "guilds"]
+pub struct Guild {
+    pub id: i64,
+}
 
 #[derive(Identifiable, Queryable, PartialEq, Debug, Serialize)]
 #[table_name = "users"]
@@ -29,6 +43,29 @@ pub(super) struct NewUser {
     pub gw2_id: String,
 }
 
+/// A role the user signs up with by default. Rank 1 is the main role
+#[derive(Identifiable, Queryable, Insertable, Associations, PartialEq, Debug)]
+#[belongs_to(User)]
+#[belongs_to(Role)]
+#[table_name = "user_default_roles"]
+#[primary_key(user_id, role_id)]
+pub struct UserDefaultRole {
+    pub user_id: i32,
+    pub role_id: i32,
+    pub rank: i16,
+}
+
+/// Defaults of the user in a guild besides the roles
+#[derive(Identifiable, Queryable, Insertable, Associations, PartialEq, Debug)]
+#[belongs_to(User)]
+#[table_name = "user_defaults"]
+#[primary_key(user_id, guild_id)]
+pub struct UserDefaults {
+    pub user_id: i32,
+    pub guild_id: i64,
+    pub comment: Option<String>,
+}
+
 #[derive(Identifiable, Queryable, Associations, Clone, PartialEq, Debug)]
 #[belongs_to(User)]
 #[belongs_to(Training)]
@@ -38,6 +75,9 @@ pub struct Signup {
     pub user_id: i32,
     pub training_id: i32,
     pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+    /// Also changes when the roles of the sign-up change
+    pub updated_at: NaiveDateTime,
 }
 
 #[derive(Insertable, Debug)]
@@ -84,6 +124,98 @@ impl str::FromStr for TrainingState {
     }
 }
 
+/// Emoji of a role or boss. Either a custom emoji of the emoji guild or a
+/// standard unicode emoji. Saved as the id for custom and as is for unicode
+/// emojis, which never consist of digits only
+#[derive(Debug, Clone, PartialEq, Eq, Hash, AsExpression, FromSqlRow)]
+#[sql_type = "Text"]
+pub enum Emoji {
+    Custom(EmojiId),
+    Unicode(String),
+}
+
+impl Emoji {
+    /// Accepts a single standard unicode emoji, including skin tones. Discord
+    /// rejects anything else once it is used
+    pub fn unicode(input: &str) -> Option<Self> {
+        emojis::get(input.trim()).map(|e| Emoji::Unicode(e.as_str().to_string()))
+    }
+
+    fn stored(&self) -> String {
+        match self {
+            Emoji::Custom(id) => id.0.to_string(),
+            Emoji::Unicode(e) => e.clone(),
+        }
+    }
+
+    fn from_stored(stored: String) -> Self {
+        match stored.parse::<u64>() {
+            Ok(id) => Emoji::Custom(EmojiId(id)),
+            Err(_) => Emoji::Unicode(stored),
+        }
+    }
+}
+
+impl fmt::Display for Emoji {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Emoji::Custom(id) => write!(f, "{}", Mention::from(*id)),
+            Emoji::Unicode(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<Emoji> for ReactionType {
+    fn from(emoji: Emoji) -> Self {
+        match emoji {
+            Emoji::Custom(id) => ReactionType::from(id),
+            Emoji::Unicode(e) => ReactionType::Unicode(e),
+        }
+    }
+}
+
+impl From<EmojiId> for Emoji {
+    fn from(id: EmojiId) -> Self {
+        Emoji::Custom(id)
+    }
+}
+
+impl ToSql<Text, Pg> for Emoji {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        ToSql::<Text, Pg>::to_sql(&self.stored(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for Emoji {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        let stored: String = FromSql::<Text, Pg>::from_sql(bytes)?;
+        Ok(Emoji::from_stored(stored))
+    }
+}
+
+impl Serialize for Emoji {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.stored())
+    }
+}
+
+impl<'de> Deserialize<'de> for Emoji {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Exports from before unicode emojis contain the custom id as a number
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Exported {
+            Id(u64),
+            Stored(String),
+        }
+
+        Ok(match Exported::deserialize(deserializer)? {
+            Exported::Id(id) => Emoji::Custom(EmojiId(id)),
+            Exported::Stored(s) => Emoji::from_stored(s),
+        })
+    }
+}
+
 #[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Serialize, Clone)]
 #[belongs_to(Tier)]
 #[table_name = "trainings"]
@@ -94,6 +226,14 @@ pub struct Training {
     pub state: TrainingState,
     pub tier_id: Option<i32>,
     pub board_message_id: Option<i64>,
+    pub guild_id: i64,
+    /// Sign-ups past this count are waitlisted. Unlimited if not set
+    pub max_participants: Option<i32>,
+    /// Where the roster was posted once the training closed
+    pub announcement_channel_id: Option<i64>,
+    pub announcement_message_id: Option<i64>,
+    /// Discussion thread created once the training opened
+    pub thread_id: Option<i64>,
 }
 
 #[derive(Insertable, Debug)]
@@ -102,17 +242,72 @@ pub(super) struct NewTraining {
     pub title: String,
     pub date: NaiveDateTime,
     pub tier_id: Option<i32>,
+    pub guild_id: i64,
+    pub max_participants: Option<i32>,
+}
+
+/// A training that is not yet saved, together with its roles and bosses
+#[derive(Debug, Clone)]
+pub struct TrainingDraft {
+    pub title: String,
+    pub date: NaiveDateTime,
+    pub tier_id: Option<i32>,
+    pub max_participants: Option<i32>,
+    pub role_ids: Vec<i32>,
+    pub boss_ids: Vec<i32>,
+    pub requirements: Vec<RequirementDraft>,
+}
+
+/// A requirement that is not yet saved. Exactly one of role and category is set
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequirementDraft {
+    pub role_id: Option<i32>,
+    pub category: Option<String>,
+    pub min_count: i32,
+    pub max_count: Option<i32>,
+}
+
+/// An active training together with the tier required to join it
+#[derive(Debug, Clone)]
+pub struct TrainingWithTier {
+    pub training: Training,
+    pub tier: Option<Tier>,
+    /// Includes the discord roles of all tiers above
+    pub tier_mappings: Vec<TierMapping>,
+}
+
+/// Everything the signup board shows about a single training
+#[derive(Debug)]
+pub struct TrainingOverview {
+    pub training: Training,
+    pub tier: Option<Tier>,
+    /// Includes the discord roles of all tiers above
+    pub tier_mappings: Vec<TierMapping>,
+    pub signup_count: i64,
+    pub composition: Vec<RequirementStatus>,
+    /// Sorted by category and position
+    pub bosses: Vec<TrainingBoss>,
+}
+
+/// All active trainings for the signup board, loaded at once
+#[derive(Debug, Default)]
+pub struct SignupBoardOverview {
+    pub trainings: Vec<TrainingOverview>,
+    /// Distinct users signed up for any training on that day
+    pub users_per_day: HashMap<NaiveDate, i64>,
 }
 
-#[derive(Identifiable, Queryable, Associations, Hash, PartialEq, Eq, Debug, Serialize)]
+#[derive(Identifiable, Queryable, Associations, Hash, PartialEq, Eq, Debug, Serialize, Clone)]
 #[table_name = "roles"]
 pub struct Role {
     pub id: i32,
     pub title: String,
     pub repr: String,
-    pub emoji: i64,
+    pub emoji: Emoji,
     pub active: bool,
     pub priority: i16,
+    pub guild_id: i64,
+    pub category: Option<String>,
 }
 
 #[derive(Insertable, Debug)]
@@ -120,8 +315,20 @@ pub struct Role {
 pub(super) struct NewRole {
     pub title: String,
     pub repr: String,
-    pub emoji: i64,
+    pub emoji: Emoji,
     pub priority: Option<i16>,
+    pub guild_id: i64,
+    pub category: Option<String>,
+}
+
+/// Changes to an existing role. Fields left at None are kept as they are
+#[derive(AsChangeset, Default, Debug)]
+#[table_name = "roles"]
+pub struct RoleChanges {
+    pub title: Option<String>,
+    pub emoji: Option<Emoji>,
+    pub priority: Option<i16>,
+    pub active: Option<bool>,
 }
 
 #[derive(Identifiable, Queryable, Associations, PartialEq, Debug)]
@@ -132,6 +339,8 @@ pub(super) struct NewRole {
 pub struct SignupRole {
     pub signup_id: i32,
     pub role_id: i32,
+    /// Preference of the user. 1 is the main role
+    pub rank: i16,
 }
 
 #[derive(Insertable, Debug)]
@@ -139,6 +348,7 @@ pub struct SignupRole {
 pub(super) struct NewSignupRole {
     pub signup_id: i32,
     pub role_id: i32,
+    pub rank: i16,
 }
 
 #[derive(Identifiable, Queryable, Associations, PartialEq, Debug)]
@@ -158,20 +368,196 @@ pub(super) struct NewTrainingRole {
     pub role_id: i32,
 }
 
-#[derive(Identifiable, Queryable, PartialEq, Debug)]
+/// Minimum and maximum amount of sign-ups for a role or all roles of a category
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
+#[belongs_to(Training)]
+#[table_name = "training_requirements"]
+pub struct TrainingRequirement {
+    pub id: i32,
+    pub training_id: i32,
+    pub role_id: Option<i32>,
+    pub category: Option<String>,
+    pub min_count: i32,
+    pub max_count: Option<i32>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "training_requirements"]
+pub(super) struct NewTrainingRequirement {
+    pub training_id: i32,
+    pub role_id: Option<i32>,
+    pub category: Option<String>,
+    pub min_count: i32,
+    pub max_count: Option<i32>,
+}
+
+/// How many sign-ups currently fill a requirement
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequirementStatus {
+    /// The repr of the role or the name of the category
+    pub name: String,
+    pub min_count: i32,
+    pub max_count: Option<i32>,
+    pub count: i64,
+}
+
+impl RequirementStatus {
+    /// Every sign-up counts with a single role: the first of its roles, main role
+    /// first, that a requirement still misses, otherwise its main role.
+    /// `roles` are all roles of the training, `signups` the role ids of each
+    /// sign-up that is not waitlisted, in the order they signed up
+    pub fn compute(
+        requirements: &[TrainingRequirement],
+        roles: &[Role],
+        signups: &[Vec<i32>],
+    ) -> Vec<Self> {
+        let matching = requirements
+            .iter()
+            .map(|req| {
+                roles
+                    .iter()
+                    .filter(|r| match (&req.role_id, &req.category) {
+                        (Some(id), _) => r.id == *id,
+                        (None, Some(cat)) => r.category.as_ref() == Some(cat),
+                        (None, None) => false,
+                    })
+                    .map(|r| r.id)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut counts = vec![0; requirements.len()];
+        for role_ids in signups {
+            let missing = |id: &i32| {
+                requirements
+                    .iter()
+                    .zip(&matching)
+                    .zip(&counts)
+                    .any(|((req, m), c)| m.contains(id) && *c < req.min_count as i64)
+            };
+            let role = role_ids
+                .iter()
+                .find(|id| missing(id))
+                .or_else(|| role_ids.first())
+                .copied();
+            if let Some(role) = role {
+                for (m, c) in matching.iter().zip(counts.iter_mut()) {
+                    if m.contains(&role) {
+                        *c += 1;
+                    }
+                }
+            }
+        }
+
+        requirements
+            .iter()
+            .zip(counts)
+            .map(|(req, count)| {
+                let name = match (&req.role_id, &req.category) {
+                    (Some(id), _) => roles
+                        .iter()
+                        .find(|r| r.id == *id)
+                        .map(|r| r.repr.clone())
+                        .unwrap_or_else(|| id.to_string()),
+                    (None, cat) => cat.clone().unwrap_or_default(),
+                };
+                RequirementStatus {
+                    name,
+                    min_count: req.min_count,
+                    max_count: req.max_count,
+                    count,
+                }
+            })
+            .collect()
+    }
+
+    pub fn is_filled(&self) -> bool {
+        self.count >= self.min_count as i64
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        matches!(self.max_count, Some(max) if self.count > max as i64)
+    }
+}
+
+impl fmt::Display for RequirementStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.is_exceeded() {
+            "⚠️"
+        } else if self.is_filled() {
+            "✅"
+        } else {
+            "❌"
+        };
+        match self.max_count {
+            Some(max) => write!(
+                f,
+                "{} {} {}/{}-{}",
+                status, self.name, self.count, self.min_count, max
+            ),
+            None => write!(
+                f,
+                "{} {} {}/{}",
+                status, self.name, self.count, self.min_count
+            ),
+        }
+    }
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug, Clone)]
 #[table_name = "tiers"]
 pub struct Tier {
     pub id: i32,
     pub name: String,
+    pub guild_id: i64,
+    /// The next higher tier. Members of it also qualify for this one
+    pub parent_id: Option<i32>,
+}
+
+impl Tier {
+    /// The tier itself followed by all tiers above it, closest first.
+    /// Stops at a tier that was already visited
+    pub fn with_parents<'a>(&'a self, tiers: &'a [Tier]) -> Vec<&'a Tier> {
+        let mut chain = vec![self];
+        let mut parent_id = self.parent_id;
+        while let Some(id) = parent_id {
+            match tiers.iter().find(|t| t.id == id) {
+                Some(parent) if !chain.iter().any(|t| t.id == id) => {
+                    chain.push(parent);
+                    parent_id = parent.parent_id;
+                }
+                _ => break,
+            }
+        }
+        chain
+    }
+
+    /// The discord roles that qualify for this tier, including the ones of all tiers above
+    pub fn inherited_mappings(&self, tiers: &[Tier], mappings: &[TierMapping]) -> Vec<TierMapping> {
+        let chain = self.with_parents(tiers);
+        let mut inherited: Vec<TierMapping> = Vec::new();
+        for tier in chain {
+            for m in mappings.iter().filter(|m| m.tier_id == tier.id) {
+                if !inherited
+                    .iter()
+                    .any(|i| i.discord_role_id == m.discord_role_id)
+                {
+                    inherited.push(m.clone());
+                }
+            }
+        }
+        inherited
+    }
 }
 
 #[derive(Insertable, Debug)]
 #[table_name = "tiers"]
 pub(super) struct NewTier {
     pub name: String,
+    pub guild_id: i64,
 }
 
-#[derive(Identifiable, Queryable, Associations, PartialEq, Debug)]
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
 #[table_name = "tier_mappings"]
 #[belongs_to(Tier)]
 #[primary_key(tier_id, discord_role_id)]
@@ -187,23 +573,172 @@ pub(super) struct NewTierMapping {
     pub discord_role_id: i64,
 }
 
-#[derive(Queryable, Insertable, Debug)]
-#[table_name = "config"]
+/// Promotes members that attended enough finished trainings of `from_tier_id`
+/// to the discord role of the tier mapping (`tier_id`, `discord_role_id`)
+#[derive(Identifiable, Queryable, PartialEq, Debug, Clone)]
+#[table_name = "promotion_rules"]
+pub struct PromotionRule {
+    pub id: i32,
+    pub tier_id: i32,
+    pub discord_role_id: i64,
+    pub from_tier_id: i32,
+    /// Only trainings with this main role count
+    pub role_id: Option<i32>,
+    pub min_count: i32,
+    /// Assigns the role without waiting for a squadmaker
+    pub automatic: bool,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "promotion_rules"]
+pub(super) struct NewPromotionRule {
+    pub tier_id: i32,
+    pub discord_role_id: i64,
+    pub from_tier_id: i32,
+    pub role_id: Option<i32>,
+    pub min_count: i32,
+    pub automatic: bool,
+}
+
+#[derive(Debug, DbEnum, PartialEq, Clone, Serialize)]
+#[DieselType = "Promotion_state"]
+pub enum PromotionState {
+    Pending,
+    Approved,
+    Denied,
+}
+
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
+#[table_name = "promotions"]
+#[belongs_to(PromotionRule, foreign_key = "rule_id")]
+#[belongs_to(User)]
+pub struct Promotion {
+    pub id: i32,
+    pub rule_id: i32,
+    pub user_id: i32,
+    pub state: PromotionState,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "promotions"]
+pub(super) struct NewPromotion {
+    pub rule_id: i32,
+    pub user_id: i32,
+    pub state: PromotionState,
+}
+
+/// A member asking for a tier. Decided on like a promotion
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
+#[table_name = "tier_requests"]
+#[belongs_to(User)]
+#[belongs_to(Tier)]
+pub struct TierRequest {
+    pub id: i32,
+    pub user_id: i32,
+    pub tier_id: i32,
+    pub justification: Option<String>,
+    pub logs: Option<String>,
+    pub state: PromotionState,
+    pub created_at: NaiveDateTime,
+    pub decided_at: Option<NaiveDateTime>,
+    /// Discord id of the squadmaker
+    pub decided_by: Option<i64>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "tier_requests"]
+pub(super) struct NewTierRequest {
+    pub user_id: i32,
+    pub tier_id: i32,
+    pub justification: Option<String>,
+    pub logs: Option<String>,
+}
+
+/// A config value of a guild. The guild is left out since configs
+/// are always loaded and saved for a specific guild
+#[derive(Queryable, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     pub name: String,
     pub value: String,
 }
 
-#[derive(Identifiable, Queryable, Associations, Hash, PartialEq, Eq, Debug, Serialize)]
+#[derive(Insertable, Debug)]
+#[table_name = "config"]
+pub(super) struct NewConfig<'a> {
+    pub guild_id: i64,
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+/// What kind of content a boss belongs to. Together with the category number
+/// it groups bosses, eg raid wing 3 or the strikes of End of Dragons
+#[derive(
+    Debug, DbEnum, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize,
+)]
+#[DieselType = "Boss_category"]
+#[serde(rename_all = "lowercase")]
+pub enum BossCategory {
+    Raid,
+    Strike,
+    Fractal,
+    Other,
+}
+
+impl BossCategory {
+    /// Full name of the group, eg "Wing 3" or "Strikes: End of Dragons"
+    pub fn group_name(&self, number: i32) -> String {
+        match (self, number) {
+            (Self::Raid, n) => format!("Wing {}", n),
+            (Self::Strike, 1) => String::from("Strikes: Icebrood Saga"),
+            (Self::Strike, 2) => String::from("Strikes: End of Dragons"),
+            (Self::Strike, n) => format!("Strikes {}", n),
+            (Self::Fractal, _) => String::from("Fractal CMs"),
+            (Self::Other, _) => String::from("Other"),
+        }
+    }
+
+    /// Short name of the group for tight spaces, eg "W3" or "EoD"
+    pub fn group_short_name(&self, number: i32) -> String {
+        match (self, number) {
+            (Self::Raid, n) => format!("W{}", n),
+            (Self::Strike, 1) => String::from("IBS"),
+            (Self::Strike, 2) => String::from("EoD"),
+            (Self::Strike, n) => format!("S{}", n),
+            (Self::Fractal, _) => String::from("CM"),
+            (Self::Other, _) => String::from("Other"),
+        }
+    }
+}
+
+#[derive(Identifiable, Queryable, Associations, Hash, PartialEq, Eq, Debug, Serialize, Clone)]
 #[table_name = "training_bosses"]
 pub struct TrainingBoss {
     pub id: i32,
     pub repr: String,
     pub name: String,
-    pub wing: i32,
+    /// The raid wing or strike expansion. 0 for the other categories
+    pub category_number: i32,
     pub position: i32,
-    pub emoji: i64,
+    pub emoji: Emoji,
     pub url: Option<String>,
+    pub guild_id: i64,
+    pub category: BossCategory,
+}
+
+impl TrainingBoss {
+    /// Groups the bosses by category and orders them within it
+    pub fn sort_key(&self) -> (BossCategory, i32, i32) {
+        (self.category, self.category_number, self.position)
+    }
+
+    pub fn group(&self) -> (BossCategory, i32) {
+        (self.category, self.category_number)
+    }
+
+    pub fn group_name(&self) -> String {
+        self.category.group_name(self.category_number)
+    }
 }
 
 #[derive(Insertable, Associations, Debug)]
@@ -211,9 +746,23 @@ pub struct TrainingBoss {
 pub struct NewTrainingBoss {
     pub repr: String,
     pub name: String,
-    pub wing: i32,
+    pub category: BossCategory,
+    pub category_number: i32,
     pub position: i32,
-    pub emoji: i64,
+    pub emoji: Emoji,
+    pub url: Option<String>,
+    pub guild_id: i64,
+}
+
+/// Changes to an existing boss. Fields left at None are kept as they are
+#[derive(AsChangeset, Default, Debug)]
+#[table_name = "training_bosses"]
+pub struct TrainingBossChanges {
+    pub name: Option<String>,
+    pub category: Option<BossCategory>,
+    pub category_number: Option<i32>,
+    pub position: Option<i32>,
+    pub emoji: Option<Emoji>,
     pub url: Option<String>,
 }
 
@@ -223,3 +772,118 @@ pub struct TrainingBossMapping {
     pub training_id: i32,
     pub training_boss_id: i32,
 }
+
+/// A boss of a finished training the user attended
+#[derive(Identifiable, Queryable, Associations, PartialEq, Debug, Clone)]
+#[table_name = "boss_completions"]
+#[belongs_to(User)]
+#[belongs_to(TrainingBoss)]
+pub struct BossCompletion {
+    pub id: i32,
+    pub user_id: i32,
+    pub training_boss_id: i32,
+    /// None once the training was deleted
+    pub training_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "boss_completions"]
+pub(super) struct NewBossCompletion {
+    pub user_id: i32,
+    pub training_boss_id: i32,
+    pub training_id: i32,
+}
+
+/// Database independent representation of the bot configuration.
+/// Everything is referenced by repr or name instead of by id
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BotConfiguration {
+    pub roles: Vec<RoleConfig>,
+    pub training_bosses: Vec<TrainingBossConfig>,
+    pub tiers: Vec<TierConfig>,
+    pub tier_mappings: Vec<TierMappingConfig>,
+    pub config: Vec<Config>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoleConfig {
+    pub title: String,
+    pub repr: String,
+    pub emoji: Emoji,
+    pub priority: i16,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrainingBossConfig {
+    pub repr: String,
+    pub name: String,
+    /// Exports from before categories only had a wing
+    #[serde(default = "default_boss_category")]
+    pub category: BossCategory,
+    #[serde(alias = "wing")]
+    pub category_number: i32,
+    pub position: i32,
+    pub emoji: Emoji,
+    pub url: Option<String>,
+}
+
+fn default_boss_category() -> BossCategory {
+    BossCategory::Raid
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "TierConfigFormat")]
+pub struct TierConfig {
+    pub name: String,
+    /// Name of the next higher tier
+    pub parent: Option<String>,
+}
+
+/// Exports from before tier parents only had the names
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TierConfigFormat {
+    Name(String),
+    Tier {
+        name: String,
+        #[serde(default)]
+        parent: Option<String>,
+    },
+}
+
+impl From<TierConfigFormat> for TierConfig {
+    fn from(format: TierConfigFormat) -> Self {
+        match format {
+            TierConfigFormat::Name(name) => TierConfig { name, parent: None },
+            TierConfigFormat::Tier { name, parent } => TierConfig { name, parent },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TierMappingConfig {
+    pub tier: String,
+    pub discord_role_id: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Emoji;
+
+    #[test]
+    fn only_real_emojis_are_unicode_emojis() {
+        for valid in ["🐉", "👍🏽", " ⚔️ ", "🏳️‍🌈"] {
+            assert!(Emoji::unicode(valid).is_some(), "{} was rejected", valid);
+        }
+        for invalid in ["", "é", "日本語", "🐉🐉", ":dragon:", "123"] {
+            assert!(
+                Emoji::unicode(invalid).is_none(),
+                "{} was accepted",
+                invalid
+            );
+        }
+    }
+}
@@ -1,5 +1,46 @@
 #![allow(unused_imports)]
 
+table! {
+    use diesel::sql_types::*;
+    use crate::db::*;
+
+    /// Representation of the `boss_completions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    boss_completions (id) {
+        /// The `id` column of the `boss_completions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `user_id` column of the `boss_completions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `training_boss_id` column of the `boss_completions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        training_boss_id -> Int4,
+        /// The `training_id` column of the `boss_completions` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        training_id -> Nullable<Int4>,
+        /// The `created_at` column of the `boss_completions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
     use crate::db::*;
@@ -7,7 +48,7 @@ table! {
     /// Representation of the `config` table.
     ///
     /// (Automatically generated by Diesel.)
-    config (name) {
+    config (guild_id, name) {
         /// The `name` column of the `config` table.
         ///
         /// Its SQL type is `Text`.
@@ -20,6 +61,123 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         value -> Text,
+        /// The `guild_id` column of the `config` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        guild_id -> Int8,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::*;
+
+    /// Representation of the `guilds` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    guilds (id) {
+        /// The `id` column of the `guilds` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int8,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::*;
+
+    /// Representation of the `promotion_rules` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    promotion_rules (id) {
+        /// The `id` column of the `promotion_rules` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `tier_id` column of the `promotion_rules` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        tier_id -> Int4,
+        /// The `discord_role_id` column of the `promotion_rules` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        discord_role_id -> Int8,
+        /// The `from_tier_id` column of the `promotion_rules` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        from_tier_id -> Int4,
+        /// The `role_id` column of the `promotion_rules` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        role_id -> Nullable<Int4>,
+        /// The `min_count` column of the `promotion_rules` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        min_count -> Int4,
+        /// The `automatic` column of the `promotion_rules` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        automatic -> Bool,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::*;
+
+    /// Representation of the `promotions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    promotions (id) {
+        /// The `id` column of the `promotions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `rule_id` column of the `promotions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        rule_id -> Int4,
+        /// The `user_id` column of the `promotions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `state` column of the `promotions` table.
+        ///
+        /// Its SQL type is `Promotion_state`.
+        ///
+        /// (Automatically generated by Diesel.)
+        state -> Promotion_state,
+        /// The `created_at` column of the `promotions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
     }
 }
 
@@ -51,10 +209,10 @@ table! {
         repr -> Text,
         /// The `emoji` column of the `roles` table.
         ///
-        /// Its SQL type is `Int8`.
+        /// Its SQL type is `Text`.
         ///
         /// (Automatically generated by Diesel.)
-        emoji -> Int8,
+        emoji -> Text,
         /// The `active` column of the `roles` table.
         ///
         /// Its SQL type is `Bool`.
@@ -67,6 +225,18 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         priority -> Int2,
+        /// The `guild_id` column of the `roles` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        guild_id -> Int8,
+        /// The `category` column of the `roles` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        category -> Nullable<Text>,
     }
 }
 
@@ -136,6 +306,12 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         role_id -> Int4,
+        /// The `rank` column of the `signup_roles` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        rank -> Int2,
     }
 }
 
@@ -171,6 +347,18 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         comment -> Nullable<Text>,
+        /// The `created_at` column of the `signups` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `updated_at` column of the `signups` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamp,
     }
 }
 
@@ -197,6 +385,71 @@ table! {
     }
 }
 
+table! {
+    use diesel::sql_types::*;
+    use crate::db::*;
+
+    /// Representation of the `tier_requests` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    tier_requests (id) {
+        /// The `id` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `user_id` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `tier_id` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        tier_id -> Int4,
+        /// The `justification` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        justification -> Nullable<Text>,
+        /// The `logs` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        logs -> Nullable<Text>,
+        /// The `state` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Promotion_state`.
+        ///
+        /// (Automatically generated by Diesel.)
+        state -> Promotion_state,
+        /// The `created_at` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamp,
+        /// The `decided_at` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        decided_at -> Nullable<Timestamp>,
+        /// The `decided_by` column of the `tier_requests` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        decided_by -> Nullable<Int8>,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
     use crate::db::*;
@@ -217,6 +470,18 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         name -> Text,
+        /// The `guild_id` column of the `tiers` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        guild_id -> Int8,
+        /// The `parent_id` column of the `tiers` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        parent_id -> Nullable<Int4>,
     }
 }
 
@@ -269,12 +534,12 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         name -> Text,
-        /// The `wing` column of the `training_bosses` table.
+        /// The `category_number` column of the `training_bosses` table.
         ///
         /// Its SQL type is `Int4`.
         ///
         /// (Automatically generated by Diesel.)
-        wing -> Int4,
+        category_number -> Int4,
         /// The `position` column of the `training_bosses` table.
         ///
         /// Its SQL type is `Int4`.
@@ -283,16 +548,75 @@ table! {
         position -> Int4,
         /// The `emoji` column of the `training_bosses` table.
         ///
-        /// Its SQL type is `Int8`.
+        /// Its SQL type is `Text`.
         ///
         /// (Automatically generated by Diesel.)
-        emoji -> Int8,
+        emoji -> Text,
         /// The `url` column of the `training_bosses` table.
         ///
         /// Its SQL type is `Nullable<Text>`.
         ///
         /// (Automatically generated by Diesel.)
         url -> Nullable<Text>,
+        /// The `guild_id` column of the `training_bosses` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        guild_id -> Int8,
+        /// The `category` column of the `training_bosses` table.
+        ///
+        /// Its SQL type is `Boss_category`.
+        ///
+        /// (Automatically generated by Diesel.)
+        category -> Boss_category,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::*;
+
+    /// Representation of the `training_requirements` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    training_requirements (id) {
+        /// The `id` column of the `training_requirements` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `training_id` column of the `training_requirements` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        training_id -> Int4,
+        /// The `role_id` column of the `training_requirements` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        role_id -> Nullable<Int4>,
+        /// The `category` column of the `training_requirements` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        category -> Nullable<Text>,
+        /// The `min_count` column of the `training_requirements` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        min_count -> Int4,
+        /// The `max_count` column of the `training_requirements` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        max_count -> Nullable<Int4>,
     }
 }
 
@@ -363,6 +687,94 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         board_message_id -> Nullable<Int8>,
+        /// The `guild_id` column of the `trainings` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        guild_id -> Int8,
+        /// The `max_participants` column of the `trainings` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        max_participants -> Nullable<Int4>,
+        /// The `announcement_channel_id` column of the `trainings` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        announcement_channel_id -> Nullable<Int8>,
+        /// The `announcement_message_id` column of the `trainings` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        announcement_message_id -> Nullable<Int8>,
+        /// The `thread_id` column of the `trainings` table.
+        ///
+        /// Its SQL type is `Nullable<Int8>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        thread_id -> Nullable<Int8>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::*;
+
+    /// Representation of the `user_default_roles` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    user_default_roles (user_id, role_id) {
+        /// The `user_id` column of the `user_default_roles` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `role_id` column of the `user_default_roles` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        role_id -> Int4,
+        /// The `rank` column of the `user_default_roles` table.
+        ///
+        /// Its SQL type is `Int2`.
+        ///
+        /// (Automatically generated by Diesel.)
+        rank -> Int2,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::*;
+
+    /// Representation of the `user_defaults` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    user_defaults (user_id, guild_id) {
+        /// The `user_id` column of the `user_defaults` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user_id -> Int4,
+        /// The `guild_id` column of the `user_defaults` table.
+        ///
+        /// Its SQL type is `Int8`.
+        ///
+        /// (Automatically generated by Diesel.)
+        guild_id -> Int8,
+        /// The `comment` column of the `user_defaults` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        comment -> Nullable<Text>,
     }
 }
 
@@ -395,6 +807,14 @@ table! {
     }
 }
 
+joinable!(boss_completions -> training_bosses (training_boss_id));
+joinable!(boss_completions -> trainings (training_id));
+joinable!(boss_completions -> users (user_id));
+joinable!(config -> guilds (guild_id));
+joinable!(promotion_rules -> roles (role_id));
+joinable!(promotions -> promotion_rules (rule_id));
+joinable!(promotions -> users (user_id));
+joinable!(roles -> guilds (guild_id));
 joinable!(signup_boss_preference_mappings -> signups (signup_id));
 joinable!(signup_boss_preference_mappings -> training_bosses (training_boss_id));
 joinable!(signup_roles -> roles (role_id));
@@ -402,24 +822,43 @@ joinable!(signup_roles -> signups (signup_id));
 joinable!(signups -> trainings (training_id));
 joinable!(signups -> users (user_id));
 joinable!(tier_mappings -> tiers (tier_id));
+joinable!(tier_requests -> tiers (tier_id));
+joinable!(tier_requests -> users (user_id));
+joinable!(tiers -> guilds (guild_id));
 joinable!(training_boss_mappings -> training_bosses (training_boss_id));
 joinable!(training_boss_mappings -> trainings (training_id));
+joinable!(training_bosses -> guilds (guild_id));
+joinable!(training_requirements -> roles (role_id));
+joinable!(training_requirements -> trainings (training_id));
 joinable!(training_roles -> roles (role_id));
 joinable!(training_roles -> trainings (training_id));
+joinable!(trainings -> guilds (guild_id));
 joinable!(trainings -> tiers (tier_id));
+joinable!(user_default_roles -> roles (role_id));
+joinable!(user_default_roles -> users (user_id));
+joinable!(user_defaults -> guilds (guild_id));
+joinable!(user_defaults -> users (user_id));
 
 allow_tables_to_appear_in_same_query!(
+    boss_completions,
     config,
+    guilds,
+    promotion_rules,
+    promotions,
     roles,
     signup_board_channels,
     signup_boss_preference_mappings,
     signup_roles,
     signups,
     tier_mappings,
+    tier_requests,
     tiers,
     training_boss_mappings,
     training_bosses,
+    training_requirements,
     training_roles,
     trainings,
+    user_default_roles,
+    user_defaults,
     users,
 );
@@ -0,0 +1,351 @@
+//! # discord
+//! A small facade over the discord gateway and http api. It only covers what the bot does
+//! outside of interaction responses, like checking roles and maintaining messages. Code
+//! depending on [`DiscordFacade`] instead of the serenity Context can be tested with the
+//! recording mock
+
+use anyhow::Result;
+use serenity::{
+    async_trait,
+    builder::{CreateComponents, CreateEmbed},
+    client::Context,
+    json::{json, JsonMap},
+    model::{
+        channel::{Channel, ChannelType},
+        id::{ChannelId, GuildId, MessageId, RoleId, UserId},
+    },
+};
+
+/// Text, embeds and components of a message, in the form they are send to discord
+#[derive(Debug, Clone, Default)]
+pub struct MessageContent {
+    /// Plain text above the embeds. Only mentions in here notify the users
+    pub content: String,
+    pub embeds: Vec<CreateEmbed>,
+    pub components: CreateComponents,
+}
+
+#[async_trait]
+pub trait DiscordFacade: Send + Sync {
+    /// All discord roles of the guild member
+    async fn member_roles(&self, guild_id: GuildId, user_id: UserId) -> Result<Vec<RoleId>>;
+
+    /// Sends a new message to the channel and returns its id
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        content: MessageContent,
+    ) -> Result<MessageId>;
+
+    /// Replaces all embeds and components of an existing message. The text is only
+    /// replaced if there is a new one
+    async fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: MessageContent,
+    ) -> Result<()>;
+
+    /// Sends a direct message to the user
+    async fn send_dm(&self, user_id: UserId, content: MessageContent) -> Result<()>;
+
+    /// Gives the guild member the discord role
+    async fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()>;
+
+    /// Creates a thread starting with the message. In text channels the message is
+    /// posted first, in forum channels it becomes the post. Returns the thread id
+    async fn create_thread(
+        &self,
+        channel_id: ChannelId,
+        name: String,
+        content: MessageContent,
+    ) -> Result<ChannelId>;
+
+    /// Adds the guild member to the thread
+    async fn add_thread_member(&self, thread_id: ChannelId, user_id: UserId) -> Result<()>;
+
+    /// Archives the thread. Sending a message to it opens it again
+    async fn archive_thread(&self, thread_id: ChannelId) -> Result<()>;
+}
+
+#[async_trait]
+impl DiscordFacade for Context {
+    async fn member_roles(&self, guild_id: GuildId, user_id: UserId) -> Result<Vec<RoleId>> {
+        if let Some(member) = self.cache.member(guild_id, user_id) {
+            return Ok(member.roles);
+        }
+        // Only members that are not cached are requested over http
+        let member = self.http.get_member(guild_id.0, user_id.0).await?;
+        Ok(member.roles)
+    }
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        content: MessageContent,
+    ) -> Result<MessageId> {
+        let msg = channel_id
+            .send_message(self, |m| {
+                if !content.content.is_empty() {
+                    m.content(content.content);
+                }
+                m.set_embeds(content.embeds);
+                m.set_components(content.components)
+            })
+            .await?;
+        Ok(msg.id)
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: MessageContent,
+    ) -> Result<()> {
+        channel_id
+            .edit_message(self, message_id, |m| {
+                if !content.content.is_empty() {
+                    m.content(content.content);
+                }
+                m.set_embeds(content.embeds);
+                m.set_components(content.components)
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn send_dm(&self, user_id: UserId, content: MessageContent) -> Result<()> {
+        user_id
+            .create_dm_channel(self)
+            .await?
+            .send_message(self, |m| {
+                if !content.content.is_empty() {
+                    m.content(content.content);
+                }
+                m.set_embeds(content.embeds);
+                m.set_components(content.components)
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()> {
+        self.http
+            .add_member_role(guild_id.0, user_id.0, role_id.0, Some("Promotion"))
+            .await?;
+        Ok(())
+    }
+
+    async fn create_thread(
+        &self,
+        channel_id: ChannelId,
+        name: String,
+        content: MessageContent,
+    ) -> Result<ChannelId> {
+        let is_forum = match channel_id.to_channel(self).await? {
+            Channel::Guild(c) => c.kind == ChannelType::Forum,
+            _ => false,
+        };
+        if !is_forum {
+            let message_id = self.send_message(channel_id, content).await?;
+            let thread = channel_id
+                .create_public_thread(self, message_id, |t| t.name(name))
+                .await?;
+            return Ok(thread.id);
+        }
+
+        // serenity has no builder for forum posts. They are created on the same
+        // route as private threads, with the first message included
+        let mut map = JsonMap::new();
+        map.insert(String::from("name"), json!(name));
+        map.insert(
+            String::from("message"),
+            json!({
+                "content": content.content,
+                "embeds": content.embeds.into_iter().map(|e| e.0).collect::<Vec<_>>(),
+            }),
+        );
+        let thread = self.http.create_private_thread(channel_id.0, &map).await?;
+        Ok(thread.id)
+    }
+
+    async fn add_thread_member(&self, thread_id: ChannelId, user_id: UserId) -> Result<()> {
+        thread_id.add_thread_member(self, user_id).await?;
+        Ok(())
+    }
+
+    async fn archive_thread(&self, thread_id: ChannelId) -> Result<()> {
+        thread_id.edit_thread(self, |t| t.archived(true)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use anyhow::bail;
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+    };
+
+    #[derive(Debug, Clone)]
+    pub enum DiscordCall {
+        MemberRoles(GuildId, UserId),
+        SendMessage(ChannelId, MessageContent),
+        EditMessage(ChannelId, MessageId, MessageContent),
+        SendDm(UserId, MessageContent),
+        AddMemberRole(GuildId, UserId, RoleId),
+        CreateThread(ChannelId, String, MessageContent),
+        AddThreadMember(ChannelId, UserId),
+        ArchiveThread(ChannelId),
+    }
+
+    /// Answers role checks from a fixed set of member roles and records every call
+    #[derive(Debug, Default)]
+    pub struct RecordingDiscord {
+        pub member_roles: HashMap<UserId, Vec<RoleId>>,
+        /// Sending to these channels fails, as if the bot lacked the permission
+        pub broken_channels: Vec<ChannelId>,
+        /// Changing these members fails, as if they left the server
+        pub broken_members: Vec<UserId>,
+        calls: Mutex<Vec<DiscordCall>>,
+        next_message_id: AtomicU64,
+    }
+
+    impl RecordingDiscord {
+        pub fn give_role(&mut self, user_id: UserId, role_id: RoleId) {
+            self.member_roles.entry(user_id).or_default().push(role_id);
+        }
+
+        pub fn calls(&self) -> Vec<DiscordCall> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        /// Content of the last edit of the message, if it was edited at all
+        pub fn last_edit(
+            &self,
+            channel_id: ChannelId,
+            message_id: MessageId,
+        ) -> Option<MessageContent> {
+            self.calls().into_iter().rev().find_map(|call| match call {
+                DiscordCall::EditMessage(c, m, content) if c == channel_id && m == message_id => {
+                    Some(content)
+                }
+                _ => None,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl DiscordFacade for RecordingDiscord {
+        async fn member_roles(&self, guild_id: GuildId, user_id: UserId) -> Result<Vec<RoleId>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(DiscordCall::MemberRoles(guild_id, user_id));
+            Ok(self.member_roles.get(&user_id).cloned().unwrap_or_default())
+        }
+
+        async fn send_message(
+            &self,
+            channel_id: ChannelId,
+            content: MessageContent,
+        ) -> Result<MessageId> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(DiscordCall::SendMessage(channel_id, content));
+            if self.broken_channels.contains(&channel_id) {
+                bail!("Missing access to {}", channel_id);
+            }
+            Ok(MessageId::from(
+                self.next_message_id.fetch_add(1, Ordering::Relaxed) + 1,
+            ))
+        }
+
+        async fn edit_message(
+            &self,
+            channel_id: ChannelId,
+            message_id: MessageId,
+            content: MessageContent,
+        ) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(DiscordCall::EditMessage(channel_id, message_id, content));
+            Ok(())
+        }
+
+        async fn send_dm(&self, user_id: UserId, content: MessageContent) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(DiscordCall::SendDm(user_id, content));
+            Ok(())
+        }
+
+        async fn add_member_role(
+            &self,
+            guild_id: GuildId,
+            user_id: UserId,
+            role_id: RoleId,
+        ) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(DiscordCall::AddMemberRole(guild_id, user_id, role_id));
+            if self.broken_members.contains(&user_id) {
+                bail!("Unknown member {}", user_id);
+            }
+            Ok(())
+        }
+
+        async fn create_thread(
+            &self,
+            channel_id: ChannelId,
+            name: String,
+            content: MessageContent,
+        ) -> Result<ChannelId> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(DiscordCall::CreateThread(channel_id, name, content));
+            Ok(ChannelId::from(
+                self.next_message_id.fetch_add(1, Ordering::Relaxed) + 1,
+            ))
+        }
+
+        async fn add_thread_member(&self, thread_id: ChannelId, user_id: UserId) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(DiscordCall::AddThreadMember(thread_id, user_id));
+            if self.broken_members.contains(&user_id) {
+                bail!("Unknown member {}", user_id);
+            }
+            Ok(())
+        }
+
+        async fn archive_thread(&self, thread_id: ChannelId) -> Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(DiscordCall::ArchiveThread(thread_id));
+            Ok(())
+        }
+    }
+}
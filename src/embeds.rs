@@ -1,9 +1,8 @@
 use crate::db;
 use chrono::{Duration, NaiveDateTime};
-use serenity::{
-    builder::{CreateEmbed, CreateEmbedAuthor},
-    model::{id::EmojiId, mention::Mention},
-};
+use itertools::Itertools;
+use serenity::builder::{CreateEmbed, CreateEmbedAuthor};
+use serenity_tools::builder::CreateEmbedExt;
 
 const EMBED_AUTHOR_ICON_URL: &str = "https://cdn.discordapp.com/avatars/512706205647372302/eb7a7f2de9a97006e8217b73ab5c7836.webp?size=128";
 const EMBED_AUTHOR_NAME: &str = "Crossroads Bot";
@@ -68,6 +67,25 @@ pub(crate) fn field_training_date(training: &db::Training) -> (String, String, b
     )
 }
 
+/// Boss with its emoji, linked to more information if there is any
+pub fn boss_link(boss: &db::TrainingBoss) -> String {
+    match &boss.url {
+        Some(l) => format!("{} | [{}]({})", boss.emoji, boss.name, l),
+        None => format!("{} | {}", boss.emoji, boss.name),
+    }
+}
+
+/// Adds one field per boss category. The bosses have to be sorted by category
+pub fn embed_add_bosses<F>(e: &mut CreateEmbed, bosses: &[db::TrainingBoss], f: F)
+where
+    F: Fn(&db::TrainingBoss) -> String,
+{
+    for (_, group) in &bosses.iter().group_by(|b| b.group()) {
+        let group = group.collect::<Vec<_>>();
+        e.fields_chunked_fmt(&group, |b| f(b), group[0].group_name(), false, 8);
+    }
+}
+
 pub fn embed_add_roles(e: &mut CreateEmbed, roles: &[db::Role], inline: bool, reprs: bool) {
     let title_width = roles
         .iter()
@@ -85,18 +103,14 @@ pub fn embed_add_roles(e: &mut CreateEmbed, roles: &[db::Role], inline: bool, re
                         .fold(usize::MIN, std::cmp::max);
                     format!(
                         "{} `| {:^rwidth$} |` `| {:^twidth$} |`",
-                        Mention::from(EmojiId::from(r.emoji as u64)),
+                        r.emoji,
                         &r.repr,
                         &r.title,
                         rwidth = repr_width,
                         twidth = title_width
                     )
                 } else {
-                    format!(
-                        "{} | {} ",
-                        Mention::from(EmojiId::from(r.emoji as u64)),
-                        &r.title,
-                    )
+                    format!("{} | {} ", r.emoji, &r.title,)
                 }
             })
             .collect::<Vec<_>>()
@@ -1,17 +1,19 @@
 use std::sync::Arc;
 
-use crate::logging::*;
+use crate::{discord::MessageContent, logging::*};
 
 use serenity::{
-    builder::{CreateActionRow, CreateButton},
+    builder::{CreateActionRow, CreateButton, CreateEmbed},
     model::{
         channel::ReactionType,
+        id::RoleId,
         interactions::message_component::{ButtonStyle, MessageComponentInteraction},
     },
     prelude::*,
 };
 
 mod manage_sign_up;
+mod review;
 
 const COMPONENT_MANAGE_SIGNUPS: &str = "SIGN UP / SIGN OUT / EDIT SIGN-UP";
 const MEMO_EMOJI: char = '📝';
@@ -32,6 +34,7 @@ impl std::error::Error for GlobalInteractionParseError {}
 #[non_exhaustive]
 pub enum GlobalInteraction {
     Overview(OverviewMessageInteraction),
+    Review(ReviewInteraction),
 }
 
 impl std::str::FromStr for GlobalInteraction {
@@ -41,6 +44,9 @@ impl std::str::FromStr for GlobalInteraction {
         if let Ok(bgi) = s.parse::<OverviewMessageInteraction>() {
             return Ok(Self::Overview(bgi));
         }
+        if let Ok(ri) = s.parse::<ReviewInteraction>() {
+            return Ok(Self::Review(ri));
+        }
         Err(GlobalInteractionParseError {})
     }
 }
@@ -49,6 +55,7 @@ impl std::fmt::Display for GlobalInteraction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Overview(bgi) => write!(f, "{}", bgi),
+            Self::Review(ri) => write!(f, "{}", ri),
         }
     }
 }
@@ -84,6 +91,119 @@ impl std::fmt::Display for OverviewMessageInteraction {
     }
 }
 
+/// What a review request is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewKind {
+    Promotion,
+    TierRequest,
+}
+
+impl ReviewKind {
+    fn prefix(&self) -> &'static str {
+        match self {
+            Self::Promotion => "promotion",
+            Self::TierRequest => "tierrequest",
+        }
+    }
+}
+
+/// Buttons of a promotion or tier request. The requests can be decided on long after
+/// they were posted, so the id of the request is part of the custom id. Approving a
+/// tier request also carries the discord role the squadmaker picked
+#[derive(Debug, Clone)]
+pub enum ReviewInteraction {
+    Approve(ReviewKind, i32, Option<RoleId>),
+    Deny(ReviewKind, i32),
+}
+
+impl std::str::FromStr for ReviewInteraction {
+    type Err = GlobalInteractionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split('_').collect();
+        if parts.len() < 3 {
+            return Err(GlobalInteractionParseError {});
+        }
+        let kind = [ReviewKind::Promotion, ReviewKind::TierRequest]
+            .into_iter()
+            .find(|k| k.prefix() == parts[0])
+            .ok_or(GlobalInteractionParseError {})?;
+        let id = parts[2]
+            .parse::<i32>()
+            .map_err(|_| GlobalInteractionParseError {})?;
+        match (parts[1], &parts[3..]) {
+            ("approve", []) => Ok(Self::Approve(kind, id, None)),
+            ("approve", [role]) => {
+                let role = role
+                    .parse::<u64>()
+                    .map_err(|_| GlobalInteractionParseError {})?;
+                Ok(Self::Approve(kind, id, Some(RoleId(role))))
+            }
+            ("deny", []) => Ok(Self::Deny(kind, id)),
+            _ => Err(GlobalInteractionParseError {}),
+        }
+    }
+}
+
+impl std::fmt::Display for ReviewInteraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Approve(kind, id, None) => write!(f, "{}_approve_{}", kind.prefix(), id),
+            Self::Approve(kind, id, Some(role)) => {
+                write!(f, "{}_approve_{}_{}", kind.prefix(), id, role.0)
+            }
+            Self::Deny(kind, id) => write!(f, "{}_deny_{}", kind.prefix(), id),
+        }
+    }
+}
+
+/// The request with buttons to decide on it. Without roles there is a single approve
+/// button, otherwise one per discord role the squadmaker can grant
+pub fn review_request(
+    kind: ReviewKind,
+    id: i32,
+    emb: CreateEmbed,
+    roles: &[(RoleId, String)],
+) -> MessageContent {
+    let approve = |role: Option<RoleId>, label: String| {
+        let mut b = CreateButton::default();
+        b.style(ButtonStyle::Success);
+        b.label(label);
+        b.custom_id(ReviewInteraction::Approve(kind, id, role));
+        b
+    };
+    // Discord allows 5 rows of 5 buttons, with labels of up to 80 characters
+    let mut buttons = match roles {
+        [] => vec![approve(None, String::from("Approve"))],
+        roles => roles
+            .iter()
+            .take(24)
+            .map(|(role, name)| {
+                let name = name.chars().take(60).collect::<String>();
+                approve(Some(*role), format!("Approve as {}", name))
+            })
+            .collect(),
+    };
+    let mut deny = CreateButton::default();
+    deny.style(ButtonStyle::Danger);
+    deny.label("Deny");
+    deny.custom_id(ReviewInteraction::Deny(kind, id));
+    buttons.push(deny);
+
+    let mut content = MessageContent {
+        embeds: vec![emb],
+        ..Default::default()
+    };
+    for chunk in buttons.chunks(5) {
+        let mut ar = CreateActionRow::default();
+        for b in chunk {
+            ar.add_button(b.clone());
+        }
+        content.components.add_action_row(ar);
+    }
+    content
+}
+
 pub fn overview_action_row() -> CreateActionRow {
     let mut ar = CreateActionRow::default();
 
@@ -119,6 +239,38 @@ pub async fn button_interaction(ctx: &Context, mci: MessageComponentInteraction)
     if let Ok(bi) = mci.data.custom_id.parse::<GlobalInteraction>() {
         match &bi {
             GlobalInteraction::Overview(bgi) => button_general_interaction(ctx, mci, bgi).await,
+            GlobalInteraction::Review(ri) => {
+                log_discord(ctx, mci.clone().as_ref(), |trace| async move {
+                    review::decide(ctx, mci, ri, trace).await
+                })
+                .await
+            }
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_interactions_round_trip() {
+        for ri in [
+            ReviewInteraction::Approve(ReviewKind::Promotion, 7, None),
+            ReviewInteraction::Approve(ReviewKind::TierRequest, 7, Some(RoleId(42))),
+            ReviewInteraction::Deny(ReviewKind::TierRequest, 7),
+        ] {
+            let parsed = ri.to_string().parse::<ReviewInteraction>().unwrap();
+            assert_eq!(parsed.to_string(), ri.to_string());
+        }
+        // Buttons posted before roles could be picked
+        assert!(matches!(
+            "tierrequest_approve_7".parse::<ReviewInteraction>(),
+            Ok(ReviewInteraction::Approve(ReviewKind::TierRequest, 7, None))
+        ));
+        assert!("promotion_deny_7_42".parse::<ReviewInteraction>().is_err());
+        assert!("overview_managesignups"
+            .parse::<ReviewInteraction>()
+            .is_err());
+    }
+}
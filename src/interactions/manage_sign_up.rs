@@ -8,21 +8,19 @@ use std::{
 
 use anyhow::{anyhow, bail, Context as ErrContext, Error, Result};
 use itertools::Itertools;
-use regex::Regex;
 use serenity::{
     builder::{CreateButton, CreateEmbed, CreateSelectMenu},
     client::Context,
     futures::StreamExt,
     model::{
         channel::{Message, ReactionType},
-        id::{EmojiId, RoleId},
+        id::{GuildId, RoleId, UserId},
         interactions::{
             message_component::{
                 ActionRowComponent, ButtonStyle, InputTextStyle, MessageComponentInteraction,
             },
             InteractionResponseType,
         },
-        mention::Mention,
     },
     prelude::Mentionable,
 };
@@ -31,16 +29,23 @@ use serenity_tools::{
     collectors::{PagedSelectorConfig, PagedSelectorError, UpdatAbleMessage},
     interactions::MessageComponentInteractionExt,
 };
+use tracing::warn;
 
 use crate::{
-    data, db,
+    announcements,
+    db::{self, DBPoolProvider},
+    discord::DiscordFacade,
     embeds::{self, CrossroadsEmbeds},
     logging::{self, LogTrace, ReplyHelper},
-    signup_board::title_sort_value,
+    signup_board::{title_sort_value, SignupBoard},
+    slash_commands::register_user,
+    threads, waitlist,
 };
 
 enum Buttons {
     Join,
+    JoinWithDefaults,
+    SelectRoles,
     Leave,
     EditRoles,
     EditPreferences,
@@ -52,6 +57,8 @@ impl Display for Buttons {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), FmtError> {
         match self {
             Self::Join => write!(f, "Sign Up"),
+            Self::JoinWithDefaults => write!(f, "Sign up with my defaults"),
+            Self::SelectRoles => write!(f, "Select Roles"),
             Self::Leave => write!(f, "Sign Out"),
             Self::EditRoles => write!(f, "Edit Roles"),
             Self::EditPreferences => write!(f, "Edit Boss Preferences (soon TM)"),
@@ -67,6 +74,8 @@ impl FromStr for Buttons {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "overview_st_join" => Ok(Self::Join),
+            "overview_st_join_defaults" => Ok(Self::JoinWithDefaults),
+            "overview_st_select_roles" => Ok(Self::SelectRoles),
             "overview_st_leave" => Ok(Self::Leave),
             "overview_st_edit_roles" => Ok(Self::EditRoles),
             "overview_st_edit_preferences" => Ok(Self::EditPreferences),
@@ -81,6 +90,8 @@ impl Buttons {
     fn custom_id(&self) -> &'static str {
         match self {
             Self::Join => "overview_st_join",
+            Self::JoinWithDefaults => "overview_st_join_defaults",
+            Self::SelectRoles => "overview_st_select_roles",
             Self::Leave => "overview_st_leave",
             Self::EditRoles => "overview_st_edit_roles",
             Self::EditPreferences => "overview_st_edit_preferences",
@@ -96,6 +107,8 @@ impl Buttons {
 
         match self {
             Self::Join => b.style(ButtonStyle::Success),
+            Self::JoinWithDefaults => b.style(ButtonStyle::Success),
+            Self::SelectRoles => b.style(ButtonStyle::Primary),
             Self::Leave => b.style(ButtonStyle::Danger),
             Self::EditRoles => b.style(ButtonStyle::Primary),
             Self::EditPreferences => b.style(ButtonStyle::Primary).disabled(true),
@@ -114,60 +127,19 @@ pub(crate) async fn interaction(
     mci: Arc<MessageComponentInteraction>,
     trace: LogTrace,
 ) -> Result<()> {
-    let guild_id = {
-        ctx.data
-            .read()
-            .await
-            .get::<data::ConfigValuesData>()
-            .unwrap()
-            .clone()
-            .main_guild_id
-    };
+    // The board is always posted in a guild
+    let guild_id = mci
+        .guild_id
+        .context("Unexpected interaction outside of a server")?;
 
     trace.step("Loading user");
     let (db_user, mut mci) = load_user(ctx, mci, trace.clone()).await?;
     let mut msg = mci.get_interaction_response(ctx).await?;
 
     loop {
-        // TODO cache them on the signup board
-        trace.step("Reloading trainings");
-        let trainings_all = db::Training::all_active(ctx).await?;
-        let mut trainings: Vec<db::Training> = Vec::with_capacity(trainings_all.len());
-
-        for training in trainings_all {
-            // filter for trainings user can join
-            let tier = training.get_tier(ctx).await.transpose()?;
-            let tier_roles = match &tier {
-                Some(t) => Some(t.get_discord_roles(ctx).await?),
-                None => None,
-            };
-            // check if user can join the training
-            let can_join = if let Some(tier_roles) = tier_roles {
-                let mut cj = false;
-                for tr in tier_roles {
-                    if let Ok(b) = mci
-                        .user
-                        .has_role(ctx, guild_id, RoleId::from(tr.discord_role_id as u64))
-                        .await
-                    {
-                        if b {
-                            cj = true;
-                            break;
-                        }
-                    }
-                }
-                cj
-            } else {
-                true
-            };
-
-            if !can_join {
-                continue;
-            }
-
-            // Add training to selection options
-            trainings.push(training);
-        }
+        trace.step("Loading trainings");
+        let active = SignupBoard::active_trainings(ctx, guild_id).await?;
+        let mut trainings = joinable_trainings(ctx, guild_id, mci.user.id, &active).await;
 
         if trainings.is_empty() {
             trace.step("No training's available");
@@ -184,6 +156,14 @@ pub(crate) async fn interaction(
         trainings.sort_by_key(|t| t.date);
 
         let signups = db_user.active_signups(ctx).await?;
+        let mut waitlisted: Vec<i32> = Vec::new();
+        for s in &signups {
+            if let Some(t) = trainings.iter().find(|t| t.id == s.training_id) {
+                if s.waitlist_position(ctx, t).await?.is_some() {
+                    waitlisted.push(t.id);
+                }
+            }
+        }
         let mut joined: Vec<&db::Training> = Vec::with_capacity(trainings.len());
         let mut not_joined: Vec<&db::Training> = Vec::with_capacity(trainings.len());
 
@@ -207,11 +187,14 @@ pub(crate) async fn interaction(
         for (d, v) in joined.iter().group_by(|t| t.date.date()).into_iter() {
             joined_str.push_str(&format!("```\n{}\n\n", d.format("%A, %v")));
             for t in v {
-                if t.state == db::TrainingState::Open {
-                    joined_str.push_str(&format!("> {}\n", &t.title));
-                } else {
-                    joined_str.push_str(&format!("> {} 🔒\n", &t.title));
+                joined_str.push_str(&format!("> {}", &t.title));
+                if waitlisted.contains(&t.id) {
+                    joined_str.push_str(" ⏳");
                 }
+                if t.state != db::TrainingState::Open {
+                    joined_str.push_str(" 🔒");
+                }
+                joined_str.push('\n');
             }
             joined_str.push_str("```");
         }
@@ -350,6 +333,185 @@ pub(crate) async fn interaction(
     }
 }
 
+/// Filters the active trainings for the ones the user is allowed to join. A training
+/// with a tier requires the user to have at least one of the tiers discord roles or
+/// one of the discord roles of a tier above it
+pub(crate) async fn joinable_trainings(
+    ctx: &impl DiscordFacade,
+    guild_id: GuildId,
+    user_id: UserId,
+    active: &[db::TrainingWithTier],
+) -> Vec<db::Training> {
+    // If the roles can not be loaded only trainings without tier are offered
+    let roles = ctx
+        .member_roles(guild_id, user_id)
+        .await
+        .unwrap_or_default();
+
+    active
+        .iter()
+        .filter(|t| {
+            t.tier.is_none()
+                || t.tier_mappings
+                    .iter()
+                    .any(|m| roles.contains(&RoleId::from(m.discord_role_id as u64)))
+        })
+        .map(|t| t.training.clone())
+        .collect()
+}
+
+/// Refreshes everything showing the sign-ups of the training
+async fn roster_changed(ctx: &Context, training: &db::Training) {
+    SignupBoard::request_update(ctx, training.guild()).await;
+    if let Err(e) = announcements::refresh(ctx, training, &[]).await {
+        warn!("Failed to refresh announcement of {}: {}", training.id, e);
+    }
+}
+
+/// Signs the user up for the training with the selected roles. The roles are
+/// ordered by preference, the first one is the main role. The user is added to
+/// the discussion thread of the training
+pub(crate) async fn sign_up(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    db_user: &db::User,
+    training: &db::Training,
+    roles: &[&db::Role],
+) -> Result<db::Signup> {
+    if training.state != db::TrainingState::Open {
+        bail!("{} is not open for sign-ups", training.title);
+    }
+
+    let signup = db::Signup::insert(ctx, db_user, training)
+        .await
+        .context("Failed to create signup")?;
+
+    for (rank, r) in (1..).zip(roles) {
+        signup
+            .add_role(ctx, r, rank)
+            .await
+            .with_context(|| format!("Failed to add role: {}", r.title))?;
+    }
+
+    // The sign-up counts even if discord refuses
+    if let Err(e) = threads::join(ctx, training, db_user).await {
+        warn!("Failed to add {} to thread: {}", db_user.gw2_id, e);
+    }
+
+    Ok(signup)
+}
+
+/// Removes the signup. Everyone who moves up from the waitlist because of it gets a DM
+/// and is mentioned below the announcement. Returns the promoted users
+pub(crate) async fn sign_out(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    training: &db::Training,
+    signup: db::Signup,
+) -> Result<Vec<db::User>> {
+    let promoted =
+        waitlist::remove_signups(ctx, std::slice::from_ref(training), signup.remove(ctx)).await?;
+    Ok(promoted.into_iter().map(|(u, _)| u).collect())
+}
+
+/// Replaces the roles of the signup with the selected ones, ordered by preference
+pub(crate) async fn update_roles(
+    ctx: &impl DBPoolProvider,
+    signup: &db::Signup,
+    roles: &[&db::Role],
+) -> Result<()> {
+    signup.clear_roles(ctx).await?;
+    for (rank, r) in (1..).zip(roles) {
+        signup.add_role(ctx, r, rank).await?;
+    }
+    Ok(())
+}
+
+/// The default roles of the user that the training offers, in the users order
+pub(crate) fn applicable_defaults<'a>(
+    defaults: &[db::Role],
+    available: &'a [db::Role],
+) -> Vec<&'a db::Role> {
+    defaults
+        .iter()
+        .filter_map(|d| available.iter().find(|r| r.id == d.id))
+        .collect()
+}
+
+/// Lets the user order the selected roles by preference, one pick at a time.
+/// Nothing to pick for a single role
+async fn rank_roles<'a>(
+    ctx: &Context,
+    mci: &MessageComponentInteraction,
+    msg: &Message,
+    base_emb: &CreateEmbed,
+    mut roles: Vec<&'a db::Role>,
+) -> Result<Vec<&'a db::Role>> {
+    let mut ranked: Vec<&db::Role> = Vec::with_capacity(roles.len());
+    while roles.len() > 1 {
+        let mut emb = base_emb.clone();
+        emb.description(format!(
+            "Which of the remaining roles do you prefer the most? Pick your choice #{}",
+            ranked.len() + 1
+        ));
+        if !ranked.is_empty() {
+            emb.field(
+                "Your preference",
+                ranked
+                    .iter()
+                    .enumerate()
+                    .map(|(i, r)| format!("#{} {} | {}", i + 1, r.emoji, r.title))
+                    .join("\n"),
+                false,
+            );
+        }
+
+        let mut select_menu = CreateSelectMenu::default();
+        select_menu.custom_id("_user_role_rank_select");
+        select_menu.placeholder(format!("Choice #{}", ranked.len() + 1));
+        select_menu.options(|opts| {
+            for r in &roles {
+                opts.create_option(|o| {
+                    o.label(&r.title);
+                    o.emoji(ReactionType::from(r.emoji.clone()));
+                    o.value(r.id)
+                });
+            }
+            opts
+        });
+        mci.edit_original_interaction_response(ctx, |r| {
+            r.add_embed(emb);
+            r.components(|c| c.create_action_row(|ar| ar.add_select_menu(select_menu)))
+        })
+        .await?;
+
+        let pick = msg
+            .await_component_interaction(ctx)
+            .timeout(Duration::from_secs(60 * 3))
+            .await
+            .context(logging::InfoError::TimedOut)
+            .map_err_reply(|what| mci.edit_quick_info(ctx, what))
+            .await?;
+        pick.defer(ctx).await?;
+
+        let id = pick
+            .data
+            .values
+            .first()
+            .and_then(|v| v.parse::<i32>().ok())
+            .context("Unexpected value on role select menu. Aborted")
+            .map_err_reply(|what| mci.edit_quick_error(ctx, what))
+            .await?;
+        let pos = roles
+            .iter()
+            .position(|r| r.id == id)
+            .context("Unexpected mismatch of selected and available roles. Aborted")
+            .map_err_reply(|what| mci.edit_quick_error(ctx, what))
+            .await?;
+        ranked.push(roles.remove(pos));
+    }
+    ranked.append(&mut roles);
+    Ok(ranked)
+}
+
 // The Interactions already were responded to. So always edit
 // The returned interaction will also be responded to already
 async fn edit(
@@ -361,8 +523,6 @@ async fn edit(
     trace: LogTrace,
 ) -> Result<Arc<MessageComponentInteraction>> {
     trace.step("Signup edit");
-    let bosses = training.all_training_bosses(ctx).await?;
-    let roles = training.all_roles(ctx).await?;
 
     // Current selected roles by user
     let mut curr_roles: Vec<_> = signup
@@ -378,47 +538,31 @@ async fn edit(
     base_emb.title(&training.title);
     let (a, b, c) = embeds::field_training_date(training);
     base_emb.field(a, b, c);
-    base_emb.description("✅ You are signed up\n**Feel free to dismiss this message**");
+    match signup.waitlist_position(ctx, training).await? {
+        None => base_emb.description("✅ You are signed up\n**Feel free to dismiss this message**"),
+        Some(pos) => base_emb.description(format!(
+            "⏳ You are on the waitlist at position {}. You get a DM once a spot opens up\n\
+            **Feel free to dismiss this message**",
+            pos
+        )),
+    };
 
     loop {
+        // Reloaded every time so edits of roles and bosses show up right away
+        let bosses = training.all_training_bosses(ctx).await?;
+        let roles = training.all_roles(ctx).await?;
+
         let mut emb = base_emb.clone();
         if let Some(comment) = &signup.comment {
             emb.field("Comment", &comment, false);
         }
-        emb.fields_chunked_fmt(
-            &bosses,
-            |b| {
-                let boss_link = match &b.url {
-                    Some(l) => format!("[{}]({})", b.name, l),
-                    None => b.name.to_string(),
-                };
-                // TODO highlight already preferred bosses
-                format!(
-                    "{} | {}",
-                    Mention::from(EmojiId::from(b.emoji as u64)),
-                    boss_link
-                )
-            },
-            "Boss Pool",
-            false,
-            8,
-        );
+        // TODO highlight already preferred bosses
+        embeds::embed_add_bosses(&mut emb, &bosses, embeds::boss_link);
         emb.fields_chunked_fmt(
             &roles,
-            |r| {
-                if curr_roles.contains(&r.id) {
-                    format!(
-                        "{} | __**{}**__",
-                        Mention::from(EmojiId::from(r.emoji as u64)),
-                        r.title
-                    )
-                } else {
-                    format!(
-                        "{} | {}",
-                        Mention::from(EmojiId::from(r.emoji as u64)),
-                        r.title
-                    )
-                }
+            |r| match curr_roles.iter().position(|id| *id == r.id) {
+                Some(i) => format!("{} | __**{}**__ #{}", r.emoji, r.title, i + 1),
+                None => format!("{} | {}", r.emoji, r.title),
             },
             "Available Roles",
             true,
@@ -456,12 +600,13 @@ async fn edit(
                 match Buttons::from_str(&mci.data.custom_id)? {
                     Buttons::Leave => {
                         mci.defer(ctx).await?;
-                        signup
-                            .remove(ctx)
+                        sign_out(ctx, training, signup)
                             .await
                             .context("Something went wrong while removing your signup =(")
                             .map_err_reply(|what| mci.edit_quick_error(ctx, what))
                             .await?;
+                        // The announcement was refreshed along with the waitlist
+                        SignupBoard::request_update(ctx, training.guild()).await;
                         return Ok(mci);
                     },
                     Buttons::EditRoles => {
@@ -486,7 +631,7 @@ async fn edit(
                         let selected = match selector
                             .paged_selector(ctx, selector_conf, &roles, |r| {
                                 (
-                                    ReactionType::from(EmojiId::from(r.emoji as u64)),
+                                    ReactionType::from(r.emoji.clone()),
                                     r.title.to_string(),
                                 )
                             })
@@ -499,12 +644,17 @@ async fn edit(
                                     return Err(err);
                                 },
                                 Err(e) => return Err(e.into()),
-                        };
-
-                        signup.clear_roles(ctx).await?;
-                        for r in &selected {
-                            signup.add_role(ctx, r).await?;
                         }
+                        .into_iter()
+                        .collect::<Vec<_>>();
+
+                        trace.step("Rank roles");
+                        let mut rank_emb = base_emb.clone();
+                        rank_emb.title(format!("{} - Rank your roles", training.title));
+                        let selected = rank_roles(ctx, &mci, msg, &rank_emb, selected).await?;
+
+                        update_roles(ctx, &signup, &selected).await?;
+                        roster_changed(ctx, training).await;
 
                         curr_roles = selected
                             .into_iter()
@@ -601,6 +751,67 @@ async fn join(
 ) -> Result<Arc<MessageComponentInteraction>> {
     trace.step("New Signup");
     let roles = training.all_roles(ctx).await?;
+
+    let defaults = db_user.default_roles(ctx, training.guild()).await?;
+    let defaults = applicable_defaults(&defaults, &roles);
+    if !defaults.is_empty() {
+        let comment = db_user.default_comment(ctx, training.guild()).await?;
+        trace.step("Offering defaults");
+        let mut emb = CreateEmbed::xdefault();
+        emb.title(&training.title);
+        let (a, b, c) = embeds::field_training_date(training);
+        emb.field(a, b, c);
+        emb.field(
+            "Your defaults",
+            defaults
+                .iter()
+                .enumerate()
+                .map(|(i, r)| format!("#{} {} | {}", i + 1, r.emoji, r.title))
+                .join("\n"),
+            false,
+        );
+        if let Some(comment) = &comment {
+            emb.field("Comment", comment, false);
+        }
+        mci.edit_original_interaction_response(ctx, |r| {
+            r.add_embed(emb);
+            r.components(|c| {
+                c.create_action_row(|ar| {
+                    ar.add_button(Buttons::JoinWithDefaults.button());
+                    ar.add_button(Buttons::SelectRoles.button());
+                    ar.add_button(Buttons::BackToSelection.button())
+                })
+            })
+        })
+        .await?;
+
+        let pick = msg
+            .await_component_interaction(ctx)
+            .timeout(Duration::from_secs(60 * 3))
+            .await
+            .context(logging::InfoError::TimedOut)
+            .map_err_reply(|what| mci.edit_quick_info(ctx, what))
+            .await?;
+        pick.defer(ctx).await?;
+
+        match Buttons::from_str(&pick.data.custom_id)? {
+            Buttons::JoinWithDefaults => {
+                trace.step("Sign up with defaults");
+                let mut signup = sign_up(ctx, db_user, training, &defaults)
+                    .await
+                    .map_err_reply(|what| mci.edit_quick_error(ctx, what))
+                    .await?;
+                if let Some(comment) = comment {
+                    signup = signup.update_comment(ctx, Some(comment)).await?;
+                }
+                roster_changed(ctx, training).await;
+                return edit(ctx, pick, msg, training, signup, trace).await;
+            }
+            Buttons::SelectRoles => (),
+            Buttons::BackToSelection => return Ok(pick),
+            _ => bail!("Unexpected interaction"),
+        }
+    }
     let mut selector = UpdatAbleMessage::ComponentInteraction(&mci, msg);
     let mut selector_conf = PagedSelectorConfig::default();
     selector_conf
@@ -619,10 +830,7 @@ async fn join(
 
     let selected = match selector
         .paged_selector(ctx, selector_conf, &roles, |r| {
-            (
-                ReactionType::from(EmojiId::from(r.emoji as u64)),
-                r.title.to_string(),
-            )
+            (ReactionType::from(r.emoji.clone()), r.title.to_string())
         })
         .await
     {
@@ -638,20 +846,21 @@ async fn join(
     .into_iter()
     .collect::<Vec<_>>();
 
-    let signup = db::Signup::insert(ctx, db_user, training)
+    trace.step("Rank roles");
+    let mut rank_emb = CreateEmbed::xdefault();
+    rank_emb.title("Rank your role(s)");
+    rank_emb.field(
+        &training.title,
+        format!("<t:{}>", training.date.timestamp()),
+        false,
+    );
+    let selected = rank_roles(ctx, &mci, msg, &rank_emb, selected).await?;
+
+    let signup = sign_up(ctx, db_user, training, &selected)
         .await
-        .context("Failed to create signup")
         .map_err_reply(|what| mci.edit_quick_error(ctx, what))
         .await?;
-
-    for r in selected {
-        signup
-            .add_role(ctx, r)
-            .await
-            .with_context(|| format!("Failed to add role: {}", r.title))
-            .map_err_reply(|what| mci.edit_quick_error(ctx, what))
-            .await?;
-    }
+    roster_changed(ctx, training).await;
 
     let mci = edit(ctx, mci, msg, training, signup, trace).await?;
     Ok(mci)
@@ -719,9 +928,6 @@ async fn load_user(
         }
     };
 
-    trace.step("Checking for valid format");
-    let regex = Regex::new("^[a-zA-Z\\s]{3,27}\\.[0-9]{4}$").unwrap();
-
     let name = resp
         .data
         .components
@@ -739,46 +945,28 @@ async fn load_user(
         })
         .await?;
 
-    if !regex.is_match(&name) {
-        resp.create_interaction_response(ctx, |r| {
-            r.interaction_response_data(|d| {
-                d.add_embed(CreateEmbed::error_box(format!(
-                    "{} Invalid gw2 account format. Please try again",
-                    resp.user.mention()
-                )));
-                d.ephemeral(true)
-            })
-        })
-        .await?;
-        bail!("Invalid gw2 account name format");
-    }
-
-    resp.create_interaction_response(ctx, |r| {
-        r.interaction_response_data(|d| {
-            d.add_embed(CreateEmbed::info_box("Saving..."));
-            d.ephemeral(true)
-        })
-    })
-    .await?;
-
     trace.step("Saving user to db");
-    let user = db::User::upsert(ctx, mci.user.id.0, name)
+    let user = register_user(ctx, mci.user.id, &name)
         .await
-        .context("Unexpected error")
         .map_err_reply(|err| {
-            resp.edit_original_interaction_response(ctx, |r| {
-                r.add_embed(CreateEmbed::error_box(err))
+            let err = format!("{} {}", resp.user.mention(), err);
+            resp.create_interaction_response(ctx, move |r| {
+                r.interaction_response_data(|d| {
+                    d.add_embed(CreateEmbed::error_box(err));
+                    d.ephemeral(true)
+                })
             })
         })
         .await?;
 
-    let msg = resp
-        .edit_original_interaction_response(ctx, |r| {
-            r.add_embed(CreateEmbed::info_box(format!(
+    resp.create_interaction_response(ctx, |r| {
+        r.interaction_response_data(|d| {
+            d.add_embed(CreateEmbed::info_box(format!(
                 "{} Sucesfully registerd 🎉\nClick the button to continue to training selection",
                 mci.user.mention()
             )));
-            r.components(|c| {
+            d.ephemeral(true);
+            d.components(|c| {
                 c.create_action_row(|ar| {
                     ar.create_button(|b| {
                         b.custom_id("__register_continue")
@@ -788,7 +976,9 @@ async fn load_user(
                 })
             })
         })
-        .await?;
+    })
+    .await?;
+    let msg = resp.get_interaction_response(ctx).await?;
 
     let mci = match msg
         .await_component_interaction(ctx)
@@ -818,3 +1008,281 @@ async fn load_user(
 
     Ok((user, mci))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        discord::mock::{DiscordCall, RecordingDiscord},
+        testing::{self, TestContext, GUILD_ID},
+    };
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn join_edit_and_leave_signup() {
+        let ctx = TestContext::new();
+        let user = testing::user(&ctx, 2001).await;
+        let (tank, heal, dps) = (
+            testing::role(&ctx).await,
+            testing::role(&ctx).await,
+            testing::role(&ctx).await,
+        );
+        let training =
+            testing::training(&ctx, db::TrainingState::Open, None, &[&tank, &heal, &dps]).await;
+
+        let signup = sign_up(&ctx, &user, &training, &[&tank, &dps])
+            .await
+            .unwrap();
+        let mut roles = signup.get_roles(&ctx).await.unwrap();
+        roles.sort_by_key(|r| r.id);
+        assert_eq!(roles, vec![tank, dps]);
+        assert_eq!(training.get_signup_count(&ctx).await.unwrap(), 1);
+
+        update_roles(&ctx, &signup, &[&heal]).await.unwrap();
+        assert_eq!(signup.get_roles(&ctx).await.unwrap(), vec![heal]);
+
+        signup.remove(&ctx).await.unwrap();
+        assert_eq!(training.get_signup_count(&ctx).await.unwrap(), 0);
+        assert!(user.active_signups(&ctx).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn sign_out_promotes_waitlisted() {
+        let ctx = TestContext::new();
+        let role = testing::role(&ctx).await;
+        let training = testing::training(&ctx, db::TrainingState::Open, None, &[&role])
+            .await
+            .set_max_participants(&ctx, Some(1))
+            .await
+            .unwrap();
+        let mut signups = Vec::new();
+        for id in [2011, 2012, 2013] {
+            let user = testing::user(&ctx, id).await;
+            signups.push(sign_up(&ctx, &user, &training, &[&role]).await.unwrap());
+        }
+        let (first, second, third) = (&signups[0], &signups[1], &signups[2]);
+        assert_eq!(
+            first.waitlist_position(&ctx, &training).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            second.waitlist_position(&ctx, &training).await.unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            third.waitlist_position(&ctx, &training).await.unwrap(),
+            Some(2)
+        );
+
+        // Leaving the waitlist promotes nobody
+        let promoted = sign_out(&ctx, &training, third.clone()).await.unwrap();
+        assert!(promoted.is_empty());
+
+        let promoted = sign_out(&ctx, &training, first.clone()).await.unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].id, second.user_id);
+        assert_eq!(
+            second.waitlist_position(&ctx, &training).await.unwrap(),
+            None
+        );
+        assert!(training.waitlist(&ctx).await.unwrap().is_empty());
+        assert!(matches!(
+            &ctx.discord.calls()[..],
+            [DiscordCall::SendDm(u, content)] if *u == UserId(2012) && content.embeds.len() == 1
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn signups_are_loaded_first_come_first() {
+        let ctx = TestContext::new();
+        let role = testing::role(&ctx).await;
+        let training = testing::training(&ctx, db::TrainingState::Open, None, &[&role]).await;
+        let mut ids = Vec::new();
+        for id in [2021, 2022, 2023] {
+            let user = testing::user(&ctx, id).await;
+            let signup = sign_up(&ctx, &user, &training, &[&role]).await.unwrap();
+            assert!(signup.updated_at >= signup.created_at);
+            ids.push(signup.id);
+        }
+
+        let loaded = training.get_signups(&ctx).await.unwrap();
+        assert_eq!(loaded.iter().map(|s| s.id).collect::<Vec<_>>(), ids);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn roles_keep_preference_order() {
+        let ctx = TestContext::new();
+        let user = testing::user(&ctx, 2031).await;
+        let (tank, heal, dps) = (
+            testing::role(&ctx).await,
+            testing::role(&ctx).await,
+            testing::role(&ctx).await,
+        );
+        let training =
+            testing::training(&ctx, db::TrainingState::Open, None, &[&tank, &heal, &dps]).await;
+
+        let signup = sign_up(&ctx, &user, &training, &[&dps, &tank])
+            .await
+            .unwrap();
+        assert_eq!(
+            signup.get_roles(&ctx).await.unwrap(),
+            vec![dps.clone(), tank.clone()]
+        );
+
+        update_roles(&ctx, &signup, &[&heal, &dps, &tank])
+            .await
+            .unwrap();
+        assert_eq!(signup.get_roles(&ctx).await.unwrap(), vec![heal, dps, tank]);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn defaults_only_apply_training_roles() {
+        let ctx = TestContext::new();
+        let user = testing::user(&ctx, 2041).await;
+        let (tank, heal, dps) = (
+            testing::role(&ctx).await,
+            testing::role(&ctx).await,
+            testing::role(&ctx).await,
+        );
+        user.set_default_roles(&ctx, GUILD_ID, &[&heal, &dps])
+            .await
+            .unwrap();
+        // Setting them again replaces the old ones
+        user.set_default_roles(&ctx, GUILD_ID, &[&dps, &tank, &heal])
+            .await
+            .unwrap();
+        let defaults = user.default_roles(&ctx, GUILD_ID).await.unwrap();
+        assert_eq!(defaults, vec![dps.clone(), tank.clone(), heal.clone()]);
+
+        let training = testing::training(&ctx, db::TrainingState::Open, None, &[&tank, &dps]).await;
+        let available = training.all_roles(&ctx).await.unwrap();
+        assert_eq!(
+            applicable_defaults(&defaults, &available),
+            vec![&dps, &tank]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn default_comment_is_per_guild() {
+        let ctx = TestContext::new();
+        let user = testing::user(&ctx, 2042).await;
+        let other = GuildId(2);
+        testing::guild(&ctx).await;
+        db::Guild::insert(&ctx, other).await.unwrap();
+
+        user.set_default_comment(&ctx, GUILD_ID, Some(String::from("late")))
+            .await
+            .unwrap();
+        user.set_default_comment(&ctx, other, Some(String::from("no mic")))
+            .await
+            .unwrap();
+        user.set_default_comment(&ctx, other, None).await.unwrap();
+
+        assert_eq!(
+            user.default_comment(&ctx, GUILD_ID)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("late")
+        );
+        assert_eq!(user.default_comment(&ctx, other).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn join_requires_open_training() {
+        let ctx = TestContext::new();
+        let user = testing::user(&ctx, 2002).await;
+        let role = testing::role(&ctx).await;
+        let training = testing::training(&ctx, db::TrainingState::Closed, None, &[&role]).await;
+
+        assert!(sign_up(&ctx, &user, &training, &[&role]).await.is_err());
+        assert_eq!(training.get_signup_count(&ctx).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn joinable_trainings_respect_tier_roles() {
+        let (member, outsider) = (UserId(2003), UserId(2004));
+        let tier_role = RoleId(3001);
+        let mut discord = RecordingDiscord::default();
+        discord.give_role(member, tier_role);
+        let ctx = TestContext::with_discord(discord);
+
+        testing::guild(&ctx).await;
+        let tier = db::Tier::insert(&ctx, GUILD_ID, testing::unique("tier"))
+            .await
+            .unwrap();
+        tier.add_discord_role(&ctx, tier_role.0).await.unwrap();
+        let open = testing::training(&ctx, db::TrainingState::Open, None, &[]).await;
+        let tiered = testing::training(&ctx, db::TrainingState::Open, Some(&tier), &[]).await;
+
+        let active = db::Training::all_active_with_tier(&ctx, GUILD_ID)
+            .await
+            .unwrap();
+        let ids = |t: Vec<db::Training>| t.into_iter().map(|t| t.id).collect::<Vec<_>>();
+
+        let for_member = ids(joinable_trainings(&ctx, GUILD_ID, member, &active).await);
+        assert!(for_member.contains(&open.id));
+        assert!(for_member.contains(&tiered.id));
+
+        let for_outsider = ids(joinable_trainings(&ctx, GUILD_ID, outsider, &active).await);
+        assert!(for_outsider.contains(&open.id));
+        assert!(!for_outsider.contains(&tiered.id));
+
+        // roles are requested once per check, not once per training
+        let role_requests = ctx
+            .discord
+            .calls()
+            .into_iter()
+            .filter_map(|c| match c {
+                DiscordCall::MemberRoles(g, u) if g == GUILD_ID => Some(u),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(role_requests, vec![member, outsider]);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn higher_tiers_qualify_for_lower_ones() {
+        let (veteran, beginner) = (UserId(2005), UserId(2006));
+        let (veteran_role, beginner_role) = (RoleId(3002), RoleId(3003));
+        let mut discord = RecordingDiscord::default();
+        discord.give_role(veteran, veteran_role);
+        discord.give_role(beginner, beginner_role);
+        let ctx = TestContext::with_discord(discord);
+
+        testing::guild(&ctx).await;
+        let high = db::Tier::insert(&ctx, GUILD_ID, testing::unique("high"))
+            .await
+            .unwrap();
+        high.add_discord_role(&ctx, veteran_role.0).await.unwrap();
+        let low = db::Tier::insert(&ctx, GUILD_ID, testing::unique("low"))
+            .await
+            .unwrap();
+        low.add_discord_role(&ctx, beginner_role.0).await.unwrap();
+        let low = low.set_parent(&ctx, Some(high.id)).await.unwrap();
+        let high_training =
+            testing::training(&ctx, db::TrainingState::Open, Some(&high), &[]).await;
+        let low_training = testing::training(&ctx, db::TrainingState::Open, Some(&low), &[]).await;
+
+        let active = db::Training::all_active_with_tier(&ctx, GUILD_ID)
+            .await
+            .unwrap();
+        let ids = |t: Vec<db::Training>| t.into_iter().map(|t| t.id).collect::<Vec<_>>();
+
+        let for_veteran = ids(joinable_trainings(&ctx, GUILD_ID, veteran, &active).await);
+        assert!(for_veteran.contains(&high_training.id));
+        assert!(for_veteran.contains(&low_training.id));
+
+        let for_beginner = ids(joinable_trainings(&ctx, GUILD_ID, beginner, &active).await);
+        assert!(!for_beginner.contains(&high_training.id));
+        assert!(for_beginner.contains(&low_training.id));
+    }
+}
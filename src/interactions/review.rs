@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as ErrContext, Result};
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    model::{
+        id::GuildId,
+        interactions::{message_component::MessageComponentInteraction, InteractionResponseType},
+        mention::Mention,
+    },
+};
+use serenity_tools::interactions::MessageComponentInteractionExt;
+
+use super::{ReviewInteraction, ReviewKind};
+use crate::{
+    data::ConfigValues,
+    logging::{LogTrace, ReplyHelper},
+    promotions, tier_requests,
+};
+
+/// Only squadmakers and admins may decide on promotions and tier requests
+async fn check_reviewer(ctx: &Context, mci: &MessageComponentInteraction) -> Result<GuildId> {
+    let guild_id = mci
+        .guild_id
+        .context("Unexpected interaction outside of a server")?;
+    let conf = ConfigValues::get(ctx, guild_id).await?;
+    let allowed = matches!(&mci.member, Some(m)
+        if m.roles.contains(&conf.squadmaker_role_id) || m.roles.contains(&conf.admin_role_id));
+    if !allowed {
+        Err(anyhow!("Only squadmakers can decide on this"))
+            .map_err_reply(|what| mci.create_quick_error(ctx, what, true))
+            .await?;
+    }
+    Ok(guild_id)
+}
+
+/// Adds the decision to the reviewed message and removes the buttons
+async fn mark_decided(
+    ctx: &Context,
+    mci: &MessageComponentInteraction,
+    approve: bool,
+) -> Result<()> {
+    let decision = format!(
+        "{} by {}",
+        if approve { "Approved" } else { "Denied" },
+        Mention::from(mci.user.id)
+    );
+    let mut emb = mci
+        .message
+        .embeds
+        .first()
+        .cloned()
+        .map(CreateEmbed::from)
+        .unwrap_or_default();
+    emb.field("Decision", decision, false);
+    mci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::UpdateMessage);
+        r.interaction_response_data(|d| {
+            d.set_embeds(vec![emb]);
+            d.components(|c| c)
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Approves or denies the promotion or tier request the button belongs to
+pub(crate) async fn decide(
+    ctx: &Context,
+    mci: Arc<MessageComponentInteraction>,
+    ri: &ReviewInteraction,
+    trace: LogTrace,
+) -> Result<()> {
+    trace.step("Checking permissions");
+    let guild_id = check_reviewer(ctx, &mci).await?;
+
+    let (kind, id, approve, role_id) = match ri {
+        ReviewInteraction::Approve(kind, id, role_id) => (*kind, *id, true, *role_id),
+        ReviewInteraction::Deny(kind, id) => (*kind, *id, false, None),
+    };
+    trace.step(match (kind, approve) {
+        (ReviewKind::Promotion, true) => "Approving promotion",
+        (ReviewKind::Promotion, false) => "Denying promotion",
+        (ReviewKind::TierRequest, true) => "Approving tier request",
+        (ReviewKind::TierRequest, false) => "Denying tier request",
+    });
+    let decided = match kind {
+        ReviewKind::Promotion => promotions::decide(ctx, guild_id, id, approve)
+            .await
+            .map(|_| ()),
+        ReviewKind::TierRequest => {
+            tier_requests::decide(ctx, guild_id, id, approve, role_id, mci.user.id)
+                .await
+                .map(|_| ())
+        }
+    };
+    decided
+        .map_err_reply(|what| mci.create_quick_error(ctx, what, true))
+        .await?;
+
+    mark_decided(ctx, &mci, approve).await
+}
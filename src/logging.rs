@@ -59,6 +59,8 @@ pub struct LogInfo {
     kind: &'static str,
     /// What exactly happened
     what: String,
+    /// Where it happened. Decides which log channel is used
+    guild_id: Option<GuildId>,
 }
 
 impl LogInfo {
@@ -67,12 +69,17 @@ impl LogInfo {
             user: None,
             kind: "Automatic",
             what: what.to_string(),
+            guild_id: None,
         }
     }
 
     pub fn add_user(&mut self, user: User) {
         self.user = Some(user);
     }
+
+    pub fn add_guild(&mut self, guild_id: GuildId) {
+        self.guild_id = Some(guild_id);
+    }
 }
 
 impl From<&Message> for LogInfo {
@@ -81,6 +88,7 @@ impl From<&Message> for LogInfo {
             user: Some(msg.author.clone()),
             kind: "Message",
             what: msg.content.clone(),
+            guild_id: msg.guild_id,
         }
     }
 }
@@ -135,6 +143,7 @@ impl From<&ApplicationCommandInteraction> for LogInfo {
             user: Some(aci.user.clone()),
             kind: "Application Command",
             what: fmt_app_command_data(&aci.data),
+            guild_id: aci.guild_id,
         }
     }
 }
@@ -145,6 +154,7 @@ impl From<&MessageComponentInteraction> for LogInfo {
             user: Some(mci.user.clone()),
             kind: "Message Interaction",
             what: mci.data.custom_id.to_string(),
+            guild_id: mci.guild_id,
         }
     }
 }
@@ -153,7 +163,7 @@ impl From<&MessageComponentInteraction> for LogInfo {
 pub struct LogTrace(Arc<Mutex<Vec<(NaiveDateTime, &'static str)>>>);
 
 impl LogTrace {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         LogTrace(Arc::new(Mutex::new(Vec::new())))
     }
 
@@ -184,8 +194,9 @@ fn log_basic_embed(info: LogInfo) -> CreateEmbed {
 }
 
 async fn log_to_channel(ctx: &SerenityContext, info: LogInfo, trace: LogTrace, res: Result<()>) {
-    let log_channel_info = {
-        ctx.data
+    let log_channel_info = match info.guild_id {
+        Some(guild_id) => ctx
+            .data
             .read()
             .await
             .get::<LogConfigData>()
@@ -194,6 +205,9 @@ async fn log_to_channel(ctx: &SerenityContext, info: LogInfo, trace: LogTrace, r
             .read()
             .await
             .log
+            .get(&guild_id)
+            .copied(),
+        None => None,
     };
 
     if let Some(chan) = log_channel_info {
@@ -253,6 +267,11 @@ async fn log_to_channel(ctx: &SerenityContext, info: LogInfo, trace: LogTrace, r
         if let Err(log_err) = log_err {
             error!("Failed to log message to discord: {:?}", log_err);
         }
+    } else if let Err(err) = res {
+        error!(
+            "{} ({}) failed without a log channel: {:?}",
+            info.kind, info.what, err
+        );
     } else {
         info!("Discord log channel not set up");
     }
@@ -3,17 +3,26 @@ extern crate diesel;
 extern crate dotenv;
 extern crate serenity;
 
+mod announcements;
 mod data;
 mod db;
+mod discord;
 mod embeds;
 mod interactions;
 mod logging;
+mod promotions;
+mod roster;
 mod signup_board;
 mod slash_commands;
 mod status;
 mod tasks;
+#[cfg(test)]
+mod testing;
+mod threads;
+mod tier_requests;
+mod waitlist;
 
-use anyhow::bail;
+use anyhow::{bail, Context as _};
 use data::*;
 use logging::{log_discord, LogInfo};
 //use crate::logging;
@@ -30,15 +39,9 @@ use serenity::{
     prelude::*,
 };
 use signup_board::SignupBoard;
-use std::{
-    env,
-    str::FromStr,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-};
-use tracing::{error, info};
+use std::{collections::HashMap, env, sync::Arc};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 #[macro_use]
@@ -47,69 +50,52 @@ use diesel_migrations::embed_migrations;
 embed_migrations!("migrations/");
 
 struct Handler {
-    signup_board_loop_running: AtomicBool,
+    // Taken by the signup board task once it is started
+    signup_board_updates: Mutex<Option<mpsc::UnboundedReceiver<GuildId>>>,
+}
+
+/// Loads the guild if it went through the setup and registers the matching commands
+async fn init_guild(ctx: &Context, guild_id: GuildId) {
+    let set_up = match db::Guild::by_id(ctx, guild_id).await {
+        Ok(_) => match slash_commands::load_guild(ctx, guild_id).await {
+            Ok(()) => true,
+            Err(e) => {
+                error!("Failed to load guild {}: {:?}", guild_id, e);
+                false
+            }
+        },
+        Err(NotFound) => false,
+        Err(e) => {
+            error!("Failed to load guild {}: {:?}", guild_id, e);
+            false
+        }
+    };
+
+    if let Err(e) = slash_commands::register_commands(ctx, guild_id, set_up).await {
+        error!(
+            "Failed to create application commands for {}: {:?}",
+            guild_id, e
+        );
+    }
 }
 
 #[async_trait]
 impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, ready: Ready) {
         info!("Connected as {}", ready.user.name);
-        info!("Refreshing config values");
-
-        let log_channel = db::Config::load(&ctx, String::from(INFO_LOG_NAME))
-            .await
-            .ok();
 
-        let data_read = ctx.data.read().await;
-        let mut log_write = data_read.get::<LogConfigData>().unwrap().write().await;
-
-        match log_channel {
-            None => info!("Log channel not found in db. skipped"),
-            Some(info) => match ChannelId::from_str(&info.value) {
-                Err(e) => error!("Failed to parse log channel id: {}", e),
-                Ok(id) => log_write.log = Some(id),
-            },
+        info!("Loading guilds and setting up slash commands");
+        for guild in &ready.guilds {
+            init_guild(&ctx, guild.id).await;
         }
 
-        // Register slash commands for main guild
-        let main_guild_id = {
-            ctx.data
-                .read()
-                .await
-                .get::<ConfigValuesData>()
-                .unwrap()
-                .clone()
-                .main_guild_id
-        };
-
-        info!("Setting up slash commands");
-        if let Err(e) = main_guild_id
-            .set_application_commands(&ctx, |cmds| {
-                cmds.set_application_commands(slash_commands::AppCommands::create_default())
-            })
-            .await
-        {
-            error!("Failed to create application commands: {:?}", e);
-        }
-
-        // attempt to load SignupBoardData from db
-        data_read
-            .get::<SignupBoardData>()
-            .unwrap()
-            .write()
-            .await
-            .load_from_db(&ctx)
-            .await
-            .unwrap();
-
         info!("Setting presence");
         status::update_status(&ctx).await;
 
-        if !self.signup_board_loop_running.load(Ordering::Relaxed) {
+        if let Some(updates) = self.signup_board_updates.lock().await.take() {
             // ctx is save to clone
             let ctx = ctx.clone();
-            tokio::task::spawn(tasks::signup_board_task(ctx));
-            self.signup_board_loop_running.swap(true, Ordering::Relaxed);
+            tokio::task::spawn(tasks::signup_board_task(ctx, updates));
         }
         info!("Starting signup board loop");
     }
@@ -118,6 +104,14 @@ impl EventHandler for Handler {
         info!("Resumed");
     }
 
+    async fn guild_create(&self, ctx: Context, guild: Guild, is_new: bool) {
+        // Guilds known on startup are handled in ready
+        if is_new {
+            info!("Joined guild {}", guild.id);
+            init_guild(&ctx, guild.id).await;
+        }
+    }
+
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
         match interaction {
             Interaction::MessageComponent(mci) => interactions::button_interaction(&ctx, mci).await,
@@ -135,18 +129,8 @@ impl EventHandler for Handler {
         user: User,
         _member_data_if_available: Option<Member>,
     ) {
-        // Check if in correct guild
-        let main_guild_id = {
-            ctx.data
-                .read()
-                .await
-                .get::<ConfigValuesData>()
-                .unwrap()
-                .clone()
-                .main_guild_id
-        };
-
-        if guild_id != main_guild_id {
+        // Only guilds that went through the setup have signups
+        if ConfigValues::get(&ctx, guild_id).await.is_err() {
             return;
         }
 
@@ -154,13 +138,26 @@ impl EventHandler for Handler {
         let user_id = user.id;
         let mut log_info = LogInfo::automatic("User left server");
         log_info.add_user(user);
+        log_info.add_guild(guild_id);
 
         log_discord(ctx, log_info, |trace| async move {
             trace.step("Loading user database info");
             match db::User::by_discord_id(ctx, user_id).await {
                 Ok(db_user) => {
-                    trace.step("Deleting user from db");
-                    db_user.delete(ctx).await?;
+                    let joined = db_user
+                        .joined_active_trainings(ctx)
+                        .await?
+                        .into_iter()
+                        .filter(|t| t.guild() == guild_id)
+                        .collect::<Vec<_>>();
+                    trace.step("Removing signups of the server");
+                    waitlist::remove_signups(
+                        ctx,
+                        &joined,
+                        db_user.remove_guild_signups(ctx, guild_id),
+                    )
+                    .await?;
+                    SignupBoard::request_update(ctx, guild_id).await;
                 }
                 Err(NotFound) => {
                     trace.step("User not found in database");
@@ -174,6 +171,60 @@ impl EventHandler for Handler {
     }
 }
 
+/// Everything created before the bot supported multiple servers belongs to a
+/// placeholder. The server set by MAIN_GUILD_ID takes it over on the first start
+async fn adopt_legacy_guild(pool: &Arc<db::DBPool>) -> anyhow::Result<()> {
+    let env_id = |name: &str| env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+
+    let main_guild_id = match env_id("MAIN_GUILD_ID") {
+        Some(id) => GuildId::from(id),
+        None => return Ok(()),
+    };
+
+    if !db::Guild::has_placeholder(pool).await? {
+        return Ok(());
+    }
+    match db::Guild::by_id(pool, main_guild_id).await {
+        Ok(_) => {
+            // Both can have data by now. Merging them is not attempted, the
+            // placeholder keeps its data
+            warn!(
+                "Guild {} was set up before it took over the existing data. The data is left as is",
+                main_guild_id
+            );
+            return Ok(());
+        }
+        Err(NotFound) => (),
+        Err(e) => bail!(e),
+    }
+
+    if !db::Guild::claim_placeholder(pool, main_guild_id)
+        .await
+        .context("Failed to move existing data to the main guild")?
+    {
+        return Ok(());
+    }
+    info!("Existing data moved to guild {}", main_guild_id);
+
+    if let (Some(emoji), Some(admin), Some(squadmaker)) = (
+        env_id("EMOJI_GUILD_ID"),
+        env_id("ADMIN_ROLE_ID"),
+        env_id("SQUADMAKER_ROLE_ID"),
+    ) {
+        ConfigValues {
+            admin_role_id: RoleId::from(admin),
+            squadmaker_role_id: RoleId::from(squadmaker),
+            emoji_guild_id: GuildId::from(emoji),
+        }
+        .save_to_db(pool, main_guild_id)
+        .await
+        .context("Failed to save the configuration of the main guild")?;
+    } else {
+        info!("Legacy configuration incomplete. Use /setup in the main guild");
+    }
+    Ok(())
+}
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
     // Load .env into ENV
@@ -200,59 +251,32 @@ async fn main() {
         .parse::<u64>()
         .expect("Failed to parse application id");
 
-    let main_guild_id = GuildId::from(
-        env::var("MAIN_GUILD_ID")
-            .expect("MAIN_GUILD_ID not set")
-            .parse::<u64>()
-            .expect("Failed to parse manager guild id"),
-    );
-
-    let emoji_guild_id = GuildId::from(
-        env::var("EMOJI_GUILD_ID")
-            .expect("EMOJI_GUILD_ID not set")
-            .parse::<u64>()
-            .expect("Failed to parse emoji guild id"),
-    );
-
-    let admin_role_id = RoleId::from(
-        env::var("ADMIN_ROLE_ID")
-            .expect("ADMIN_ROLE_ID not set")
-            .parse::<u64>()
-            .expect("Failed to parse admin role id"),
-    );
-
-    let squadmaker_role_id = RoleId::from(
-        env::var("SQUADMAKER_ROLE_ID")
-            .expect("SQUADMAKER_ROLE_ID not set")
-            .parse::<u64>()
-            .expect("Failed to parse squadmaker role id"),
-    );
+    let pool = Arc::new(db::DBPool::new());
+    if let Err(e) = adopt_legacy_guild(&pool).await {
+        error!("Failed to adopt the existing data: {:?}", e);
+    }
 
     let intents = GatewayIntents::non_privileged()
         | GatewayIntents::GUILD_MEMBERS
         | GatewayIntents::MESSAGE_CONTENT;
+    let (board_update_sender, board_update_receiver) = mpsc::unbounded_channel();
     let mut client = Client::builder(token, intents)
         .application_id(app_id)
         .event_handler(Handler {
-            signup_board_loop_running: AtomicBool::new(false),
+            signup_board_updates: Mutex::new(Some(board_update_receiver)),
         })
         .await
         .expect("Error creating client");
 
     {
         let mut data = client.data.write().await;
-        data.insert::<ConfigValuesData>(Arc::new(ConfigValues {
-            main_guild_id,
-            admin_role_id,
-            squadmaker_role_id,
-            emoji_guild_id,
-        }));
-        data.insert::<LogConfigData>(Arc::new(RwLock::new(LogConfig { log: None })));
-        data.insert::<DBPoolData>(Arc::new(db::DBPool::new()));
-        data.insert::<SignupBoardData>(Arc::new(RwLock::new(SignupBoard {
-            overview_channel_id: None,
-            overview_message_id: None,
+        data.insert::<ConfigValuesData>(Arc::new(RwLock::new(HashMap::new())));
+        data.insert::<LogConfigData>(Arc::new(RwLock::new(LogConfig {
+            log: HashMap::new(),
         })));
+        data.insert::<DBPoolData>(pool);
+        data.insert::<SignupBoardData>(Arc::new(RwLock::new(HashMap::new())));
+        data.insert::<SignupBoardUpdateData>(board_update_sender);
     }
 
     let shard_manager = client.shard_manager.clone();
@@ -0,0 +1,380 @@
+//! # promotions
+//! Promotes members to higher tiers based on the trainings they attended. The rules are
+//! evaluated whenever trainings finish. Automatic rules assign the discord role right
+//! away, all others are posted with buttons for a squadmaker to decide
+
+use crate::{
+    db::{self, DBPoolProvider},
+    discord::{DiscordFacade, MessageContent},
+    embeds::CrossroadsEmbeds,
+    interactions::{review_request, ReviewKind},
+};
+use anyhow::{bail, Context as ErrContext, Result};
+use serenity::{
+    builder::CreateEmbed,
+    model::{
+        id::{ChannelId, GuildId, RoleId, UserId},
+        mention::Mention,
+    },
+};
+use tracing::warn;
+
+/// Short description of the rule, for example
+/// "3 finished trainings of **Beginner** as 🛡 Tank → @Veteran (**Advanced**)"
+pub(crate) fn describe_rule(
+    rule: &db::PromotionRule,
+    tiers: &[db::Tier],
+    roles: &[db::Role],
+) -> String {
+    let tier_name = |id: i32| {
+        tiers
+            .iter()
+            .find(|t| t.id == id)
+            .map_or("?", |t| t.name.as_str())
+    };
+    let mut desc = format!(
+        "{} finished trainings of **{}**",
+        rule.min_count,
+        tier_name(rule.from_tier_id)
+    );
+    if let Some(role_id) = rule.role_id {
+        match roles.iter().find(|r| r.id == role_id) {
+            Some(role) => desc.push_str(&format!(" as {}", role)),
+            None => desc.push_str(" as a removed role"),
+        }
+    }
+    desc.push_str(&format!(
+        " → {} (**{}**)",
+        Mention::from(RoleId::from(rule.discord_role_id as u64)),
+        tier_name(rule.tier_id)
+    ));
+    if rule.automatic {
+        desc.push_str(" automatic");
+    }
+    desc
+}
+
+fn request_message(
+    promotion: &db::Promotion,
+    user: &db::User,
+    attended: i64,
+    rule_desc: String,
+) -> MessageContent {
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Promotion request");
+    emb.description(format!(
+        "{} | {} attended **{}** trainings and qualifies for a promotion",
+        Mention::from(UserId::from(user.discord_id())),
+        user.gw2_id,
+        attended
+    ));
+    emb.field("Rule", rule_desc, false);
+    review_request(ReviewKind::Promotion, promotion.id, emb, &[])
+}
+
+async fn notify(ctx: &impl DiscordFacade, user: &db::User, rule: &db::PromotionRule) {
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("You were promoted");
+    emb.description(format!(
+        "Thanks to the trainings you attended you now have the role {}",
+        Mention::from(RoleId::from(rule.discord_role_id as u64))
+    ));
+    let content = MessageContent {
+        embeds: vec![emb],
+        ..Default::default()
+    };
+    // Users can have DMs disabled. The role is assigned anyway
+    if let Err(e) = ctx.send_dm(UserId::from(user.discord_id()), content).await {
+        warn!("Failed to notify {} about promotion: {}", user.gw2_id, e);
+    }
+}
+
+/// Checks every rule counting trainings of the given tiers and promotes or queues
+/// the members that qualify. Members that already have the role or were handled by
+/// the rule before are skipped. Returns the new promotions
+pub(crate) async fn evaluate(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    guild_id: GuildId,
+    tier_ids: &[i32],
+    channel_id: Option<ChannelId>,
+) -> Result<Vec<db::Promotion>> {
+    let rules = db::PromotionRule::all(ctx, guild_id).await?;
+    let tiers = db::Tier::all(ctx, guild_id).await?;
+    let roles = db::Role::all_active(ctx, guild_id).await?;
+
+    let mut promotions: Vec<(db::Promotion, i64)> = Vec::new();
+    for rule in rules.iter().filter(|r| tier_ids.contains(&r.from_tier_id)) {
+        let known = rule.promotions(ctx).await?;
+        let role_id = RoleId::from(rule.discord_role_id as u64);
+
+        for (user, attended) in rule.attendance(ctx).await? {
+            if attended < i64::from(rule.min_count)
+                || known.iter().any(|p| p.user_id == user.id)
+                // Several rules can lead to the same role
+                || promotions
+                    .iter()
+                    .any(|(p, r)| p.user_id == user.id && *r == rule.discord_role_id)
+            {
+                continue;
+            }
+            let user_id = UserId::from(user.discord_id());
+            // Members that left the server can not be promoted
+            let has_role = match ctx.member_roles(guild_id, user_id).await {
+                Ok(member_roles) => member_roles.contains(&role_id),
+                Err(_) => continue,
+            };
+            if has_role {
+                continue;
+            }
+
+            let promotion = if rule.automatic {
+                // One member discord refuses must not hold up the others
+                if let Err(e) = ctx.add_member_role(guild_id, user_id, role_id).await {
+                    warn!("Failed to promote {}: {}", user.gw2_id, e);
+                    continue;
+                }
+                let promotion =
+                    db::Promotion::insert(ctx, rule, &user, db::PromotionState::Approved).await?;
+                notify(ctx, &user, rule).await;
+                promotion
+            } else {
+                // Without a channel nobody could approve it. Not saving it
+                // means it is offered again once a channel is set
+                let channel_id = match channel_id {
+                    Some(c) => c,
+                    None => continue,
+                };
+                // The buttons need the id, so it is saved first and removed
+                // again if the request can not be posted
+                let promotion =
+                    db::Promotion::insert(ctx, rule, &user, db::PromotionState::Pending).await?;
+                let msg = request_message(
+                    &promotion,
+                    &user,
+                    attended,
+                    describe_rule(rule, &tiers, &roles),
+                );
+                if let Err(e) = ctx.send_message(channel_id, msg).await {
+                    warn!("Failed to post promotion of {}: {}", user.gw2_id, e);
+                    promotion.delete(ctx).await?;
+                    continue;
+                }
+                promotion
+            };
+            promotions.push((promotion, rule.discord_role_id));
+        }
+    }
+
+    Ok(promotions.into_iter().map(|(p, _)| p).collect())
+}
+
+/// Approves or denies a pending promotion of the guild. Approving assigns the role
+pub(crate) async fn decide(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    guild_id: GuildId,
+    promotion_id: i32,
+    approve: bool,
+) -> Result<db::Promotion> {
+    let promotion = db::Promotion::by_id(ctx, promotion_id)
+        .await
+        .context("The promotion does not exist anymore")?;
+    let rule = db::PromotionRule::by_id(ctx, guild_id, promotion.rule_id)
+        .await
+        .context("The promotion rule does not exist anymore")?;
+    if promotion.state != db::PromotionState::Pending {
+        bail!("The promotion was already decided on");
+    }
+    let user = promotion.user(ctx).await?;
+
+    let state = if approve {
+        db::PromotionState::Approved
+    } else {
+        db::PromotionState::Denied
+    };
+    // Decided before the role is assigned, so only one of two squadmakers
+    // clicking at the same time gets through
+    let decided = promotion
+        .decide(ctx, state)
+        .await?
+        .context("The promotion was already decided on")?;
+    if !approve {
+        return Ok(decided);
+    }
+    if let Err(e) = ctx
+        .add_member_role(
+            guild_id,
+            UserId::from(user.discord_id()),
+            RoleId::from(rule.discord_role_id as u64),
+        )
+        .await
+    {
+        decided.reopen(ctx).await?;
+        return Err(e.context("Failed to assign the role"));
+    }
+    notify(ctx, &user, &rule).await;
+    Ok(decided)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        discord::mock::{DiscordCall, RecordingDiscord},
+        testing::{self, TestContext, GUILD_ID},
+    };
+
+    const CHANNEL_ID: ChannelId = ChannelId(4001);
+
+    /// A rule from a new tier to a new tier with the given discord role, and
+    /// two finished trainings of the lower tier
+    async fn setup(
+        ctx: &TestContext,
+        target_role: RoleId,
+        automatic: bool,
+    ) -> (db::PromotionRule, db::Tier, [db::Training; 2]) {
+        testing::guild(ctx).await;
+        let from = db::Tier::insert(ctx, GUILD_ID, testing::unique("from"))
+            .await
+            .unwrap();
+        let to = db::Tier::insert(ctx, GUILD_ID, testing::unique("to"))
+            .await
+            .unwrap();
+        let mapping = to.add_discord_role(ctx, target_role.0).await.unwrap();
+        let rule = db::PromotionRule::insert(ctx, &mapping, &from, None, 2, automatic)
+            .await
+            .unwrap();
+        let first = testing::training(ctx, db::TrainingState::Created, Some(&from), &[]).await;
+        let second = testing::training(ctx, db::TrainingState::Created, Some(&from), &[]).await;
+        (rule, from, [first, second])
+    }
+
+    async fn finish(ctx: &TestContext, trainings: [db::Training; 2]) {
+        for t in trainings {
+            t.set_state(ctx, db::TrainingState::Finished).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn automatic_rules_assign_the_role_once() {
+        let target_role = RoleId(3101);
+        let ctx = TestContext::new();
+        let (_, from, trainings) = setup(&ctx, target_role, true).await;
+        let regular = testing::user(&ctx, 2101).await;
+        let guest = testing::user(&ctx, 2102).await;
+        for t in &trainings {
+            db::Signup::insert(&ctx, &regular, t).await.unwrap();
+        }
+        db::Signup::insert(&ctx, &guest, &trainings[0])
+            .await
+            .unwrap();
+        finish(&ctx, trainings).await;
+
+        let promoted = evaluate(&ctx, GUILD_ID, &[from.id], Some(CHANNEL_ID))
+            .await
+            .unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].user_id, regular.id);
+        assert_eq!(promoted[0].state, db::PromotionState::Approved);
+
+        // Evaluating again does not promote anyone twice
+        let promoted = evaluate(&ctx, GUILD_ID, &[from.id], Some(CHANNEL_ID))
+            .await
+            .unwrap();
+        assert!(promoted.is_empty());
+
+        let assigned = ctx
+            .discord
+            .calls()
+            .into_iter()
+            .filter_map(|c| match c {
+                DiscordCall::AddMemberRole(g, u, r) if g == GUILD_ID => Some((u, r)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(assigned, vec![(UserId(2101), target_role)]);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn manual_rules_wait_for_approval() {
+        let (target_role, member) = (RoleId(3102), UserId(2103));
+        let mut discord = RecordingDiscord::default();
+        // Only the member that does not have the role yet is asked for
+        discord.give_role(UserId(2104), target_role);
+        let ctx = TestContext::with_discord(discord);
+        let (_, from, trainings) = setup(&ctx, target_role, false).await;
+        for discord_id in [member.0, 2104] {
+            let user = testing::user(&ctx, discord_id).await;
+            for t in &trainings {
+                db::Signup::insert(&ctx, &user, t).await.unwrap();
+            }
+        }
+        finish(&ctx, trainings).await;
+
+        let promoted = evaluate(&ctx, GUILD_ID, &[from.id], Some(CHANNEL_ID))
+            .await
+            .unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].state, db::PromotionState::Pending);
+        let requests = ctx
+            .discord
+            .calls()
+            .into_iter()
+            .filter(|c| matches!(c, DiscordCall::SendMessage(c, _) if *c == CHANNEL_ID))
+            .count();
+        assert_eq!(requests, 1);
+
+        let approved = decide(&ctx, GUILD_ID, promoted[0].id, true).await.unwrap();
+        assert_eq!(approved.state, db::PromotionState::Approved);
+        assert!(ctx.discord.calls().into_iter().any(|c| matches!(
+            c,
+            DiscordCall::AddMemberRole(_, u, r) if u == member && r == target_role
+        )));
+        assert!(decide(&ctx, GUILD_ID, promoted[0].id, false).await.is_err());
+        // A second squadmaker that loaded it before the first one decided
+        assert!(promoted[0]
+            .decide(&ctx, db::PromotionState::Denied)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn refused_promotions_are_offered_again() {
+        let target_role = RoleId(3103);
+        let mut discord = RecordingDiscord::default();
+        discord.broken_members.push(UserId(2105));
+        discord.broken_channels.push(CHANNEL_ID);
+        let ctx = TestContext::with_discord(discord);
+
+        // Automatic: the member discord refuses does not stop the next one
+        let (_, from, trainings) = setup(&ctx, target_role, true).await;
+        for discord_id in [2105, 2106] {
+            let user = testing::user(&ctx, discord_id).await;
+            for t in &trainings {
+                db::Signup::insert(&ctx, &user, t).await.unwrap();
+            }
+        }
+        finish(&ctx, trainings).await;
+        let promoted = evaluate(&ctx, GUILD_ID, &[from.id], Some(CHANNEL_ID))
+            .await
+            .unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].user_id, testing::user(&ctx, 2106).await.id);
+
+        // Manual: a request that could not be posted is not kept
+        let (rule, from, trainings) = setup(&ctx, target_role, false).await;
+        let user = testing::user(&ctx, 2107).await;
+        for t in &trainings {
+            db::Signup::insert(&ctx, &user, t).await.unwrap();
+        }
+        finish(&ctx, trainings).await;
+        let promoted = evaluate(&ctx, GUILD_ID, &[from.id], Some(CHANNEL_ID))
+            .await
+            .unwrap();
+        assert!(promoted.is_empty());
+        assert!(rule.promotions(&ctx).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+//! # roster
+//! Sign-ups of a training grouped by the role they play. Shared by everything that
+//! shows who is coming, split into embed fields and pages that stay within the
+//! discord limits
+
+use crate::db::{self, DBPoolProvider};
+use diesel::QueryResult;
+use serenity::futures::future;
+
+/// Discord allows 1024 characters per field value
+const FIELD_VALUE_LIMIT: usize = 1024;
+/// Discord allows 25 fields and 6000 characters per embed. Leaves room for the
+/// title and footer
+const PAGE_FIELD_LIMIT: usize = 25;
+const PAGE_CHAR_LIMIT: usize = 5000;
+const COMMENT_PREVIEW_LEN: usize = 50;
+
+pub(crate) struct RosterEntry {
+    /// Starts at 1, in the order they signed up
+    pub position: usize,
+    pub user: db::User,
+    pub signup: db::Signup,
+    /// Ordered by preference, main role first
+    pub role_ids: Vec<i32>,
+    pub waitlisted: bool,
+}
+
+pub(crate) struct Roster {
+    pub roles: Vec<db::Role>,
+    pub entries: Vec<RosterEntry>,
+}
+
+impl Roster {
+    pub async fn load(ctx: &impl DBPoolProvider, training: &db::Training) -> QueryResult<Self> {
+        let roles = training.all_roles(ctx).await?;
+        // Sign-ups are loaded in the order they signed up
+        let signups = training.get_signups(ctx).await?;
+        let users = future::try_join_all(signups.iter().map(|s| s.get_user(ctx))).await?;
+        let signup_roles = future::try_join_all(signups.iter().map(|s| s.get_roles(ctx))).await?;
+
+        let entries = signups
+            .into_iter()
+            .zip(users)
+            .zip(signup_roles)
+            .enumerate()
+            .map(|(i, ((signup, user), roles))| RosterEntry {
+                position: i + 1,
+                user,
+                signup,
+                role_ids: roles.into_iter().map(|r| r.id).collect(),
+                waitlisted: matches!(training.max_participants, Some(max) if i >= max as usize),
+            })
+            .collect();
+
+        Ok(Roster { roles, entries })
+    }
+
+    /// One field per role with everyone that picked it as main role. Other roles
+    /// of a user are listed behind the name
+    pub fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        let mut groups = self
+            .roles
+            .iter()
+            .map(|r| (Some(r), Vec::new()))
+            .collect::<Vec<_>>();
+        let mut without_role = Vec::new();
+
+        for e in &self.entries {
+            let mut line = format!("`{:>2}` {}", e.position, e.user.gw2_id);
+            if e.role_ids.len() > 1 {
+                let others = e.role_ids[1..]
+                    .iter()
+                    .filter_map(|id| self.roles.iter().find(|r| r.id == *id))
+                    .map(|r| r.repr.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                line.push_str(&format!(" ✚ {}", others));
+            }
+            if e.waitlisted {
+                line.push_str(" ⏳");
+            }
+            if let Some(comment) = &e.signup.comment {
+                line.push_str(&format!(" — _{}_", preview(comment)));
+            }
+
+            let group = e.role_ids.first().and_then(|id| {
+                groups
+                    .iter_mut()
+                    .find(|(r, _)| matches!(r, Some(r) if r.id == *id))
+            });
+            match group {
+                Some((_, lines)) => lines.push(line),
+                None => without_role.push(line),
+            }
+        }
+        groups.push((None, without_role));
+
+        for (role, lines) in groups.into_iter().filter(|(_, l)| !l.is_empty()) {
+            let name = match role {
+                Some(r) => format!("{} {} ({})", r.emoji, r.title, lines.len()),
+                None => format!("No role ({})", lines.len()),
+            };
+            for value in chunk_lines(&lines) {
+                fields.push((name.clone(), value));
+            }
+        }
+        fields
+    }
+}
+
+fn preview(comment: &str) -> String {
+    let flat = comment.replace('\n', " ");
+    if flat.chars().count() > COMMENT_PREVIEW_LEN {
+        format!(
+            "{}…",
+            flat.chars().take(COMMENT_PREVIEW_LEN).collect::<String>()
+        )
+    } else {
+        flat
+    }
+}
+
+/// Joins the lines into as few field values as possible. Lines that are too
+/// long on their own are cut
+pub(crate) fn chunk_lines(lines: &[String]) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        let line = if line.chars().count() > FIELD_VALUE_LIMIT {
+            line.chars().take(FIELD_VALUE_LIMIT).collect()
+        } else {
+            line.clone()
+        };
+        // Plus one for the line break
+        if !current.is_empty()
+            && current.chars().count() + 1 + line.chars().count() > FIELD_VALUE_LIMIT
+        {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&line);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Splits the fields into pages that each fit into one embed
+pub(crate) fn paginate(fields: Vec<(String, String)>) -> Vec<Vec<(String, String)>> {
+    let mut pages: Vec<Vec<(String, String)>> = Vec::new();
+    let mut current = Vec::new();
+    let mut chars = 0;
+    for (name, value) in fields {
+        let len = name.chars().count() + value.chars().count();
+        if !current.is_empty()
+            && (current.len() == PAGE_FIELD_LIMIT || chars + len > PAGE_CHAR_LIMIT)
+        {
+            pages.push(std::mem::take(&mut current));
+            chars = 0;
+        }
+        chars += len;
+        current.push((name, value));
+    }
+    if !current.is_empty() {
+        pages.push(current);
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signups_are_grouped_by_main_role() {
+        let role = |id, title: &str| db::Role {
+            id,
+            title: title.to_string(),
+            repr: title.to_lowercase(),
+            emoji: db::Emoji::Unicode(String::from("🛡")),
+            active: true,
+            priority: 2,
+            guild_id: 1,
+            category: None,
+        };
+        let entry = |position: usize, role_ids: Vec<i32>, comment: Option<&str>| RosterEntry {
+            position,
+            user: db::User {
+                id: position as i32,
+                discord_id: position as i64,
+                gw2_id: format!("Account.{}", position),
+            },
+            signup: db::Signup {
+                id: position as i32,
+                user_id: position as i32,
+                training_id: 1,
+                comment: comment.map(str::to_string),
+                created_at: chrono::NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0),
+                updated_at: chrono::NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0),
+            },
+            role_ids,
+            waitlisted: position > 2,
+        };
+        let roster = Roster {
+            roles: vec![role(1, "Tank"), role(2, "Healer")],
+            entries: vec![
+                entry(1, vec![2, 1], None),
+                entry(2, vec![2], Some("late")),
+                entry(3, vec![1], None),
+            ],
+        };
+
+        let fields = roster.fields();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].0, "🛡 Tank (1)");
+        assert_eq!(fields[0].1, "` 3` Account.3 ⏳");
+        assert_eq!(fields[1].0, "🛡 Healer (2)");
+        assert_eq!(
+            fields[1].1,
+            "` 1` Account.1 ✚ tank\n` 2` Account.2 — _late_"
+        );
+    }
+
+    #[test]
+    fn chunks_and_pages_stay_within_limits() {
+        let lines = (0..200)
+            .map(|i| format!("`{:>3}` Some Account.{:04} — _a comment_", i, i))
+            .collect::<Vec<_>>();
+        let chunks = chunk_lines(&lines);
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|c| c.chars().count() <= FIELD_VALUE_LIMIT));
+        assert_eq!(chunks.join("\n"), lines.join("\n"));
+
+        let fields = chunks
+            .into_iter()
+            .cycle()
+            .take(60)
+            .map(|c| (String::from("Role"), c))
+            .collect::<Vec<_>>();
+        let pages = paginate(fields);
+        assert_eq!(pages.iter().map(|p| p.len()).sum::<usize>(), 60);
+        for page in pages {
+            assert!(page.len() <= PAGE_FIELD_LIMIT);
+            let chars = page
+                .iter()
+                .map(|(n, v)| n.chars().count() + v.chars().count())
+                .sum::<usize>();
+            assert!(chars <= PAGE_CHAR_LIMIT);
+        }
+    }
+}
@@ -1,5 +1,11 @@
 use crate::embeds::CrossroadsEmbeds;
-use crate::{data, data::SignupBoardData, db, interactions, logging::LogTrace};
+use crate::{
+    data::{SignupBoardData, SignupBoardUpdateData},
+    db::{self, DBPoolProvider},
+    discord::{DiscordFacade, MessageContent},
+    interactions,
+    logging::LogTrace,
+};
 use anyhow::Result;
 use chrono::NaiveDate;
 use itertools::Itertools;
@@ -18,8 +24,11 @@ const LOCK_EMOJI: char = '🔒';
 
 // Hold on to often used values
 pub struct SignupBoard {
+    guild_id: GuildId,
     pub overview_channel_id: Option<ChannelId>,
     pub overview_message_id: Option<MessageId>,
+    // Loaded on first use and dropped whenever trainings or tiers change
+    active_trainings: Option<Arc<Vec<db::TrainingWithTier>>>,
 }
 
 #[derive(Debug)]
@@ -43,20 +52,6 @@ impl std::fmt::Display for SignupBoardError {
 
 impl std::error::Error for SignupBoardError {}
 
-// loads the main guild id, that is always set
-async fn load_guild_id(ctx: &Context) -> Result<GuildId> {
-    // Load guild id provided on startup
-    let guild_id = ctx
-        .data
-        .read()
-        .await
-        .get::<data::ConfigValuesData>()
-        .unwrap()
-        .main_guild_id;
-
-    Ok(guild_id)
-}
-
 pub(crate) fn title_sort_value(t: &db::Training) -> u64 {
     if t.title.contains("Beginner") {
         return 10;
@@ -71,30 +66,132 @@ pub(crate) fn title_sort_value(t: &db::Training) -> u64 {
 }
 
 impl SignupBoard {
-    // get a lock on the SignupBoardConfig
-    pub async fn get(ctx: &Context) -> Arc<RwLock<SignupBoard>> {
-        ctx.data
+    /// An empty board that has neither channel nor message set
+    pub fn new(guild_id: GuildId) -> Self {
+        SignupBoard {
+            guild_id,
+            overview_channel_id: None,
+            overview_message_id: None,
+            active_trainings: None,
+        }
+    }
+
+    // get a lock on the SignupBoard of the guild
+    pub async fn get(ctx: &Context, guild_id: GuildId) -> Arc<RwLock<SignupBoard>> {
+        let boards = ctx
+            .data
             .read()
             .await
             .get::<SignupBoardData>()
             .unwrap()
+            .clone();
+
+        if let Some(board) = boards.read().await.get(&guild_id) {
+            return board.clone();
+        }
+        let mut boards = boards.write().await;
+        boards
+            .entry(guild_id)
+            .or_insert_with(|| Arc::new(RwLock::new(SignupBoard::new(guild_id))))
             .clone()
     }
 
+    /// The boards of all guilds
+    pub async fn all(ctx: &Context) -> Vec<Arc<RwLock<SignupBoard>>> {
+        let boards = ctx
+            .data
+            .read()
+            .await
+            .get::<SignupBoardData>()
+            .unwrap()
+            .clone();
+        let boards = boards.read().await;
+        boards.values().cloned().collect()
+    }
+
+    pub fn guild_id(&self) -> GuildId {
+        self.guild_id
+    }
+
+    /// Active trainings of the guild with the tier required to join them. Loaded from
+    /// the db on first use and kept until [`SignupBoard::invalidate_trainings`] is called
+    pub async fn active_trainings(
+        ctx: &Context,
+        guild_id: GuildId,
+    ) -> Result<Arc<Vec<db::TrainingWithTier>>> {
+        let board = Self::get(ctx, guild_id).await;
+        if let Some(cached) = &board.read().await.active_trainings {
+            return Ok(cached.clone());
+        }
+
+        let mut board = board.write().await;
+        // Someone else might have loaded them while waiting for the lock
+        if let Some(cached) = &board.active_trainings {
+            return Ok(cached.clone());
+        }
+        let loaded = Arc::new(db::Training::all_active_with_tier(ctx, guild_id).await?);
+        board.active_trainings = Some(loaded.clone());
+        Ok(loaded)
+    }
+
+    /// Requests a refresh of the overview message of the guild. Changes in quick
+    /// succession are combined into a single update by the signup board task
+    pub async fn request_update(ctx: &Context, guild_id: GuildId) {
+        let sender = ctx
+            .data
+            .read()
+            .await
+            .get::<SignupBoardUpdateData>()
+            .unwrap()
+            .clone();
+        // Only fails if the task is gone. Nothing left to update then
+        let _ = sender.send(guild_id);
+    }
+
+    /// For changes that are not bound to a guild, like a user removing all their data
+    pub async fn request_update_all(ctx: &Context) {
+        for board in Self::all(ctx).await {
+            let guild_id = board.read().await.guild_id;
+            Self::request_update(ctx, guild_id).await;
+        }
+    }
+
+    /// Has to be called whenever trainings, their state or tiers of the guild change
+    pub async fn invalidate_trainings(ctx: &Context, guild_id: GuildId) {
+        Self::get(ctx, guild_id)
+            .await
+            .write()
+            .await
+            .active_trainings = None;
+    }
+
     pub async fn load_from_db(&mut self, ctx: &Context) -> Result<()> {
+        let guild_id = self.guild_id;
         let new_board = SignupBoard {
-            overview_channel_id: match db::Config::load(ctx, OVERVIEW_CHANNEL_ID.to_string()).await
+            guild_id,
+            overview_channel_id: match db::Config::load(
+                ctx,
+                guild_id,
+                OVERVIEW_CHANNEL_ID.to_string(),
+            )
+            .await
             {
                 Ok(conf) => Some(conf.value.parse::<ChannelId>()?),
                 Err(diesel::NotFound) => None,
                 Err(e) => return Err(e.into()),
             },
-            overview_message_id: match db::Config::load(ctx, OVERVIEW_MESSAGE_ID.to_string()).await
+            overview_message_id: match db::Config::load(
+                ctx,
+                guild_id,
+                OVERVIEW_MESSAGE_ID.to_string(),
+            )
+            .await
             {
                 Ok(conf) => Some(conf.value.parse::<u64>()?.into()),
                 Err(diesel::NotFound) => None,
                 Err(e) => return Err(e.into()),
             },
+            active_trainings: None,
         };
         // overwrite at once and not value by value
         let _ = mem::replace(self, new_board);
@@ -107,7 +204,7 @@ impl SignupBoard {
                 name: OVERVIEW_CHANNEL_ID.to_string(),
                 value: oci.to_string(),
             }
-            .save(ctx)
+            .save(ctx, self.guild_id)
             .await?;
         }
 
@@ -116,7 +213,7 @@ impl SignupBoard {
                 name: OVERVIEW_MESSAGE_ID.to_string(),
                 value: omi.to_string(),
             }
-            .save(ctx)
+            .save(ctx, self.guild_id)
             .await?;
         }
 
@@ -131,8 +228,7 @@ impl SignupBoard {
         trace: LogTrace,
     ) -> Result<()> {
         trace.step("Looking for channel in guild");
-        let gid = load_guild_id(ctx).await?;
-        let channels = gid.channels(ctx).await?;
+        let channels = self.guild_id.channels(ctx).await?;
         if let Some(channel) = channels.get(&chan) {
             trace.step("Found. Setting new channel internally");
             self.overview_channel_id = Some(channel.id);
@@ -143,7 +239,11 @@ impl SignupBoard {
     }
 
     /// Creates the message for the overview and saves the message id internally
-    pub async fn create_overview(&mut self, ctx: &Context, trace: LogTrace) -> Result<()> {
+    pub async fn create_overview(
+        &mut self,
+        ctx: &impl DiscordFacade,
+        trace: LogTrace,
+    ) -> Result<()> {
         trace.step("Loading channel for overview");
         let chan = match self.overview_channel_id {
             Some(c) => c,
@@ -151,20 +251,27 @@ impl SignupBoard {
         };
 
         trace.step("Writing initial message to overview");
-        let msg = chan
-            .send_message(ctx, |m| {
-                m.set_embed(CreateEmbed::info_box("Setting up overview message"))
-            })
+        let msg = ctx
+            .send_message(
+                chan,
+                MessageContent {
+                    embeds: vec![CreateEmbed::info_box("Setting up overview message")],
+                    ..Default::default()
+                },
+            )
             .await?;
 
         trace.step("Setting new message internally");
-        self.overview_message_id = Some(msg.id);
+        self.overview_message_id = Some(msg);
 
         Ok(())
     }
 
     /// Loads all relevant training(s) from the db and updates the overview message
-    pub async fn update_overview(&self, ctx: &Context, trace: LogTrace) -> Result<()> {
+    pub async fn update_overview<C>(&self, ctx: &C, trace: LogTrace) -> Result<()>
+    where
+        C: DBPoolProvider + DiscordFacade,
+    {
         trace.step("Loading overview information");
         let msg = match self.overview_message_id {
             Some(m) => m,
@@ -176,148 +283,453 @@ impl SignupBoard {
         };
 
         trace.step("Loading training(s)");
-        let active_trainings = db::Training::all_active(ctx).await?;
-
-        struct TierInfo {
-            _tier: db::Tier,
-            discord: Vec<RoleId>,
-        }
-
-        struct TrainingInfo {
-            training: db::Training,
-            signup_count: i64,
-            tier_info: Option<TierInfo>,
-            bosses: Vec<db::TrainingBoss>,
-        }
-
-        trace.step("Loading additional training info");
-        let mut trainings: Vec<TrainingInfo> = Vec::new();
-        for training in active_trainings {
-            let signup_count = training.get_signup_count(ctx).await?;
-            let tier = training.get_tier(ctx).await.transpose()?;
-            let tier_info = if let Some(_tier) = tier {
-                let discord = _tier
-                    .get_discord_roles(ctx)
-                    .await?
-                    .into_iter()
-                    .map(|t| RoleId::from(t.discord_role_id as u64))
-                    .collect::<Vec<_>>();
-
-                Some(TierInfo { _tier, discord })
-            } else {
-                None
-            };
-            let mut bosses = training.all_training_bosses(ctx).await?;
-            bosses.sort_by_key(|b| b.position);
-            bosses.sort_by_key(|b| b.wing);
-
-            trainings.push(TrainingInfo {
-                training,
-                signup_count,
-                tier_info,
-                bosses,
-            });
-        }
+        let overview = db::SignupBoardOverview::load(ctx, self.guild_id).await?;
+        let mut trainings = overview.trainings;
 
         // Sort by custom names and dates
         trainings.sort_by(|a, b| title_sort_value(&b.training).cmp(&title_sort_value(&a.training)));
         trainings.sort_by(|a, b| a.training.date.date().cmp(&b.training.date.date()));
 
-        let mut _groups: Vec<(NaiveDate, Vec<&TrainingInfo>)> = Vec::new();
+        let mut groups: Vec<(NaiveDate, Vec<&db::TrainingOverview>, i64)> = Vec::new();
         for (d, v) in trainings
             .iter()
             .group_by(|t| t.training.date.date())
             .into_iter()
         {
-            _groups.push((d, v.collect()));
-        }
-
-        let mut groups: Vec<(NaiveDate, Vec<&TrainingInfo>, usize)> =
-            Vec::with_capacity(_groups.len());
-        for (d, v) in _groups {
-            // FIXME do this without extra db access
-            let mut total_users = db::User::by_signed_up_and_date(ctx, d).await?;
-            total_users.sort_by_key(|u| u.id);
-            total_users.dedup_by_key(|u| u.id);
-            groups.push((d, v, total_users.len()));
+            let total_users = overview.users_per_day.get(&d).copied().unwrap_or(0);
+            groups.push((d, v.collect(), total_users));
         }
 
         let base_emb = CreateEmbed::xdefault();
+        let mut content = MessageContent::default();
 
-        trace.step("Updating overview message");
-        chan.edit_message(ctx, msg, |m| {
-            m.add_embed(|e| {
-                e.0 = base_emb.0.clone();
-                e.title("Sign up for a training");
-                e.field(
-                    "How to",
-                    "\
+        let mut e = base_emb.clone();
+        e.title("Sign up for a training");
+        e.field(
+            "How to",
+            "\
 To **sign up**, **sign out** or to **edit** your sign-up click the button at the end of the message",
-                    false);
-                e.field(
-                    "Legend",
-                    format!(
-                        "{} => {}\n{} => {}\n{} => {}",
-                        GREEN_CIRCLE_EMOJI, "You can join this training or edit/remove your sign-up",
-                        LOCK_EMOJI, "The training is locked. Most likely squadmaking is in progress",
-                        RUNNING_EMOJI, "The training is currently ongoing"
-                        ),
-                    false);
-                e.footer(|f| f.text("Last update"));
-                e.timestamp(chrono::Utc::now())
-            });
-            for (date, trainings, total) in groups {
-                m.add_embed(|e| {
-                    e.0 = base_emb.0.clone();
-                    e.title(date.format("__**%A**, %v__"));
-                    e.description(&format!("Total sign-up count: {}", total));
-                    for t in trainings {
-                        let mut details = format!("`     Time    `   <t:{}:t>", t.training.date.timestamp());
-                        if let Some(tier) = &t.tier_info {
-                            details.push_str(&format!("\n`Tier required`   {}", tier.discord.iter().map(|d| Mention::from(*d)).join(" ")));
-                        } else {
-                            details.push_str("\n`Tier required`   None");
-                        }
-                        details.push_str(&format!("\n`Sign-up count`   {}", t.signup_count));
-                        match t.bosses.len() {
-                            0 => (),
-                            1 => details.push_str("\n`     Boss    `   "),
-                            _ => details.push_str("\n`  Boss Pool  `   "),
-                        }
-                        let boss_emojis = t.bosses
+            false);
+        e.field(
+            "Legend",
+            format!(
+                "{} => {}\n{} => {}\n{} => {}",
+                GREEN_CIRCLE_EMOJI,
+                "You can join this training or edit/remove your sign-up",
+                LOCK_EMOJI,
+                "The training is locked. Most likely squadmaking is in progress",
+                RUNNING_EMOJI,
+                "The training is currently ongoing"
+            ),
+            false,
+        );
+        e.footer(|f| f.text("Last update"));
+        e.timestamp(chrono::Utc::now());
+        content.embeds.push(e);
+
+        for (date, trainings, total) in groups {
+            let mut e = base_emb.clone();
+            e.title(date.format("__**%A**, %v__"));
+            e.description(&format!("Total sign-up count: {}", total));
+            for t in trainings {
+                let mut details =
+                    format!("`     Time    `   <t:{}:t>", t.training.date.timestamp());
+                if let Some(tier) = &t.tier {
+                    // Members of higher tiers can join as well, so their roles are listed too
+                    details.push_str(&format!(
+                        "\n`Tier required`   {} {}",
+                        tier.name,
+                        t.tier_mappings
                             .iter()
-                            .map(|b| Mention::from(EmojiId::from(b.emoji as u64)).to_string())
-                            .collect::<Vec<_>>()
-                            .join(" ");
-                        details.push_str(&boss_emojis);
-
-                        e.field(
-                            format!(
-                                "{}    **{}**",
-                                match t.training.state {
-                                    db::TrainingState::Created => CONSTRUCTION_SITE_EMOJI,
-                                    db::TrainingState::Open => GREEN_CIRCLE_EMOJI,
-                                    db::TrainingState::Closed => LOCK_EMOJI,
-                                    db::TrainingState::Started => RUNNING_EMOJI,
-                                    db::TrainingState::Finished => CROSS_EMOJI,
-                                },
-                                &t.training.title),
-                            details,
-                            false
-                        );
+                            .map(|m| Mention::from(RoleId::from(m.discord_role_id as u64)))
+                            .join(" ")
+                    ));
+                } else {
+                    details.push_str("\n`Tier required`   None");
+                }
+                let signup_count = match t.training.max_participants.map(i64::from) {
+                    Some(max) if t.signup_count > max => {
+                        format!("{}/{} (+{} waitlisted)", max, max, t.signup_count - max)
                     }
-                    e
-                });
-            }
-            m.components(|c| {
-                if !trainings.is_empty() {
-                    c.add_action_row(interactions::overview_action_row());
+                    Some(max) => format!("{}/{}", t.signup_count, max),
+                    None => t.signup_count.to_string(),
+                };
+                details.push_str(&format!("\n`Sign-up count`   {}", signup_count));
+                if !t.composition.is_empty() {
+                    details.push_str(&format!(
+                        "\n` Composition `   {}",
+                        t.composition.iter().join(" · ")
+                    ));
                 }
-                c
-            });
-            m
-        }).await?;
+                match t.bosses.len() {
+                    0 => (),
+                    1 => details.push_str("\n`     Boss    `   "),
+                    _ => details.push_str("\n`  Boss Pool  `   "),
+                }
+                let boss_groups = t.bosses.iter().group_by(|b| b.group());
+                let boss_emojis = boss_groups
+                    .into_iter()
+                    .map(|((category, number), bosses)| {
+                        format!(
+                            "`{}` {}",
+                            category.group_short_name(number),
+                            bosses.map(|b| b.emoji.to_string()).join(" ")
+                        )
+                    })
+                    .join("  ");
+                details.push_str(&boss_emojis);
+
+                e.field(
+                    format!(
+                        "{}    **{}**",
+                        match t.training.state {
+                            db::TrainingState::Created => CONSTRUCTION_SITE_EMOJI,
+                            db::TrainingState::Open => GREEN_CIRCLE_EMOJI,
+                            db::TrainingState::Closed => LOCK_EMOJI,
+                            db::TrainingState::Started => RUNNING_EMOJI,
+                            db::TrainingState::Finished => CROSS_EMOJI,
+                        },
+                        &t.training.title
+                    ),
+                    details,
+                    false,
+                );
+            }
+            content.embeds.push(e);
+        }
+
+        if !trainings.is_empty() {
+            content
+                .components
+                .add_action_row(interactions::overview_action_row());
+        }
+
+        trace.step("Updating overview message");
+        ctx.edit_message(chan, msg, content).await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        discord::mock::DiscordCall,
+        testing::{self, TestContext},
+    };
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn update_overview_lists_active_trainings() {
+        let ctx = TestContext::new();
+        let chan = ChannelId(4001);
+        let mut board = SignupBoard {
+            overview_channel_id: Some(chan),
+            ..SignupBoard::new(testing::GUILD_ID)
+        };
+        board.create_overview(&ctx, ctx.trace()).await.unwrap();
+        let msg = board.overview_message_id.unwrap();
+        assert!(matches!(
+            &ctx.discord.calls()[..],
+            [DiscordCall::SendMessage(c, content)] if *c == chan && content.embeds.len() == 1
+        ));
+
+        let user = testing::user(&ctx, 5001).await;
+        let role = testing::role(&ctx).await;
+        let open = testing::training(&ctx, db::TrainingState::Open, None, &[&role]).await;
+        let created = testing::training(&ctx, db::TrainingState::Created, None, &[&role]).await;
+        let signup = db::Signup::insert(&ctx, &user, &open).await.unwrap();
+        signup.add_role(&ctx, &role, 1).await.unwrap();
+        let emojis = [
+            db::Emoji::from(EmojiId(4101)),
+            db::Emoji::Unicode(String::from("🐉")),
+        ];
+        for (position, emoji) in (1..).zip(emojis) {
+            let repr = testing::unique("boss");
+            let boss = db::TrainingBoss::insert(
+                &ctx,
+                testing::GUILD_ID,
+                repr.clone(),
+                repr,
+                db::BossCategory::Raid,
+                1,
+                position,
+                emoji,
+                None,
+            )
+            .await
+            .unwrap();
+            open.add_training_boss(&ctx, boss.id).await.unwrap();
+        }
+
+        board.update_overview(&ctx, ctx.trace()).await.unwrap();
+
+        let content = ctx.discord.last_edit(chan, msg).unwrap();
+        let rendered = content
+            .embeds
+            .iter()
+            .map(|e| serde_json::to_string(&e.0).unwrap())
+            .collect::<String>();
+        assert!(rendered.contains(&open.title));
+        assert!(!rendered.contains(&created.title));
+        assert!(rendered.contains("Sign-up count`   1"));
+        assert!(rendered.contains("<:omitted:4101> 🐉"));
+        assert_eq!(content.components.0.len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn overview_counts_composition() {
+        let ctx = TestContext::new();
+        let tank = testing::role(&ctx).await;
+        let mut healers = Vec::new();
+        for _ in 0..2 {
+            let repr = testing::unique("heal");
+            let role = db::Role::insert(
+                &ctx,
+                testing::GUILD_ID,
+                repr.clone(),
+                repr,
+                EmojiId(1).into(),
+                None,
+                Some(String::from("healer")),
+            )
+            .await
+            .unwrap();
+            healers.push(role);
+        }
+        let draft = db::TrainingDraft {
+            title: testing::unique("Training"),
+            date: testing::tomorrow(),
+            tier_id: None,
+            max_participants: Some(3),
+            role_ids: vec![tank.id, healers[0].id, healers[1].id],
+            boss_ids: Vec::new(),
+            requirements: vec![
+                db::RequirementDraft {
+                    role_id: Some(tank.id),
+                    category: None,
+                    min_count: 1,
+                    max_count: Some(1),
+                },
+                db::RequirementDraft {
+                    role_id: None,
+                    category: Some(String::from("healer")),
+                    min_count: 2,
+                    max_count: None,
+                },
+            ],
+        };
+        let training = db::Training::insert_drafts(&ctx, testing::GUILD_ID, vec![draft])
+            .await
+            .unwrap()
+            .remove(0)
+            .set_state(&ctx, db::TrainingState::Open)
+            .await
+            .unwrap();
+        // The second tank plays healer since the tank spot is taken. The last
+        // sign-up is waitlisted and not counted
+        for (id, roles) in [
+            (5101, vec![&tank, &healers[0]]),
+            (5102, vec![&tank, &healers[0]]),
+            (5103, vec![&healers[1]]),
+            (5104, vec![&tank]),
+        ] {
+            let user = testing::user(&ctx, id).await;
+            let signup = db::Signup::insert(&ctx, &user, &training).await.unwrap();
+            for (rank, r) in (1..).zip(roles) {
+                signup.add_role(&ctx, r, rank).await.unwrap();
+            }
+        }
+
+        let overview = db::SignupBoardOverview::load(&ctx, testing::GUILD_ID)
+            .await
+            .unwrap();
+        let t = overview
+            .trainings
+            .iter()
+            .find(|t| t.training.id == training.id)
+            .unwrap();
+        let composition = t.composition.iter().map(|c| c.to_string()).collect_vec();
+        assert_eq!(
+            composition,
+            [
+                format!("✅ {} 1/1-1", tank.repr),
+                String::from("✅ healer 2/2"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn update_overview_requires_message() {
+        let ctx = TestContext::new();
+        let board = SignupBoard {
+            overview_channel_id: Some(ChannelId(4002)),
+            ..SignupBoard::new(testing::GUILD_ID)
+        };
+
+        let err = board.update_overview(&ctx, ctx.trace()).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<SignupBoardError>(),
+            Some(SignupBoardError::OverviewMessageNotSet)
+        ));
+        assert!(ctx.discord.calls().is_empty());
+    }
+
+    // How the board loaded its data before everything was batched into
+    // SignupBoardOverview. Kept as baseline for the benchmark
+    async fn load_per_training(
+        ctx: &TestContext,
+    ) -> (Vec<(i32, i64, usize, Vec<i32>)>, Vec<usize>) {
+        let mut trainings = Vec::new();
+        let mut days = Vec::new();
+        for t in db::Training::all_active(ctx, testing::GUILD_ID)
+            .await
+            .unwrap()
+        {
+            let signup_count = t.get_signup_count(ctx).await.unwrap();
+            let tier_roles = match t.get_tier(ctx).await.transpose().unwrap() {
+                Some(tier) => tier.get_discord_roles(ctx).await.unwrap().len(),
+                None => 0,
+            };
+            let mut bosses = t.all_training_bosses(ctx).await.unwrap();
+            bosses.sort_by_key(|b| b.sort_key());
+            days.push(t.date.date());
+            trainings.push((
+                t.id,
+                signup_count,
+                tier_roles,
+                bosses.iter().map(|b| b.id).collect(),
+            ));
+        }
+        days.sort();
+        days.dedup();
+        let mut users = Vec::new();
+        for d in days {
+            let mut u = db::User::by_signed_up_and_date(ctx, testing::GUILD_ID, d)
+                .await
+                .unwrap();
+            u.sort_by_key(|u| u.id);
+            u.dedup_by_key(|u| u.id);
+            users.push(u.len());
+        }
+        trainings.sort();
+        (trainings, users)
+    }
+
+    async fn load_batched(ctx: &TestContext) -> (Vec<(i32, i64, usize, Vec<i32>)>, Vec<usize>) {
+        let overview = db::SignupBoardOverview::load(ctx, testing::GUILD_ID)
+            .await
+            .unwrap();
+        let mut trainings: Vec<_> = overview
+            .trainings
+            .iter()
+            .map(|t| {
+                (
+                    t.training.id,
+                    t.signup_count,
+                    t.tier_mappings.len(),
+                    t.bosses.iter().map(|b| b.id).collect(),
+                )
+            })
+            .collect();
+        trainings.sort();
+        let users = overview
+            .users_per_day
+            .into_iter()
+            .sorted()
+            .map(|(_, u)| u as usize)
+            .collect();
+        (trainings, users)
+    }
+
+    // cargo test --release bench_overview_queries -- --ignored --nocapture
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn bench_overview_queries() {
+        const DAYS: i64 = 7;
+        const TRAININGS_PER_DAY: i64 = 4;
+        const USERS: u64 = 40;
+        const RUNS: u32 = 20;
+
+        let ctx = TestContext::new();
+        let role = testing::role(&ctx).await;
+        let tier = db::Tier::insert(&ctx, testing::GUILD_ID, testing::unique("tier"))
+            .await
+            .unwrap();
+        tier.add_discord_role(&ctx, 6001).await.unwrap();
+        tier.add_discord_role(&ctx, 6002).await.unwrap();
+        let mut bosses = Vec::new();
+        for i in 0..5 {
+            let repr = testing::unique("boss");
+            let boss = db::TrainingBoss::insert(
+                &ctx,
+                testing::GUILD_ID,
+                repr.clone(),
+                repr,
+                db::BossCategory::Raid,
+                i % 2,
+                i,
+                EmojiId(1).into(),
+                None,
+            )
+            .await
+            .unwrap();
+            bosses.push(boss);
+        }
+        let mut users = Vec::new();
+        for i in 0..USERS {
+            users.push(testing::user(&ctx, 7000 + i).await);
+        }
+
+        for d in 0..DAYS {
+            for n in 0..TRAININGS_PER_DAY {
+                let date = testing::tomorrow() + chrono::Duration::days(d);
+                let tier_id = (n % 2 == 0).then_some(tier.id);
+                let t = db::Training::insert(
+                    &ctx,
+                    testing::GUILD_ID,
+                    testing::unique("Training"),
+                    date,
+                    tier_id,
+                )
+                .await
+                .unwrap();
+                for b in bosses.iter().skip(n as usize) {
+                    t.add_training_boss(&ctx, b.id).await.unwrap();
+                }
+                let t = t.set_state(&ctx, db::TrainingState::Open).await.unwrap();
+                for u in users.iter().skip((n * 5) as usize) {
+                    db::Signup::insert(&ctx, u, &t).await.unwrap();
+                }
+            }
+        }
+        // a training of the same day that is not active still counts towards the users of that day
+        let created = testing::training(&ctx, db::TrainingState::Created, None, &[&role]).await;
+        let late = testing::user(&ctx, 7999).await;
+        db::Signup::insert(&ctx, &late, &created).await.unwrap();
+
+        assert_eq!(load_per_training(&ctx).await, load_batched(&ctx).await);
+
+        let start = std::time::Instant::now();
+        for _ in 0..RUNS {
+            load_per_training(&ctx).await;
+        }
+        let per_training = start.elapsed() / RUNS;
+
+        let start = std::time::Instant::now();
+        for _ in 0..RUNS {
+            load_batched(&ctx).await;
+        }
+        let batched = start.elapsed() / RUNS;
+
+        println!(
+            "{} trainings: per training {:?}, batched {:?}",
+            DAYS * TRAININGS_PER_DAY,
+            per_training,
+            batched
+        );
+    }
+}
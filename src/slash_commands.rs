@@ -1,8 +1,9 @@
 use std::str::FromStr;
 
 use serenity::{
-    builder::CreateApplicationCommand, client::Context,
-    model::interactions::application_command::ApplicationCommandInteraction,
+    builder::CreateApplicationCommand,
+    client::Context,
+    model::{id::GuildId, interactions::application_command::ApplicationCommandInteraction},
 };
 
 use tracing::error;
@@ -19,12 +20,17 @@ impl std::fmt::Display for SlashCommandParseError {
 impl std::error::Error for SlashCommandParseError {}
 
 mod config;
+mod profile;
 mod register;
+mod setup;
 mod training;
 mod training_boss;
 mod training_role;
 mod training_tier;
 
+pub(crate) use register::register_user;
+pub(crate) use setup::load_guild;
+
 /// All slash commands
 #[derive(Debug)]
 pub enum AppCommands {
@@ -35,10 +41,12 @@ pub enum AppCommands {
     TrainingRole,
     TrainingTier,
     Config,
+    Profile,
+    Setup,
 }
 
-/// All commands that should be created when the bot starts
-const DEFAULT_COMMANDS: [AppCommands; 7] = [
+/// All commands that should be created for guilds that went through the setup
+const DEFAULT_COMMANDS: [AppCommands; 9] = [
     AppCommands::Register,
     AppCommands::Unregister,
     AppCommands::Training,
@@ -46,8 +54,13 @@ const DEFAULT_COMMANDS: [AppCommands; 7] = [
     AppCommands::TrainingRole,
     AppCommands::TrainingTier,
     AppCommands::Config,
+    AppCommands::Profile,
+    AppCommands::Setup,
 ];
 
+/// The only command available before a guild went through the setup
+const SETUP_COMMANDS: [AppCommands; 1] = [AppCommands::Setup];
+
 impl FromStr for AppCommands {
     type Err = SlashCommandParseError;
 
@@ -60,6 +73,8 @@ impl FromStr for AppCommands {
             training_role::CMD_TRAINING_ROLE => Ok(Self::TrainingRole),
             training_tier::CMD_TRAINING_TIER => Ok(Self::TrainingTier),
             config::CMD_CONFIG => Ok(Self::Config),
+            profile::CMD_PROFILE => Ok(Self::Profile),
+            setup::CMD_SETUP => Ok(Self::Setup),
             _ => Err(SlashCommandParseError(s.to_owned())),
         }
     }
@@ -75,6 +90,8 @@ impl AppCommands {
             Self::TrainingRole => training_role::create(),
             Self::TrainingTier => training_tier::create(),
             Self::Config => config::create(),
+            Self::Profile => profile::create(),
+            Self::Setup => setup::create(),
         }
     }
 
@@ -85,6 +102,10 @@ impl AppCommands {
             .collect::<Vec<_>>()
     }
 
+    pub fn create_setup() -> Vec<CreateApplicationCommand> {
+        SETUP_COMMANDS.iter().map(Self::create).collect::<Vec<_>>()
+    }
+
     async fn handle(&self, ctx: &Context, aci: &ApplicationCommandInteraction) {
         match self {
             Self::Register => register::handle_reg(ctx, aci).await,
@@ -94,10 +115,29 @@ impl AppCommands {
             Self::TrainingRole => training_role::handle(ctx, aci).await,
             Self::TrainingTier => training_tier::handle(ctx, aci).await,
             Self::Config => config::handle(ctx, aci).await,
+            Self::Profile => profile::handle(ctx, aci).await,
+            Self::Setup => setup::handle(ctx, aci).await,
         }
     }
 }
 
+/// Replaces the commands of the guild. Guilds that are not set up only get /setup
+pub(crate) async fn register_commands(
+    ctx: &Context,
+    guild_id: GuildId,
+    set_up: bool,
+) -> serenity::Result<()> {
+    let cmds = if set_up {
+        AppCommands::create_default()
+    } else {
+        AppCommands::create_setup()
+    };
+    guild_id
+        .set_application_commands(ctx, |c| c.set_application_commands(cmds))
+        .await?;
+    Ok(())
+}
+
 pub async fn slash_command_interaction(ctx: &Context, aci: &ApplicationCommandInteraction) {
     // Consider reworking to aci.data.id
     match AppCommands::from_str(&aci.data.name) {
@@ -109,8 +149,19 @@ pub async fn slash_command_interaction(ctx: &Context, aci: &ApplicationCommandIn
 pub mod helpers {
     use std::collections::HashMap;
 
+    use anyhow::{anyhow, Context, Result};
     use serde_json::Value;
-    use serenity::model::interactions::application_command::ApplicationCommandInteractionDataOption;
+    use serenity::{
+        client::Context as SerenityContext,
+        model::{
+            id::GuildId,
+            interactions::application_command::{
+                ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
+            },
+        },
+    };
+
+    use crate::{data::ConfigValues, db::Emoji};
 
     /// Helps to quickly access commands
     pub fn command_map(opt: &ApplicationCommandInteractionDataOption) -> HashMap<String, Value> {
@@ -119,4 +170,36 @@ pub mod helpers {
             .filter_map(|o| o.value.as_ref().map(|v| (o.name.clone(), v.clone())))
             .collect()
     }
+
+    /// The guild the command was used in. Commands are only registered for guilds
+    pub fn guild_id(aci: &ApplicationCommandInteraction) -> Result<GuildId> {
+        aci.guild_id
+            .context("Unexpected command outside of a server")
+    }
+
+    /// Looks for a custom emoji of the emoji guild by name or as pasted
+    /// into discord and falls back to a unicode emoji
+    pub async fn resolve_emoji(
+        ctx: &SerenityContext,
+        guild_id: GuildId,
+        input: &str,
+    ) -> Result<Emoji> {
+        let input = input.trim();
+        let emoji_guild_id = ConfigValues::get(ctx, guild_id).await?.emoji_guild_id;
+        let custom = emoji_guild_id
+            .emojis(ctx)
+            .await?
+            .into_iter()
+            .find(|e| e.name == input || e.to_string() == input);
+
+        custom
+            .map(|e| Emoji::from(e.id))
+            .or_else(|| Emoji::unicode(input))
+            .ok_or_else(|| {
+                anyhow!(
+                    "The emoji {} is neither in the emoji guild nor a unicode emoji",
+                    input
+                )
+            })
+    }
 }
@@ -1,15 +1,19 @@
-use anyhow::{bail, Context as ErrContext, Result};
+use std::{borrow::Cow, time::Duration};
+
+use anyhow::{anyhow, bail, Context as ErrContext, Result};
 use serenity::{
-    builder::{CreateApplicationCommand, CreateEmbed},
+    builder::{CreateApplicationCommand, CreateButton, CreateEmbed},
     client::Context,
     model::{
-        guild::Guild,
-        id::ChannelId,
+        channel::{AttachmentType, Channel, ChannelType},
+        guild::{Guild, PartialGuild},
+        id::{ChannelId, GuildId, RoleId},
         interactions::{
             application_command::{
                 ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
-                ApplicationCommandOptionType,
+                ApplicationCommandInteractionDataOptionValue, ApplicationCommandOptionType,
             },
+            message_component::ButtonStyle,
             InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
         },
         mention::Mention,
@@ -18,8 +22,9 @@ use serenity::{
 };
 use serenity_tools::{builder::CreateEmbedExt, interactions::ApplicationCommandInteractionExt};
 
+use super::{helpers::guild_id, setup::load_guild};
 use crate::{
-    data::{ConfigValuesData, LogConfigData, INFO_LOG_NAME},
+    data::{ConfigValues, Setting, SettingKind},
     db,
     embeds::CrossroadsEmbeds,
     logging::{log_discord, LogTrace, ReplyHelper},
@@ -44,22 +49,66 @@ pub fn create() -> CreateApplicationCommand {
             o.description("The channel in which the overview message will be posted")
         })
     });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommandGroup);
+        o.name("set");
+        o.description("Change a setting of this server");
+        for setting in Setting::ALL {
+            o.create_sub_option(|o| {
+                o.kind(ApplicationCommandOptionType::SubCommand);
+                o.name(setting.name());
+                o.description(setting.description());
+                o.create_sub_option(|o| {
+                    match setting.kind() {
+                        SettingKind::Role => {
+                            o.kind(ApplicationCommandOptionType::Role);
+                        }
+                        SettingKind::Channel => {
+                            o.kind(ApplicationCommandOptionType::Channel);
+                            o.channel_types(&[ChannelType::Text]);
+                        }
+                        SettingKind::ThreadParent => {
+                            o.kind(ApplicationCommandOptionType::Channel);
+                            o.channel_types(&[ChannelType::Text, ChannelType::Forum]);
+                        }
+                        SettingKind::Guild => {
+                            o.kind(ApplicationCommandOptionType::String);
+                        }
+                    }
+                    o.required(true);
+                    o.name("value");
+                    o.description("The new value")
+                })
+            });
+        }
+        o
+    });
     app.create_option(|o| {
         o.kind(ApplicationCommandOptionType::SubCommand);
-        o.name("log");
-        o.description("set the log channel");
-        o.create_sub_option(|o| {
-            o.kind(ApplicationCommandOptionType::Channel);
-            o.required(true);
-            o.name("channel");
-            o.description("The channel to which all logs are posted")
-        })
+        o.name("show");
+        o.description("Show the settings of this server")
     });
     app.create_option(|o| {
         o.kind(ApplicationCommandOptionType::SubCommand);
         o.name("emoji_list");
         o.description("Lists all emojis from the emoji server")
     });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("export");
+        o.description("Export roles, bosses, tiers and config values as json")
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("import");
+        o.description("Import roles, bosses, tiers and config values from a json export");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Attachment);
+            o.required(true);
+            o.name("file");
+            o.description("The json file created by /config export")
+        })
+    });
     app
 }
 
@@ -69,8 +118,11 @@ pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
         if let Some(sub) = aci.data.options.get(0) {
             match sub.name.as_ref() {
                 "overview" => overview(ctx, aci, sub, trace).await,
-                "log" => log(ctx, aci, sub, trace).await,
+                "set" => set(ctx, aci, sub, trace).await,
+                "show" => show(ctx, aci, trace).await,
                 "emoji_list" => emoji_list(ctx, aci, trace).await,
+                "export" => export(ctx, aci, trace).await,
+                "import" => import(ctx, aci, sub, trace).await,
                 _ => bail!("{} not yet available", sub.name),
             }
         } else {
@@ -102,8 +154,10 @@ async fn overview(
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
 
+    let guild_id = guild_id(aci)?;
+
     trace.step("Loading signup board");
-    let board = signup_board::SignupBoard::get(ctx).await;
+    let board = signup_board::SignupBoard::get(ctx, guild_id).await;
     let mut lock = board.write().await;
 
     trace.step("Set channel");
@@ -136,54 +190,130 @@ async fn overview(
     Ok(())
 }
 
-async fn log(
+/// Checks that the id points to something the setting can use
+async fn validate_setting(
+    ctx: &Context,
+    guild_id: GuildId,
+    kind: SettingKind,
+    id: u64,
+) -> Result<()> {
+    match kind {
+        SettingKind::Role => {
+            let roles = guild_id.roles(ctx).await?;
+            if !roles.contains_key(&RoleId(id)) {
+                bail!("The role does not exist on this server");
+            }
+        }
+        SettingKind::Channel => match ChannelId(id).to_channel(ctx).await {
+            Ok(Channel::Guild(c)) if c.guild_id == guild_id && c.kind == ChannelType::Text => (),
+            _ => bail!("Not a text channel of this server"),
+        },
+        SettingKind::ThreadParent => match ChannelId(id).to_channel(ctx).await {
+            Ok(Channel::Guild(c))
+                if c.guild_id == guild_id
+                    && matches!(c.kind, ChannelType::Text | ChannelType::Forum) => {}
+            _ => bail!("Not a text or forum channel of this server"),
+        },
+        SettingKind::Guild => {
+            PartialGuild::get(ctx, GuildId(id))
+                .await
+                .context("The bot is not a member of that server")?;
+        }
+    }
+    Ok(())
+}
+
+fn fmt_setting(ctx: &Context, kind: SettingKind, id: u64) -> String {
+    match kind {
+        SettingKind::Role => Mention::from(RoleId(id)).to_string(),
+        SettingKind::Channel | SettingKind::ThreadParent => {
+            Mention::from(ChannelId(id)).to_string()
+        }
+        SettingKind::Guild => match GuildId(id).name(ctx) {
+            Some(name) => format!("{} ({})", name, id),
+            None => id.to_string(),
+        },
+    }
+}
+
+async fn set(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
     option: &ApplicationCommandInteractionDataOption,
     trace: LogTrace,
 ) -> Result<()> {
-    let channel_id = option
+    let guild_id = guild_id(aci)?;
+    let sub = option
         .options
-        .get(0)
+        .first()
         .context("Unexpected missing option")
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-        .await?
-        .value
-        .as_ref()
-        .and_then(|v| v.as_str())
-        .map(|v| v.parse::<ChannelId>())
-        .context("Unexpected missing value")
+        .await?;
+    let setting = sub
+        .name
+        .parse::<Setting>()
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-        .await?
+        .await?;
+    let id = sub
+        .options
+        .first()
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .context("Could not parse the value")
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
 
-    trace.step("Setting log channel internally");
-    {
-        let write_lock = ctx
-            .data
-            .read()
-            .await
-            .get::<LogConfigData>()
-            .unwrap()
-            .clone();
-        write_lock.write().await.log = Some(channel_id);
-    }
-
-    trace.step("Saving log channel to db");
-    let conf = db::Config {
-        name: String::from(INFO_LOG_NAME),
-        value: channel_id.to_string(),
-    };
-
-    conf.save(ctx)
+    trace.step("Validating value");
+    validate_setting(ctx, guild_id, setting.kind(), id)
         .await
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
 
-    aci.create_quick_info(ctx, "Log channel updated", true)
+    trace.step("Saving setting");
+    setting
+        .set(ctx, guild_id, id)
+        .await
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
 
+    aci.create_quick_success(
+        ctx,
+        format!(
+            "{} set to {}",
+            setting.name(),
+            fmt_setting(ctx, setting.kind(), id)
+        ),
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn show(ctx: &Context, aci: &ApplicationCommandInteraction, trace: LogTrace) -> Result<()> {
+    let guild_id = guild_id(aci)?;
+
+    trace.step("Loading settings");
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Settings");
+    for setting in Setting::ALL {
+        let value = match setting.get(ctx, guild_id).await {
+            Some(id) => fmt_setting(ctx, setting.kind(), id),
+            None => String::from("Not set"),
+        };
+        emb.field(setting.name(), value, false);
+    }
+
+    aci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource);
+        r.interaction_response_data(|d| {
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            d.add_embed(emb)
+        })
+    })
+    .await?;
+
     Ok(())
 }
 
@@ -194,12 +324,10 @@ async fn emoji_list(
 ) -> Result<()> {
     // load all emojis from discord emoji guild
     trace.step("Loading from emoji guild");
-    let gid = ctx
-        .data
-        .read()
+    let gid = ConfigValues::get(ctx, guild_id(aci)?)
         .await
-        .get::<ConfigValuesData>()
-        .unwrap()
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?
         .emoji_guild_id;
     let emoji_guild = Guild::get(ctx, gid).await?;
     let emojis = emoji_guild.emojis(ctx).await?;
@@ -225,3 +353,510 @@ async fn emoji_list(
 
     Ok(())
 }
+
+async fn export(ctx: &Context, aci: &ApplicationCommandInteraction, trace: LogTrace) -> Result<()> {
+    trace.step("Loading configuration");
+    let mut conf = db::BotConfiguration::load(ctx, guild_id(aci)?)
+        .await
+        .context("Failed to load configuration")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    // The signup board message only exists on this server
+    conf.config
+        .retain(|c| Setting::by_config_name(&c.name).is_some());
+
+    let json = serde_json::to_string_pretty(&conf)?;
+    let file = AttachmentType::Bytes {
+        data: Cow::from(json.into_bytes()),
+        filename: String::from("config.json"),
+    };
+
+    trace.step("Replying with file");
+    aci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource);
+        r.interaction_response_data(|d| {
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            let mut emb = CreateEmbed::xdefault();
+            emb.title("Configuration export");
+            emb.field("Roles", conf.roles.len(), true);
+            emb.field("Bosses", conf.training_bosses.len(), true);
+            emb.field("Tiers", conf.tiers.len(), true);
+            emb.field("Tier roles", conf.tier_mappings.len(), true);
+            emb.field("Config values", conf.config.len(), true);
+            d.add_embed(emb);
+            d.add_file(file)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct ConfigDiff {
+    added: Vec<String>,
+    changed: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl ConfigDiff {
+    fn new<T, K, FK, FF>(current: &[T], new: &[T], key: FK, fmt: FF) -> Self
+    where
+        T: PartialEq,
+        K: PartialEq,
+        FK: Fn(&T) -> K,
+        FF: Fn(&T) -> String,
+    {
+        let mut diff = ConfigDiff::default();
+        for n in new {
+            match current.iter().find(|c| key(c) == key(n)) {
+                None => diff.added.push(fmt(n)),
+                Some(c) if c != n => diff.changed.push(fmt(n)),
+                Some(_) => (),
+            }
+        }
+        for c in current {
+            if !new.iter().any(|n| key(n) == key(c)) {
+                diff.removed.push(fmt(c));
+            }
+        }
+        diff
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    fn field_value(&self) -> String {
+        if self.is_empty() {
+            return String::from("_No changes_");
+        }
+        let lines = self
+            .added
+            .iter()
+            .map(|a| format!("+ {}", a))
+            .chain(self.changed.iter().map(|c| format!("! {}", c)))
+            .chain(self.removed.iter().map(|r| format!("- {}", r)))
+            .collect::<Vec<_>>();
+
+        code_block("diff", &lines)
+    }
+}
+
+/// Every parent has to be part of the file and following the parents may never lead
+/// back to the tier itself
+fn check_tier_parents(tiers: &[db::TierConfig]) -> Result<()> {
+    for tier in tiers {
+        let mut visited = vec![&tier.name];
+        let mut parent = &tier.parent;
+        while let Some(name) = parent {
+            if visited.contains(&name) {
+                bail!("Tier {} is its own parent through {}", tier.name, name);
+            }
+            let next = tiers.iter().find(|t| t.name == *name).with_context(|| {
+                format!(
+                    "Tier {} has parent {} which is not part of the file",
+                    tier.name, name
+                )
+            })?;
+            visited.push(name);
+            parent = &next.parent;
+        }
+    }
+    Ok(())
+}
+
+/// The lines in a code block that stays within discords field limit
+fn code_block(lang: &str, lines: &[String]) -> String {
+    let mut value = format!("```{}\n", lang);
+    for (i, l) in lines.iter().enumerate() {
+        if value.len() + l.len() + 20 > 1024 {
+            value.push_str(&format!("... {} more\n", lines.len() - i));
+            break;
+        }
+        value.push_str(l);
+        value.push('\n');
+    }
+    value.push_str("```");
+    value
+}
+
+const IMPORT_MERGE: &str = "config_import_merge";
+const IMPORT_REPLACE: &str = "config_import_replace";
+const IMPORT_ABORT: &str = "config_import_abort";
+
+async fn import(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let guild_id = guild_id(aci)?;
+    let attachment = match option.options.get(0).and_then(|o| o.resolved.as_ref()) {
+        Some(ApplicationCommandInteractionDataOptionValue::Attachment(a)) => a,
+        _ => {
+            Err(anyhow!("Unexpected missing attachment"))
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    aci.create_quick_info(ctx, "Loading file...", true).await?;
+    let msg = aci.get_interaction_response(ctx).await?;
+
+    trace.step("Downloading file");
+    let bytes = attachment
+        .download()
+        .await
+        .context("Failed to download file")
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+
+    trace.step("Parsing file");
+    let mut new_conf: db::BotConfiguration = serde_json::from_slice(&bytes)
+        .context("Could not parse configuration file")
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+
+    if let Some(m) = new_conf
+        .tier_mappings
+        .iter()
+        .find(|m| !new_conf.tiers.iter().any(|t| t.name == m.tier))
+    {
+        Err(anyhow!(
+            "Discord role {} is mapped to tier {} which is not part of the file",
+            m.discord_role_id,
+            m.tier
+        ))
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+    }
+    check_tier_parents(&new_conf.tiers)
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+
+    trace.step("Validating config values");
+    // Files can come from another server. Only settings that point to
+    // something of this server are taken over
+    let mut skipped = Vec::new();
+    let mut config = Vec::with_capacity(new_conf.config.len());
+    for mut c in new_conf.config.drain(..) {
+        let checked = match Setting::by_config_name(&c.name) {
+            Some(setting) => {
+                // Older exports can use an old name
+                c.name = setting.config_name().to_string();
+                match c.value.parse::<u64>() {
+                    Ok(id) => validate_setting(ctx, guild_id, setting.kind(), id).await,
+                    Err(_) => Err(anyhow!("Not an id")),
+                }
+            }
+            None => Err(anyhow!("Not a setting")),
+        };
+        match checked {
+            Ok(()) => config.push(c),
+            Err(e) => skipped.push(format!("{} | {}", c.name, e)),
+        }
+    }
+    new_conf.config = config;
+
+    trace.step("Loading current configuration");
+    let mut curr_conf = db::BotConfiguration::load(ctx, guild_id)
+        .await
+        .context("Failed to load current configuration")
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+    curr_conf
+        .config
+        .retain(|c| Setting::by_config_name(&c.name).is_some());
+
+    let diffs = [
+        (
+            "Roles",
+            ConfigDiff::new(
+                &curr_conf.roles,
+                &new_conf.roles,
+                |r| r.repr.clone(),
+                |r| format!("{} | {}", r.repr, r.title),
+            ),
+        ),
+        (
+            "Bosses",
+            ConfigDiff::new(
+                &curr_conf.training_bosses,
+                &new_conf.training_bosses,
+                |b| b.repr.clone(),
+                |b| format!("{} | {}", b.repr, b.name),
+            ),
+        ),
+        (
+            "Tiers",
+            ConfigDiff::new(
+                &curr_conf.tiers,
+                &new_conf.tiers,
+                |t| t.name.clone(),
+                |t| match &t.parent {
+                    Some(parent) => format!("{} < {}", t.name, parent),
+                    None => t.name.clone(),
+                },
+            ),
+        ),
+        (
+            "Tier roles",
+            ConfigDiff::new(
+                &curr_conf.tier_mappings,
+                &new_conf.tier_mappings,
+                |m| (m.tier.clone(), m.discord_role_id),
+                |m| format!("{} | {}", m.tier, m.discord_role_id),
+            ),
+        ),
+        (
+            "Config values",
+            ConfigDiff::new(
+                &curr_conf.config,
+                &new_conf.config,
+                |c| c.name.clone(),
+                |c| format!("{} = {}", c.name, c.value),
+            ),
+        ),
+    ];
+
+    // Replace keeps these, removing them would empty boss pools and history
+    let kept_bosses = db::TrainingBoss::all_in_use(ctx, guild_id)
+        .await
+        .context("Failed to load current configuration")
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?
+        .into_iter()
+        .filter(|b| !new_conf.training_bosses.iter().any(|n| n.repr == b.repr))
+        .map(|b| format!("{} | {}", b.repr, b.name))
+        .collect::<Vec<_>>();
+
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Configuration import");
+    emb.description(
+        "`+` added, `!` changed, `-` only removed on replace\n\
+        **Merge** adds and updates entries. **Replace** also removes everything not \
+        in the file. Removed roles are deactivated, trainings of removed tiers are \
+        opened to everyone. Config values are never removed",
+    );
+    for (name, diff) in &diffs {
+        emb.field(name, diff.field_value(), false);
+    }
+    if !skipped.is_empty() {
+        emb.field("Config values skipped", code_block("", &skipped), false);
+    }
+    if !kept_bosses.is_empty() {
+        emb.field(
+            "Bosses kept on replace since they are in use",
+            code_block("", &kept_bosses),
+            false,
+        );
+    }
+
+    let mut merge = CreateButton::default();
+    merge
+        .style(ButtonStyle::Primary)
+        .label("Merge")
+        .custom_id(IMPORT_MERGE);
+    let mut replace = CreateButton::default();
+    replace
+        .style(ButtonStyle::Danger)
+        .label("Replace")
+        .custom_id(IMPORT_REPLACE);
+    let mut abort = CreateButton::default();
+    abort
+        .style(ButtonStyle::Secondary)
+        .label("Abort")
+        .custom_id(IMPORT_ABORT);
+
+    aci.edit_original_interaction_response(ctx, |d| {
+        d.add_embed(emb);
+        d.components(|c| {
+            c.create_action_row(|ar| {
+                ar.add_button(merge);
+                ar.add_button(replace);
+                ar.add_button(abort)
+            })
+        })
+    })
+    .await?;
+
+    trace.step("Waiting for merge or replace");
+    let react = match msg
+        .await_component_interaction(ctx)
+        .timeout(Duration::from_secs(60 * 2))
+        .await
+    {
+        Some(r) => r,
+        None => {
+            Err(anyhow!("Timed out"))
+                .map_err_reply(|what| aci.edit_quick_info(ctx, what))
+                .await?;
+            return Ok(());
+        }
+    };
+    react.defer(ctx).await?;
+
+    let replace = match react.data.custom_id.as_str() {
+        IMPORT_MERGE => false,
+        IMPORT_REPLACE => true,
+        IMPORT_ABORT => {
+            trace.step("Aborted");
+            aci.edit_quick_info(ctx, "Aborted").await?;
+            return Ok(());
+        }
+        _ => bail!("Unexpected interaction"),
+    };
+
+    trace.step("Applying configuration");
+    new_conf
+        .apply(ctx, guild_id, replace)
+        .await
+        .context("Failed to apply configuration. Nothing was changed")
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+
+    trace.step("Reloading config values");
+    load_guild(ctx, guild_id).await?;
+
+    aci.edit_quick_success(
+        ctx,
+        if replace {
+            "Configuration replaced"
+        } else {
+            "Configuration merged"
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_tier_parents;
+    use crate::{
+        db,
+        testing::{self, TestContext, GUILD_ID},
+    };
+
+    async fn boss(ctx: &TestContext, position: i32) -> db::TrainingBoss {
+        let repr = testing::unique("boss");
+        db::TrainingBoss::insert(
+            ctx,
+            GUILD_ID,
+            repr.clone(),
+            repr,
+            db::BossCategory::Other,
+            9,
+            position,
+            db::Emoji::Unicode(String::from("🐉")),
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn replacing_keeps_what_is_still_used() {
+        let ctx = TestContext::new();
+        testing::guild(&ctx).await;
+        let tier = db::Tier::insert(&ctx, GUILD_ID, testing::unique("tier"))
+            .await
+            .unwrap();
+        let training = testing::training(&ctx, db::TrainingState::Open, Some(&tier), &[]).await;
+        let (used, unused, first, second) = (
+            boss(&ctx, 1).await,
+            boss(&ctx, 2).await,
+            boss(&ctx, 3).await,
+            boss(&ctx, 4).await,
+        );
+        training.add_training_boss(&ctx, used.id).await.unwrap();
+
+        // The file only has the last two bosses, with their places swapped,
+        // and no tiers
+        let mut conf = db::BotConfiguration::load(&ctx, GUILD_ID).await.unwrap();
+        conf.training_bosses
+            .retain(|b| b.repr == first.repr || b.repr == second.repr);
+        for b in &mut conf.training_bosses {
+            b.position = if b.repr == first.repr { 4 } else { 3 };
+        }
+        conf.tiers.clear();
+        conf.tier_mappings.clear();
+        conf.apply(&ctx, GUILD_ID, true).await.unwrap();
+
+        let bosses = db::TrainingBoss::all(&ctx, GUILD_ID).await.unwrap();
+        let position = |repr: &str| bosses.iter().find(|b| b.repr == repr).map(|b| b.position);
+        assert_eq!(position(&used.repr), Some(1));
+        assert_eq!(position(&unused.repr), None);
+        assert_eq!(position(&first.repr), Some(4));
+        assert_eq!(position(&second.repr), Some(3));
+
+        let training = db::Training::by_id(&ctx, GUILD_ID, training.id)
+            .await
+            .unwrap();
+        assert_eq!(training.tier_id, None);
+        assert!(db::Tier::all(&ctx, GUILD_ID).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn tiers_without_parents_still_load() {
+        let conf: db::BotConfiguration = serde_json::from_str(
+            r#"{"roles": [], "training_bosses": [], "tier_mappings": [], "config": [],
+                "tiers": ["Beginner", {"name": "Expert", "parent": "Beginner"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(conf.tiers[0].name, "Beginner");
+        assert_eq!(conf.tiers[0].parent, None);
+        assert_eq!(conf.tiers[1].parent.as_deref(), Some("Beginner"));
+    }
+
+    #[test]
+    fn tier_parents_are_checked() {
+        let tier = |name: &str, parent: Option<&str>| db::TierConfig {
+            name: name.to_string(),
+            parent: parent.map(str::to_string),
+        };
+        assert!(check_tier_parents(&[tier("a", None), tier("b", Some("a"))]).is_ok());
+        assert!(check_tier_parents(&[tier("a", Some("missing"))]).is_err());
+        assert!(check_tier_parents(&[tier("a", Some("b")), tier("b", Some("a"))]).is_err());
+        assert!(check_tier_parents(&[tier("a", Some("a"))]).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn tier_parents_survive_an_export() {
+        let ctx = TestContext::new();
+        testing::guild(&ctx).await;
+        let (low, high) = (
+            db::Tier::insert(&ctx, GUILD_ID, testing::unique("low"))
+                .await
+                .unwrap(),
+            db::Tier::insert(&ctx, GUILD_ID, testing::unique("high"))
+                .await
+                .unwrap(),
+        );
+        low.clone().set_parent(&ctx, Some(high.id)).await.unwrap();
+
+        let conf = db::BotConfiguration::load(&ctx, GUILD_ID).await.unwrap();
+        let empty = db::BotConfiguration {
+            roles: conf.roles.clone(),
+            training_bosses: conf.training_bosses.clone(),
+            ..Default::default()
+        };
+        empty.apply(&ctx, GUILD_ID, true).await.unwrap();
+        assert!(db::Tier::all(&ctx, GUILD_ID).await.unwrap().is_empty());
+
+        // Listed before its parent, which does not exist yet when it is created
+        let mut conf = conf;
+        conf.tiers.sort_by_key(|t| t.parent.is_none());
+        conf.apply(&ctx, GUILD_ID, true).await.unwrap();
+        let tiers = db::Tier::all(&ctx, GUILD_ID).await.unwrap();
+        let low = tiers.iter().find(|t| t.name == low.name).unwrap();
+        let high = tiers.iter().find(|t| t.name == high.name).unwrap();
+        assert_eq!(low.parent_id, Some(high.id));
+        assert_eq!(high.parent_id, None);
+    }
+}
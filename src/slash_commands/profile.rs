@@ -0,0 +1,486 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context as ErrContext, Result};
+use itertools::Itertools;
+use serenity::{
+    builder::{CreateApplicationCommand, CreateEmbed, CreateSelectMenu},
+    client::Context,
+    model::{
+        id::{GuildId, RoleId},
+        interactions::{
+            application_command::{
+                ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
+                ApplicationCommandOptionType,
+            },
+            message_component::{ActionRowComponent, InputTextStyle},
+            modal::ModalSubmitInteraction,
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+    },
+};
+use serenity_tools::{builder::CreateEmbedExt, interactions::ApplicationCommandInteractionExt};
+
+use super::helpers::{command_map, guild_id};
+use crate::{
+    data::review_channel,
+    db::{self, DBPoolProvider},
+    discord::DiscordFacade,
+    embeds::CrossroadsEmbeds,
+    logging::{self, log_discord, LogTrace, ReplyHelper},
+    tier_requests,
+};
+
+pub(super) const CMD_PROFILE: &str = "profile";
+
+pub fn create() -> CreateApplicationCommand {
+    let mut app = CreateApplicationCommand::default();
+    app.name(CMD_PROFILE);
+    app.description("Manage your sign-up defaults");
+    app.dm_permission(false);
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("defaults");
+        o.description("Set the roles and comment offered for one-click sign-ups");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("roles");
+            o.description("Comma separated list of repr's, main role first. Example: heal,dps");
+            o.required(true)
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("comment");
+            o.description("Added to sign-ups with your defaults. Leave empty for none")
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("show");
+        o.description("Show your account name and defaults")
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("clear");
+        o.description("Remove your default roles and comment")
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("request_tier");
+        o.description("Ask the squadmakers for a tier")
+    });
+    app
+}
+
+pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
+    log_discord(ctx, aci, |trace| async move {
+        trace.step("Parsing command");
+        let user = match db::User::by_discord_id(ctx, aci.user.id).await {
+            Ok(u) => u,
+            Err(diesel::NotFound) => {
+                Err(diesel::NotFound)
+                    .context(logging::InfoError::NotRegistered)
+                    .context("You are not registered yet. Use /register first")
+                    .map_err_reply(|what| aci.create_quick_info(ctx, what, true))
+                    .await?;
+                return Ok(());
+            }
+            Err(e) => bail!(e),
+        };
+        if let Some(sub) = aci.data.options.first() {
+            match sub.name.as_ref() {
+                "defaults" => defaults(ctx, aci, sub, user, trace).await,
+                "show" => show(ctx, aci, user, trace).await,
+                "clear" => clear(ctx, aci, user, trace).await,
+                "request_tier" => request_tier(ctx, aci, user, trace).await,
+                _ => bail!("{} not yet available", sub.name),
+            }
+        } else {
+            bail!("Invalid command")
+        }
+    })
+    .await;
+}
+
+/// Loads the roles in the given order. Repeated roles are only kept once
+async fn parse_default_roles(
+    ctx: &impl DBPoolProvider,
+    guild_id: GuildId,
+    list: &str,
+) -> Result<Vec<db::Role>> {
+    let mut roles: Vec<db::Role> = Vec::new();
+    for repr in list.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        let role = db::Role::by_repr(ctx, guild_id, repr.to_string())
+            .await
+            .with_context(|| format!("Unknown role: {}", repr))?;
+        if !roles.contains(&role) {
+            roles.push(role);
+        }
+    }
+    if roles.is_empty() {
+        bail!("Select at least one role");
+    }
+    Ok(roles)
+}
+
+async fn defaults(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    user: db::User,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = command_map(option);
+    let guild_id = guild_id(aci)?;
+
+    trace.step("Loading roles");
+    let list = cmds
+        .get("roles")
+        .and_then(|v| v.as_str())
+        .context("Unexpected missing field roles")?;
+    let roles = parse_default_roles(ctx, guild_id, list)
+        .await
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let comment = cmds
+        .get("comment")
+        .and_then(|v| v.as_str())
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty());
+    // Same limit as for comments added to a sign-up
+    if matches!(&comment, Some(c) if c.chars().count() > 256) {
+        Err(anyhow!("The comment may not be longer than 256 characters"))
+            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+            .await?;
+    }
+
+    trace.step("Saving defaults");
+    user.set_default_roles(ctx, guild_id, &roles.iter().collect::<Vec<_>>())
+        .await?;
+    let defaults = user.set_default_comment(ctx, guild_id, comment).await?;
+
+    aci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource);
+        r.interaction_response_data(|d| {
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            d.add_embed(defaults_embed(&user, &roles, defaults.comment.as_deref()))
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+async fn show(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    user: db::User,
+    trace: LogTrace,
+) -> Result<()> {
+    trace.step("Loading defaults");
+    let guild_id = guild_id(aci)?;
+    let roles = user.default_roles(ctx, guild_id).await?;
+    let comment = user.default_comment(ctx, guild_id).await?;
+    let requests = user.tier_requests(ctx, guild_id).await?;
+    let bosses = db::TrainingBoss::all(ctx, guild_id).await?;
+    let progress = user.boss_progress(ctx, guild_id).await?;
+
+    aci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource);
+        r.interaction_response_data(|d| {
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            let mut emb = defaults_embed(&user, &roles, comment.as_deref());
+            if !requests.is_empty() {
+                emb.field(
+                    "Tier Requests",
+                    requests
+                        .iter()
+                        .take(5)
+                        .map(|(r, t)| {
+                            format!(
+                                "<t:{}:d> {} | {:?}",
+                                r.created_at.timestamp(),
+                                t.name,
+                                r.state
+                            )
+                        })
+                        .join("\n"),
+                    false,
+                );
+            }
+            if !progress.is_empty() {
+                emb.field("Boss Progress", boss_progress(&bosses, &progress), false);
+            }
+            d.add_embed(emb)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// One line per boss category with how many of its bosses were completed and which
+fn boss_progress(bosses: &[db::TrainingBoss], progress: &[(db::TrainingBoss, i64)]) -> String {
+    bosses
+        .iter()
+        .map(|b| b.group())
+        .sorted()
+        .dedup()
+        .map(|(category, number)| {
+            let total = bosses
+                .iter()
+                .filter(|b| b.group() == (category, number))
+                .count();
+            let done = progress
+                .iter()
+                .filter(|(b, _)| b.group() == (category, number))
+                .map(|(b, _)| b.emoji.to_string())
+                .collect::<Vec<_>>();
+            format!(
+                "`{}` {}/{} {}",
+                category.group_short_name(number),
+                done.len(),
+                total,
+                done.join("")
+            )
+        })
+        .join("\n")
+}
+
+async fn clear(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    user: db::User,
+    trace: LogTrace,
+) -> Result<()> {
+    trace.step("Removing defaults");
+    let guild_id = guild_id(aci)?;
+    user.set_default_roles(ctx, guild_id, &[]).await?;
+    user.set_default_comment(ctx, guild_id, None).await?;
+
+    aci.create_quick_success(ctx, "Your defaults were removed", true)
+        .await?;
+
+    Ok(())
+}
+
+/// Value of the text input with the id, if it was filled in
+fn input_value(submit: &ModalSubmitInteraction, id: &str) -> Option<String> {
+    submit
+        .data
+        .components
+        .iter()
+        .flat_map(|ar| ar.components.iter())
+        .find_map(|c| match c {
+            ActionRowComponent::InputText(t) if t.custom_id == id => Some(t.value.trim()),
+            _ => None,
+        })
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+async fn request_tier(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    user: db::User,
+    trace: LogTrace,
+) -> Result<()> {
+    let guild_id = guild_id(aci)?;
+    let channel_id = review_channel(ctx, guild_id)
+        .await
+        .context("Tier requests are not set up on this server")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    trace.step("Loading tiers");
+    let tiers = db::Tier::all(ctx, guild_id).await?;
+    let mappings = db::TierMapping::all(ctx, guild_id).await?;
+    let member_roles = aci
+        .member
+        .as_ref()
+        .map(|m| m.roles.clone())
+        .unwrap_or_default();
+    let pending = user
+        .tier_requests(ctx, guild_id)
+        .await?
+        .into_iter()
+        .filter(|(r, _)| r.state == db::PromotionState::Pending)
+        .map(|(r, _)| r.tier_id)
+        .collect::<Vec<_>>();
+    let tiers = tier_requests::requestable_tiers(&tiers, &mappings, &member_roles)
+        .into_iter()
+        .filter(|t| !pending.contains(&t.id))
+        .collect::<Vec<_>>();
+    if tiers.is_empty() {
+        Err(anyhow!(
+            "There are no tiers you can request. Open requests are shown in /profile show"
+        ))
+        .map_err_reply(|what| aci.create_quick_info(ctx, what, true))
+        .await?;
+    }
+
+    let mut select_menu = CreateSelectMenu::default();
+    select_menu.custom_id("profile_tier_select");
+    select_menu.placeholder("Select a tier");
+    select_menu.options(|opts| {
+        // Discord allows at most 25 options
+        for t in tiers.iter().take(25) {
+            opts.create_option(|o| {
+                o.label(&t.name);
+                o.value(t.id)
+            });
+        }
+        opts
+    });
+    aci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource);
+        r.interaction_response_data(|d| {
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            d.content("Which tier do you want to request?");
+            d.components(|c| c.create_action_row(|ar| ar.add_select_menu(select_menu)))
+        })
+    })
+    .await?;
+
+    trace.step("Waiting for tier selection");
+    let msg = aci.get_interaction_response(ctx).await?;
+    let pick = msg
+        .await_component_interaction(ctx)
+        .author_id(aci.user.id)
+        .timeout(Duration::from_secs(60 * 3))
+        .await
+        .context(logging::InfoError::TimedOut)
+        .map_err_reply(|what| aci.edit_quick_info(ctx, what))
+        .await?;
+    let tier = pick
+        .data
+        .values
+        .first()
+        .and_then(|v| v.parse::<i32>().ok())
+        .and_then(|id| tiers.iter().find(|t| t.id == id))
+        .context("Unexpected value on tier select menu. Aborted")
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+
+    // A user could ignore the modal and select again, so only the latest one counts
+    let custom_id = format!("{}_{}", "tier_request_modal", uuid::Uuid::new_v4());
+    pick.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::Modal);
+        r.interaction_response_data(|d| {
+            d.custom_id(&custom_id);
+            d.title(format!("Request {}", tier.name));
+            d.components(|c| {
+                c.create_action_row(|ar| {
+                    ar.create_input_text(|it| {
+                        it.style(InputTextStyle::Paragraph)
+                            .custom_id("justification")
+                            .label("Why should you get the tier?")
+                            .max_length(1000)
+                            .required(false)
+                    })
+                });
+                c.create_action_row(|ar| {
+                    ar.create_input_text(|it| {
+                        it.style(InputTextStyle::Paragraph)
+                            .custom_id("logs")
+                            .label("Log links")
+                            .placeholder("https://dps.report/...")
+                            .max_length(1000)
+                            .required(false)
+                    })
+                })
+            })
+        })
+    })
+    .await?;
+
+    trace.step("Waiting for request form");
+    let submit = msg
+        .await_modal_interaction(ctx)
+        .author_id(aci.user.id)
+        .filter({
+            let custom_id = custom_id.clone();
+            move |m| m.data.custom_id == custom_id
+        })
+        .timeout(Duration::from_secs(5 * 60))
+        .await
+        .context(logging::InfoError::TimedOut)
+        .map_err_reply(|what| aci.edit_quick_info(ctx, what))
+        .await?;
+
+    trace.step("Saving tier request");
+    let request = db::TierRequest::insert(
+        ctx,
+        &user,
+        tier,
+        input_value(&submit, "justification"),
+        input_value(&submit, "logs"),
+    )
+    .await
+    .context("You already have an open request for this tier")
+    .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+    .await?;
+
+    trace.step("Posting tier request");
+    // The squadmaker picks which of the roles of the tier to grant
+    let guild_roles = guild_id.roles(ctx).await?;
+    let roles = tier
+        .get_discord_roles(ctx)
+        .await?
+        .into_iter()
+        .map(|m| {
+            let id = RoleId::from(m.discord_role_id as u64);
+            let name = guild_roles
+                .get(&id)
+                .map_or_else(|| id.to_string(), |r| r.name.clone());
+            (id, name)
+        })
+        .collect::<Vec<_>>();
+    ctx.send_message(
+        channel_id,
+        tier_requests::request_message(&request, &user, tier, &roles),
+    )
+    .await?;
+
+    submit
+        .create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::UpdateMessage);
+            r.interaction_response_data(|d| {
+                d.content("");
+                d.set_embed(CreateEmbed::success_box(format!(
+                    "Your request for **{}** was sent to the squadmakers",
+                    tier.name
+                )));
+                d.components(|c| c)
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn defaults_embed(user: &db::User, roles: &[db::Role], comment: Option<&str>) -> CreateEmbed {
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Your profile");
+    emb.field("Guild Wars 2 Account", &user.gw2_id, false);
+    if roles.is_empty() {
+        emb.field(
+            "Default Roles",
+            "None. Set them with /profile defaults",
+            false,
+        );
+    } else {
+        emb.field(
+            "Default Roles",
+            roles
+                .iter()
+                .enumerate()
+                .map(|(i, r)| format!("#{} {} | {}", i + 1, r.emoji, r.title))
+                .join("\n"),
+            false,
+        );
+    }
+    if let Some(comment) = comment {
+        emb.field("Default Comment", comment, false);
+    }
+    emb
+}
@@ -1,11 +1,14 @@
-use anyhow::{anyhow, Context as ErrContext};
+use anyhow::{anyhow, Context as ErrContext, Result};
 use regex::Regex;
 use serenity::{
     builder::{CreateApplicationCommand, CreateEmbed},
     client::Context,
-    model::interactions::{
-        application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType},
-        InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+    model::{
+        id::UserId,
+        interactions::{
+            application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType},
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
     },
 };
 use serenity_tools::{
@@ -17,8 +20,10 @@ use serenity_tools::{
 use std::time::Duration;
 
 use crate::{
-    db,
+    db::{self, DBPoolProvider},
     logging::{self, log_discord, ReplyHelper},
+    signup_board::SignupBoard,
+    waitlist,
 };
 
 pub(super) const CMD_REGISTER: &str = "register";
@@ -53,30 +58,43 @@ pub async fn handle_reg(ctx: &Context, aci: &ApplicationCommandInteraction) {
             .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
             .await?;
 
-        trace.step("Checking for valid format");
-        let regex = Regex::new("^[a-zA-Z\\s]{3,27}\\.[0-9]{4}$").unwrap();
-
-        if !regex.is_match(name) {
-            Err(anyhow!("Regex does not match"))
-                .context("Invalid Guild Wars 2 Account Name format.\nIt should look something like this: My Account.1234")
-                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-                .await?;
-        }
-
         trace.step("Saving to db");
-        let entry = db::User::upsert(ctx, aci.user.id.0, String::from(name))
+        let entry = register_user(ctx, aci.user.id, name)
             .await
-            .context("Unexpected error saving your account name =(")
             .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
             .await?;
 
-        aci.create_quick_success(ctx, format!("Guild Wars 2 Account Name set to: {}", entry.gw2_id), true).await?;
+        aci.create_quick_success(
+            ctx,
+            format!("Guild Wars 2 Account Name set to: {}", entry.gw2_id),
+            true,
+        )
+        .await?;
 
         Ok(())
     })
     .await;
 }
 
+/// Checks the Guild Wars 2 account name format and saves it for the discord user
+pub(crate) async fn register_user(
+    ctx: &impl DBPoolProvider,
+    discord_id: UserId,
+    gw2_name: &str,
+) -> Result<db::User> {
+    let regex = Regex::new("^[a-zA-Z\\s]{3,27}\\.[0-9]{4}$").unwrap();
+
+    if !regex.is_match(gw2_name) {
+        return Err(anyhow!("Regex does not match")).context(
+            "Invalid Guild Wars 2 Account Name format.\nIt should look something like this: My Account.1234",
+        );
+    }
+
+    db::User::upsert(ctx, discord_id.0, String::from(gw2_name))
+        .await
+        .context("Unexpected error saving your account name =(")
+}
+
 pub(super) const CMD_UNREGISTER: &str = "unregister";
 
 pub fn create_unreg() -> CreateApplicationCommand {
@@ -146,12 +164,18 @@ pub async fn handle_unreg(ctx: &Context, aci: &ApplicationCommandInteraction) {
         };
 
         trace.step("Deleting user entry");
-        db_user
-            .delete(ctx)
+        let joined = db_user
+            .joined_active_trainings(ctx)
+            .await
+            .context("Unexpected error loading your sign-ups =(")
+            .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+            .await?;
+        waitlist::remove_signups(ctx, &joined, db_user.delete(ctx))
             .await
             .context("Unexpected error deleting your information =(")
             .map_err_reply(|what| aci.edit_quick_error(ctx, what))
             .await?;
+        SignupBoard::request_update_all(ctx).await;
 
         aci.edit_quick_success(ctx, "Your information was deleted")
             .await?;
@@ -160,3 +184,52 @@ pub async fn handle_unreg(ctx: &Context, aci: &ApplicationCommandInteraction) {
     })
     .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestContext;
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn register_saves_and_updates_account_name() {
+        let ctx = TestContext::new();
+        let discord_id = UserId(1001);
+
+        let user = register_user(&ctx, discord_id, "My Account.1234")
+            .await
+            .unwrap();
+        assert_eq!(user.discord_id(), discord_id.0);
+        assert_eq!(user.gw2_id, "My Account.1234");
+
+        let updated = register_user(&ctx, discord_id, "Other Account.4321")
+            .await
+            .unwrap();
+        assert_eq!(updated.id, user.id);
+        assert_eq!(
+            db::User::by_discord_id(&ctx, discord_id)
+                .await
+                .unwrap()
+                .gw2_id,
+            "Other Account.4321"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn register_rejects_invalid_account_name() {
+        let ctx = TestContext::new();
+        let discord_id = UserId(1002);
+
+        for name in ["My Account", "My Account.12", "ab.1234", "My_Account.1234"] {
+            let err = register_user(&ctx, discord_id, name).await.unwrap_err();
+            assert!(err
+                .to_string()
+                .starts_with("Invalid Guild Wars 2 Account Name"));
+        }
+        assert_eq!(
+            db::User::by_discord_id(&ctx, discord_id).await,
+            Err(diesel::NotFound)
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use anyhow::{Context as ErrContext, Result};
+use serenity::{
+    builder::CreateApplicationCommand,
+    client::Context,
+    model::{
+        id::{ChannelId, GuildId, RoleId},
+        interactions::application_command::{
+            ApplicationCommandInteraction, ApplicationCommandOptionType,
+        },
+        Permissions,
+    },
+};
+use serenity_tools::interactions::ApplicationCommandInteractionExt;
+
+use super::helpers::guild_id;
+use crate::{
+    data::{ConfigValues, ConfigValuesData, LogConfigData, INFO_LOG_NAME},
+    db,
+    logging::{log_discord, ReplyHelper},
+    signup_board::SignupBoard,
+};
+
+pub(super) const CMD_SETUP: &str = "setup";
+
+pub fn create() -> CreateApplicationCommand {
+    let mut app = CreateApplicationCommand::default();
+    app.name(CMD_SETUP);
+    app.description("Set up the bot for this server");
+    app.default_member_permissions(Permissions::empty());
+    app.dm_permission(false);
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::Role);
+        o.name("squadmaker_role");
+        o.required(true);
+        o.description("Members with this role can manage trainings")
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::Role);
+        o.name("admin_role");
+        o.required(true);
+        o.description("Members with this role can configure the bot")
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::String);
+        o.name("emoji_guild");
+        o.required(false);
+        o.description(
+            "Id of the server the role and boss emojis are taken from. Defaults to this one",
+        )
+    });
+    app
+}
+
+pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
+    log_discord(ctx, aci, |trace| async move {
+        trace.step("Parsing command");
+        let guild_id = guild_id(aci)?;
+        let cmds = aci
+            .data
+            .options
+            .iter()
+            .filter_map(|o| o.value.as_ref().map(|v| (o.name.as_str(), v)))
+            .collect::<HashMap<_, _>>();
+
+        let role = |name: &'static str| {
+            cmds.get(name)
+                .and_then(|v| v.as_str())
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(RoleId::from)
+                .with_context(|| format!("Unexpected missing field {}", name))
+        };
+        let squadmaker_role_id = role("squadmaker_role")
+            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+            .await?;
+        let admin_role_id = role("admin_role")
+            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+            .await?;
+        let emoji_guild_id = match cmds.get("emoji_guild").and_then(|v| v.as_str()) {
+            None => guild_id,
+            Some(id) => {
+                id.trim()
+                    .parse::<u64>()
+                    .map(GuildId::from)
+                    .context("Could not parse the emoji server id")
+                    .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                    .await?
+            }
+        };
+
+        trace.step("Saving configuration");
+        db::Guild::insert(ctx, guild_id)
+            .await
+            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+            .await?;
+        ConfigValues {
+            admin_role_id,
+            squadmaker_role_id,
+            emoji_guild_id,
+        }
+        .save_to_db(ctx, guild_id)
+        .await
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+        trace.step("Loading guild");
+        load_guild(ctx, guild_id).await?;
+
+        trace.step("Registering commands");
+        super::register_commands(ctx, guild_id, true).await?;
+
+        aci.create_quick_success(
+            ctx,
+            "Setup complete. Use /config overview and /config set log_channel to choose the channels",
+            true,
+        )
+        .await?;
+
+        Ok(())
+    })
+    .await;
+}
+
+/// Loads everything the bot keeps in memory for a guild that went through the setup
+pub(crate) async fn load_guild(ctx: &Context, guild_id: GuildId) -> Result<()> {
+    let values = ConfigValues::load_from_db(ctx, guild_id).await?;
+    let log = db::Config::load(ctx, guild_id, String::from(INFO_LOG_NAME))
+        .await
+        .ok()
+        .and_then(|c| c.value.parse::<ChannelId>().ok());
+    {
+        let data = ctx.data.read().await;
+        data.get::<ConfigValuesData>()
+            .unwrap()
+            .write()
+            .await
+            .insert(guild_id, values.into());
+
+        let mut log_conf = data.get::<LogConfigData>().unwrap().write().await;
+        match log {
+            Some(id) => log_conf.log.insert(guild_id, id),
+            None => log_conf.log.remove(&guild_id),
+        };
+    }
+
+    let board = SignupBoard::get(ctx, guild_id).await;
+    board.write().await.load_from_db(ctx).await?;
+    SignupBoard::invalidate_trainings(ctx, guild_id).await;
+    Ok(())
+}
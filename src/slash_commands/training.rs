@@ -2,36 +2,39 @@ use std::{borrow::Cow, collections::HashMap, time::Duration};
 
 use super::helpers::*;
 use crate::{
-    data,
-    db::{self, Tier, TrainingState},
-    embeds::{embed_add_roles, CrossroadsEmbeds},
+    announcements,
+    data::{announcement_channel, discussion_channel, review_channel},
+    db::{self, DBPoolProvider, Tier, TrainingState},
+    embeds::{boss_link, embed_add_bosses, embed_add_roles, CrossroadsEmbeds},
     logging::*,
-    signup_board, status,
+    promotions,
+    roster::{self, Roster},
+    signup_board, status, threads,
 };
 use anyhow::{anyhow, bail, Context as ErrContext, Result};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use itertools::Itertools;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serenity::model::{
-    id::EmojiId,
     interactions::{
         application_command::{
             ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
-            ApplicationCommandOptionType,
+            ApplicationCommandInteractionDataOptionValue, ApplicationCommandOptionType,
         },
+        message_component::ButtonStyle,
         InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
     },
     Permissions,
 };
 use serenity::{
-    builder::{CreateApplicationCommand, CreateEmbed},
+    builder::{CreateActionRow, CreateApplicationCommand, CreateButton, CreateEmbed},
     client::Context,
     futures::future,
     futures::future::OptionFuture,
     model::{
         channel::AttachmentType,
         guild::{Member, PartialGuild, Role},
-        id::RoleId,
+        id::{GuildId, RoleId},
         mention::Mention,
     },
 };
@@ -41,6 +44,7 @@ use serenity_tools::{
     components::Button,
     interactions::{ApplicationCommandInteractionExt, MessageComponentInteractionExt},
 };
+use tracing::warn;
 
 type MessageFlags = InteractionApplicationCommandCallbackDataFlags;
 
@@ -91,6 +95,61 @@ pub fn create() -> CreateApplicationCommand {
             o.kind(ApplicationCommandOptionType::String);
             o.name("tier");
             o.description("The required tier for the training. If left empty training is open for everyone")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("composition");
+            o.description("Sign-ups needed per role or category. Example: healer:2,quickness:2,dps:0-6")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Integer);
+            o.name("max_participants");
+            o.description("Further sign-ups are put on a waitlist. Unlimited if left empty");
+            o.min_int_value(1)
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("clone");
+        o.description("Copy a training with its roles, bosses and tier to another date");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Integer);
+            o.name("id");
+            o.description("The id of the training to copy");
+            o.required(true)
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("day");
+            o.description("Day in UTC. Format: yyyy-mm-dd");
+            o.required(true)
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("time");
+            o.description("Time in UTC. Format: HH:MM:SS");
+            o.required(true)
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("signups");
+            o.description("Sign-ups to copy with their roles. Default: none");
+            o.add_string_choice("none", "none");
+            o.add_string_choice("waitlisted", "waitlisted");
+            o.add_string_choice("all", "all")
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("import");
+        o.description("Add multiple trainings from a csv or json file");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Attachment);
+            o.name("file");
+            o.description(
+                "Rows of title, date, time, roles, bosses and tier. Same format as /training add",
+            );
+            o.required(true)
         })
     });
     app.create_option(|o| {
@@ -157,10 +216,22 @@ pub fn create() -> CreateApplicationCommand {
             o.add_string_choice("json", "json");
             o.add_string_choice("csv", "csv")
         });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("sort");
+            o.description("How to sort the sign-ups of each training. Default: signup_time");
+            o.add_string_choice("signup_time", "signup_time");
+            o.add_string_choice("account", "account")
+        });
         o.create_sub_option(|o| {
             o.kind(ApplicationCommandOptionType::Boolean);
             o.name("include-finished");
             o.description("Whether to include finished training's. Defaults to false")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("missing_boss");
+            o.description("Only include sign-ups that have not completed the boss. Boss repr")
         })
     });
     app.create_option(|o| {
@@ -189,6 +260,8 @@ pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
         if let Some(sub) = aci.data.options.get(0) {
             match sub.name.as_ref() {
                 "add" => add(ctx, aci, sub, trace).await,
+                "clone" => clone(ctx, aci, sub, trace).await,
+                "import" => import(ctx, aci, sub, trace).await,
                 "set" => set(ctx, aci, sub, trace).await,
                 "download" => download(ctx, aci, sub, trace).await,
                 "info" => info(ctx, aci, sub, trace).await,
@@ -202,7 +275,11 @@ pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
     .await;
 }
 
-async fn trainings_from_days(ctx: &Context, value: &str) -> Result<Vec<db::Training>> {
+async fn trainings_from_days(
+    ctx: &Context,
+    guild_id: GuildId,
+    value: &str,
+) -> Result<Vec<db::Training>> {
     let days: Vec<NaiveDate> = value
         .split(',')
         .map(|s| s.parse())
@@ -211,7 +288,7 @@ async fn trainings_from_days(ctx: &Context, value: &str) -> Result<Vec<db::Train
 
     let trainings_fut = days
         .into_iter()
-        .map(|d| db::Training::by_date(ctx, d))
+        .map(|d| db::Training::by_date(ctx, guild_id, d))
         .collect::<Vec<_>>();
 
     Ok(future::try_join_all(trainings_fut)
@@ -221,7 +298,11 @@ async fn trainings_from_days(ctx: &Context, value: &str) -> Result<Vec<db::Train
         .collect::<Vec<_>>())
 }
 
-async fn trainings_from_ids(ctx: &Context, value: &str) -> Result<Vec<db::Training>> {
+async fn trainings_from_ids(
+    ctx: &Context,
+    guild_id: GuildId,
+    value: &str,
+) -> Result<Vec<db::Training>> {
     let i: Vec<i32> = value
         .split(',')
         .map(|s| s.parse())
@@ -229,7 +310,7 @@ async fn trainings_from_ids(ctx: &Context, value: &str) -> Result<Vec<db::Traini
 
     let trainings_fut = i
         .into_iter()
-        .map(|i| db::Training::by_id(ctx, i))
+        .map(|i| db::Training::by_id(ctx, guild_id, i))
         .collect::<Vec<_>>();
 
     future::try_join_all(trainings_fut)
@@ -237,6 +318,74 @@ async fn trainings_from_ids(ctx: &Context, value: &str) -> Result<Vec<db::Traini
         .context("Training id does not exist")
 }
 
+/// Parses requirements like `healer:2,dps:0-6`. Names are looked up as role
+/// repr of the training first and as category of its roles second
+fn parse_composition(value: &str, roles: &[db::Role]) -> Result<Vec<db::RequirementDraft>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let (name, count) = entry
+                .split_once(':')
+                .with_context(|| format!("Expected name:count but got: {}", entry))?;
+            let (name, count) = (name.trim(), count.trim());
+            let parse = |c: &str| {
+                c.trim()
+                    .parse::<i32>()
+                    .with_context(|| format!("Could not parse count of {}: {}", name, count))
+            };
+            let (min_count, max_count) = match count.split_once('-') {
+                Some((min, max)) => (parse(min)?, Some(parse(max)?)),
+                None => (parse(count)?, None),
+            };
+            if min_count < 0 || matches!(max_count, Some(max) if max < min_count) {
+                bail!("Invalid count of {}: {}", name, count);
+            }
+
+            // Categories are stored in lowercase
+            let category = name.to_lowercase();
+            let (role_id, category) = if let Some(r) = roles.iter().find(|r| r.repr == name) {
+                (Some(r.id), None)
+            } else if roles.iter().any(|r| r.category.as_ref() == Some(&category)) {
+                (None, Some(category))
+            } else {
+                bail!(
+                    "{} is neither a role nor a category of the training roles",
+                    name
+                );
+            };
+
+            Ok(db::RequirementDraft {
+                role_id,
+                category,
+                min_count,
+                max_count,
+            })
+        })
+        .collect()
+}
+
+fn fmt_composition(requirements: &[db::RequirementDraft], roles: &[db::Role]) -> String {
+    requirements
+        .iter()
+        .map(|req| {
+            let name = match (&req.role_id, &req.category) {
+                (Some(id), _) => roles
+                    .iter()
+                    .find(|r| r.id == *id)
+                    .map(|r| r.repr.as_str())
+                    .unwrap_or_default(),
+                (None, category) => category.as_deref().unwrap_or_default(),
+            };
+            match req.max_count {
+                Some(max) => format!("{} {}-{}", name, req.min_count, max),
+                None => format!("{} at least {}", name, req.min_count),
+            }
+        })
+        .join("\n")
+}
+
 async fn add(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
@@ -244,6 +393,7 @@ async fn add(
     trace: LogTrace,
 ) -> Result<()> {
     let cmds = command_map(option);
+    let guild_id = guild_id(aci)?;
 
     trace.step("Parsing basic training data");
 
@@ -272,10 +422,18 @@ async fn add(
 
     let datetime: NaiveDateTime = day.and_time(time);
 
+    let max_participants = cmds
+        .get("max_participants")
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32);
+
     let mut emb = CreateEmbed::xdefault();
     emb.title("Creating a new training");
     emb.field("Name", name, false);
     emb.field("Date/Time", format!("<t:{}>", datetime.timestamp()), false);
+    if let Some(max) = max_participants {
+        emb.field("Max participants", max, false);
+    }
 
     let mut emb_loading_roles = emb.clone();
     emb_loading_roles.field("Roles", "Loading...", false);
@@ -303,7 +461,7 @@ async fn add(
 
     let mut roles: Vec<db::Role> = Vec::with_capacity(roles_str.len());
     for r in roles_str {
-        let nr = db::Role::by_repr(ctx, r.to_string())
+        let nr = db::Role::by_repr(ctx, guild_id, r.to_string())
             .await
             .with_context(|| format!("Failed to load role: {}", r))
             .map_err_reply(|what| aci.edit_quick_error(ctx, what))
@@ -313,6 +471,18 @@ async fn add(
 
     embed_add_roles(&mut emb, &roles, true, false);
 
+    let requirements = match cmds.get("composition").and_then(|c| c.as_str()) {
+        Some(c) => {
+            parse_composition(c, &roles)
+                .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+                .await?
+        }
+        None => Vec::new(),
+    };
+    if !requirements.is_empty() {
+        emb.field("Composition", fmt_composition(&requirements, &roles), false);
+    }
+
     let mut emb_loading_bosses = emb.clone();
     emb_loading_bosses.field("Bosses", "Loading...", false);
     aci.edit_original_interaction_response(ctx, |d| d.add_embed(emb_loading_bosses))
@@ -331,7 +501,7 @@ async fn add(
 
     let mut bosses: Vec<db::TrainingBoss> = Vec::with_capacity(bosses_str.len());
     for b in bosses_str {
-        let nb = db::TrainingBoss::by_repr(ctx, b.to_string())
+        let nb = db::TrainingBoss::by_repr(ctx, guild_id, b.to_string())
             .await
             .with_context(|| format!("Failed to load boss {}", b))
             .map_err_reply(|what| aci.edit_quick_error(ctx, what))
@@ -339,7 +509,8 @@ async fn add(
         bosses.push(nb);
     }
 
-    emb.fields_chunked_fmt(&bosses, |b| b.name.clone(), "Boss Pool", false, 10);
+    bosses.sort_by_key(|b| b.sort_key());
+    embed_add_bosses(&mut emb, &bosses, |b| b.name.clone());
 
     let mut emb_loading_tier = emb.clone();
     emb_loading_tier.field("Tier", "Loading...", false);
@@ -350,7 +521,7 @@ async fn add(
     let tier_fut: OptionFuture<_> = cmds
         .get("tier")
         .and_then(|v| v.as_str())
-        .map(|t| Tier::by_name(ctx, t.to_owned()))
+        .map(|t| Tier::by_name(ctx, guild_id, t.to_owned()))
         .into();
 
     let tier = tier_fut
@@ -382,29 +553,22 @@ async fn add(
         match react.parse_button()? {
             Button::Confirm => {
                 trace.step("Confirmed. Saving training");
-                let training =
-                    db::Training::insert(ctx, name.to_string(), datetime, tier.map(|t| t.id))
-                        .await
-                        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
-                        .await?;
-
-                trace.step("Saving roles");
-                for r in roles {
-                    training
-                        .add_role(ctx, r.id)
-                        .await
-                        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
-                        .await?;
-                }
-
-                trace.step("Saving training bosses");
-                for tb in bosses {
-                    training
-                        .add_training_boss(ctx, tb.id)
-                        .await
-                        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
-                        .await?;
-                }
+                let draft = db::TrainingDraft {
+                    title: name.to_string(),
+                    date: datetime,
+                    tier_id: tier.map(|t| t.id),
+                    max_participants,
+                    role_ids: roles.iter().map(|r| r.id).collect(),
+                    boss_ids: bosses.iter().map(|b| b.id).collect(),
+                    requirements,
+                };
+                let training = db::Training::insert_drafts(ctx, guild_id, vec![draft])
+                    .await
+                    .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+                    .await?
+                    .pop()
+                    .context("Unexpected missing training")?;
+                signup_board::SignupBoard::invalidate_trainings(ctx, guild_id).await;
 
                 emb.field("Training ID", training.id, false);
                 emb.footer(|f| f.text(format!("Training added {}", CHECK_EMOJI)));
@@ -429,12 +593,337 @@ async fn add(
     Ok(())
 }
 
+// Keeps the preview within discords embed limits
+const IMPORT_MAX_ROWS: usize = 20;
+
+// One row of an import file. Roles and bosses are comma separated
+// lists of repr's, just like for /training add
+#[derive(Deserialize)]
+struct ImportRow {
+    title: String,
+    date: String,
+    time: String,
+    roles: String,
+    bosses: String,
+    tier: Option<String>,
+    #[serde(default)]
+    composition: Option<String>,
+    #[serde(default)]
+    max_participants: Option<i32>,
+}
+
+struct ImportEntry {
+    title: String,
+    details: String,
+    draft: Result<db::TrainingDraft, Vec<String>>,
+}
+
+fn import_parse(filename: &str, bytes: &[u8]) -> Result<Vec<Result<ImportRow, String>>> {
+    let extension = filename.rsplit('.').next().map(str::to_lowercase);
+    let format = match extension.as_deref() {
+        Some("csv") => DonwloadFormat::Csv,
+        Some("json") => DonwloadFormat::Json,
+        _ => bail!("Unsupported file type. Use a .csv or .json file"),
+    };
+
+    match format {
+        DonwloadFormat::Csv => {
+            let mut rdr = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(bytes);
+            Ok(rdr
+                .deserialize::<ImportRow>()
+                .map(|r| r.map_err(|e| e.to_string()))
+                .collect())
+        }
+        DonwloadFormat::Json => {
+            let rows: Vec<ImportRow> =
+                serde_json::from_slice(bytes).context("Could not parse json file")?;
+            Ok(rows.into_iter().map(Ok).collect())
+        }
+    }
+}
+
+// Repeated reprs are only kept once, they would be added to the training twice
+fn split_reprs(list: &str) -> Vec<&str> {
+    list.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .unique()
+        .collect()
+}
+
+async fn import_validate(ctx: &Context, guild_id: GuildId, row: ImportRow) -> Result<ImportEntry> {
+    let mut errors: Vec<String> = Vec::new();
+
+    let date = row.date.parse::<NaiveDate>();
+    let time = row.time.parse::<NaiveTime>();
+    if date.is_err() {
+        errors.push(format!("Could not parse date: {}", row.date));
+    }
+    if time.is_err() {
+        errors.push(format!("Could not parse time: {}", row.time));
+    }
+
+    let roles_str = split_reprs(&row.roles);
+    if roles_str.is_empty() {
+        errors.push(String::from("No roles set"));
+    }
+    let mut roles: Vec<db::Role> = Vec::with_capacity(roles_str.len());
+    for r in &roles_str {
+        match db::Role::by_repr(ctx, guild_id, r.to_string()).await {
+            Ok(role) => roles.push(role),
+            Err(diesel::NotFound) => errors.push(format!("Unknown role: {}", r)),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let bosses_str = split_reprs(&row.bosses);
+    if bosses_str.is_empty() {
+        errors.push(String::from("No bosses set"));
+    }
+    let mut boss_ids: Vec<i32> = Vec::with_capacity(bosses_str.len());
+    for b in &bosses_str {
+        match db::TrainingBoss::by_repr(ctx, guild_id, b.to_string()).await {
+            Ok(boss) => boss_ids.push(boss.id),
+            Err(diesel::NotFound) => errors.push(format!("Unknown boss: {}", b)),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let tier = match row.tier.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        None => None,
+        Some(name) => match Tier::by_name(ctx, guild_id, name.to_string()).await {
+            Ok(t) => Some(t),
+            Err(diesel::NotFound) => {
+                errors.push(format!("Unknown tier: {}", name));
+                None
+            }
+            Err(e) => return Err(e.into()),
+        },
+    };
+
+    let requirements = match row.composition.as_deref() {
+        Some(c) => parse_composition(c, &roles).unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+
+    if matches!(row.max_participants, Some(max) if max < 1) {
+        errors.push(String::from("Max participants has to be at least 1"));
+    }
+
+    let mut details = match (&date, &time) {
+        (Ok(d), Ok(t)) => format!("<t:{}>", d.and_time(*t).timestamp()),
+        _ => format!("{} {}", row.date, row.time),
+    };
+    details.push_str(&format!(
+        "\nRoles: {}\nBosses: {}\nTier: {}",
+        roles_str.join(", "),
+        bosses_str.join(", "),
+        tier.as_ref()
+            .map(|t| t.name.as_str())
+            .unwrap_or("Open for everyone"),
+    ));
+    if !requirements.is_empty() {
+        details.push_str(&format!(
+            "\nComposition: {}",
+            fmt_composition(&requirements, &roles).replace('\n', ", ")
+        ));
+    }
+    if let Some(max) = row.max_participants {
+        details.push_str(&format!("\nMax participants: {}", max));
+    }
+
+    let draft = match (date, time) {
+        (Ok(d), Ok(t)) if errors.is_empty() => Ok(db::TrainingDraft {
+            title: row.title.clone(),
+            date: d.and_time(t),
+            tier_id: tier.map(|t| t.id),
+            max_participants: row.max_participants,
+            role_ids: roles.iter().map(|r| r.id).collect(),
+            boss_ids,
+            requirements,
+        }),
+        _ => Err(errors),
+    };
+
+    Ok(ImportEntry {
+        title: row.title,
+        details,
+        draft,
+    })
+}
+
+async fn import(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let guild_id = guild_id(aci)?;
+    let attachment = match option.options.get(0).and_then(|o| o.resolved.as_ref()) {
+        Some(ApplicationCommandInteractionDataOptionValue::Attachment(a)) => a,
+        _ => {
+            Err(anyhow!("Unexpected missing attachment"))
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    aci.create_quick_info(ctx, "Loading file...", true).await?;
+    let msg = aci.get_interaction_response(ctx).await?;
+
+    trace.step("Downloading file");
+    let bytes = attachment
+        .download()
+        .await
+        .context("Failed to download file")
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+
+    trace.step("Parsing file");
+    let rows = import_parse(&attachment.filename, &bytes)
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+
+    if rows.is_empty() {
+        Err(anyhow!("The file does not contain any trainings"))
+            .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+            .await?;
+    }
+
+    if rows.len() > IMPORT_MAX_ROWS {
+        Err(anyhow!(
+            "Too many trainings in file. At most {} are allowed at once",
+            IMPORT_MAX_ROWS
+        ))
+        .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+        .await?;
+    }
+
+    trace.step("Validating rows");
+    let mut entries: Vec<ImportEntry> = Vec::with_capacity(rows.len());
+    for row in rows {
+        let entry = match row {
+            Ok(row) => {
+                import_validate(ctx, guild_id, row)
+                    .await
+                    .context("Unexpected error validating the file")
+                    .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+                    .await?
+            }
+            Err(e) => ImportEntry {
+                title: String::from("Unreadable row"),
+                details: String::new(),
+                draft: Err(vec![e]),
+            },
+        };
+        entries.push(entry);
+    }
+
+    let valid = entries.iter().all(|e| e.draft.is_ok());
+
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Importing trainings");
+    emb.description(if valid {
+        format!("{} training(s) ready to be created", entries.len())
+    } else {
+        String::from("Some rows contain errors. Fix them and try again")
+    });
+    for (i, e) in entries.iter().enumerate() {
+        let value = match &e.draft {
+            Ok(_) => e.details.clone(),
+            Err(errs) => format!(
+                "{}\n{}",
+                e.details,
+                errs.iter().map(|err| format!("❌ {}", err)).join("\n")
+            ),
+        };
+        emb.field(
+            format!(
+                "{} {} | {}",
+                if e.draft.is_ok() { CHECK_EMOJI } else { '❌' },
+                i + 1,
+                e.title
+            ),
+            value.chars().take(250).collect::<String>(),
+            false,
+        );
+    }
+
+    if !valid {
+        trace.step("Invalid rows found");
+        aci.edit_original_interaction_response(ctx, |d| d.add_embed(emb))
+            .await?;
+        return Ok(());
+    }
+
+    aci.edit_original_interaction_response(ctx, |d| {
+        d.add_embed(emb.clone());
+        d.components(|c| c.create_action_row(|a| a.confirm_button().abort_button()))
+    })
+    .await?;
+
+    trace.step("Waiting for confirm");
+    if let Some(react) = msg
+        .await_confirm_abort_interaction(ctx)
+        .timeout(Duration::from_secs(60))
+        .await
+    {
+        react.defer(ctx).await?;
+        match react.parse_button()? {
+            Button::Confirm => {
+                trace.step("Confirmed. Saving trainings");
+                let drafts = entries
+                    .into_iter()
+                    .filter_map(|e| e.draft.ok())
+                    .collect::<Vec<_>>();
+                let trainings = db::Training::insert_drafts(ctx, guild_id, drafts)
+                    .await
+                    .context("Failed to save trainings. Nothing was created")
+                    .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+                    .await?;
+                signup_board::SignupBoard::invalidate_trainings(ctx, guild_id).await;
+
+                emb.field(
+                    "Training ID's",
+                    trainings.iter().map(|t| t.id.to_string()).join(", "),
+                    false,
+                );
+                emb.footer(|f| f.text(format!("Trainings added {}", CHECK_EMOJI)));
+                aci.edit_original_interaction_response(ctx, |d| {
+                    d.add_embed(emb);
+                    d.components(|c| c)
+                })
+                .await?;
+            }
+            Button::Abort => {
+                trace.step("Aborted");
+                aci.edit_quick_info(ctx, "Aborted").await?;
+            }
+            _ => bail!("Unexpected interaction"),
+        }
+    } else {
+        Err(anyhow!("Timed out"))
+            .map_err_reply(|what| aci.edit_quick_info(ctx, what))
+            .await?;
+    }
+
+    Ok(())
+}
+
 async fn set(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
     option: &ApplicationCommandInteractionDataOption,
     trace: LogTrace,
 ) -> Result<()> {
+    let guild_id = guild_id(aci)?;
+
     // Get subcommands
     let cmds = option
         .options
@@ -464,7 +953,7 @@ async fn set(
         .and_then(|d| d.as_str())
     {
         trainings.append(
-            &mut trainings_from_days(ctx, days)
+            &mut trainings_from_days(ctx, guild_id, days)
                 .await
                 .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
                 .await?,
@@ -477,7 +966,7 @@ async fn set(
         .and_then(|d| d.as_str())
     {
         trainings.append(
-            &mut trainings_from_ids(ctx, ids)
+            &mut trainings_from_ids(ctx, guild_id, ids)
                 .await
                 .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
                 .await?,
@@ -540,11 +1029,8 @@ async fn set(
                         .await?;
 
                     trace.step("Updating traning(s)");
-                    let update_futs: Vec<_> = trainings
-                        .into_iter()
-                        .map(|t| t.set_state(ctx, state.clone()))
-                        .collect();
-                    let _ = future::try_join_all(update_futs).await?;
+                    let trainings = update_states(ctx, trainings, state.clone()).await?;
+                    signup_board::SignupBoard::invalidate_trainings(ctx, guild_id).await;
 
                     response
                         .edit_original_interaction_response(ctx, |m| {
@@ -555,19 +1041,63 @@ async fn set(
                         })
                         .await?;
 
+                    // Everything saved to the database comes first. Discord refusing
+                    // one of the updates below must not lose it
+                    if state == TrainingState::Finished {
+                        trace.step("Recording boss completions");
+                        for t in &trainings {
+                            t.record_boss_completions(ctx).await?;
+                        }
+
+                        trace.step("Evaluating promotions");
+                        let tier_ids = trainings.iter().filter_map(|t| t.tier_id).collect_vec();
+                        let channel_id = review_channel(ctx, guild_id).await;
+                        promotions::evaluate(ctx, guild_id, &tier_ids, channel_id).await?;
+                    }
+
                     trace.step("Updating signup board");
-                    signup_board::SignupBoard::get(ctx)
-                        .await
-                        .read()
-                        .await
-                        .update_overview(ctx, trace.clone())
-                        .await?;
+                    signup_board::SignupBoard::request_update(ctx, guild_id).await;
 
                     trace.step("Updating status");
                     status::update_status(ctx).await;
 
+                    trace.step("Updating discussion threads");
+                    let channel_id = discussion_channel(ctx, guild_id).await;
+                    for t in &trainings {
+                        if let Err(e) = threads::state_changed(ctx, t, channel_id).await {
+                            warn!("Failed to update thread of {}: {}", t.id, e);
+                        }
+                    }
+
+                    let mut not_announced = false;
+                    let mut failed_announcements = Vec::new();
+                    if state == TrainingState::Closed {
+                        trace.step("Announcing rosters");
+                        let channel_id = announcement_channel(ctx, guild_id).await;
+                        for t in &trainings {
+                            match announcements::announce(ctx, t, channel_id).await {
+                                Ok(announced) => not_announced |= !announced,
+                                Err(e) => {
+                                    warn!("Failed to announce {}: {}", t.id, e);
+                                    failed_announcements.push(t.id.to_string());
+                                }
+                            }
+                        }
+                    }
+
                     response
                         .edit_original_interaction_response(ctx, |m| {
+                            if not_announced {
+                                m.add_embed(CreateEmbed::info_box(
+                                    "No announcement channel set, rosters were not announced",
+                                ));
+                            }
+                            if !failed_announcements.is_empty() {
+                                m.add_embed(CreateEmbed::error_box(format!(
+                                    "Failed to announce the roster of training(s) {}",
+                                    failed_announcements.join(", ")
+                                )));
+                            }
                             m.add_embed(CreateEmbed::info_box("Everything updated"))
                         })
                         .await?;
@@ -600,6 +1130,19 @@ async fn set(
     Ok(())
 }
 
+/// Sets all trainings to the new state
+pub(crate) async fn update_states(
+    ctx: &impl DBPoolProvider,
+    trainings: Vec<db::Training>,
+    state: TrainingState,
+) -> Result<Vec<db::Training>> {
+    let update_futs: Vec<_> = trainings
+        .into_iter()
+        .map(|t| t.set_state(ctx, state.clone()))
+        .collect();
+    Ok(future::try_join_all(update_futs).await?)
+}
+
 #[derive(Serialize)]
 enum DonwloadFormat {
     Json,
@@ -619,8 +1162,12 @@ impl std::fmt::Display for DonwloadFormat {
 struct SignupData {
     user: db::User,
     member: Member,
-    roles: Vec<String>, // we only need the repr here
+    roles: Vec<String>, // we only need the repr here. Main role first
     comment: Option<String>,
+    /// Position in the order of sign-ups, starting at 1
+    position: usize,
+    signed_up: NaiveDateTime,
+    last_change: NaiveDateTime,
 }
 
 // since csv is all row based edit on the fly
@@ -634,6 +1181,14 @@ struct SignupDataCsv<'a> {
     discord_ping: String,
     #[serde(rename = "Training Name")]
     training_name: &'a str,
+    #[serde(rename = "Position")]
+    position: usize,
+    #[serde(rename = "Signed Up")]
+    signed_up: NaiveDateTime,
+    #[serde(rename = "Last Change")]
+    last_change: NaiveDateTime,
+    #[serde(rename = "Main Role")]
+    main_role: Option<&'a str>,
     #[serde(rename = "Roles")]
     roles: String,
     #[serde(rename = "Comment")]
@@ -673,6 +1228,10 @@ impl DownloadData {
                     discord_acc: s.member.user.tag(),
                     discord_ping: Mention::from(s.member.user.id).to_string(),
                     training_name: &t.training.title,
+                    position: s.position,
+                    signed_up: s.signed_up,
+                    last_change: s.last_change,
+                    main_role: s.roles.first().map(|r| r.as_str()),
                     roles: s
                         .roles
                         .iter()
@@ -696,12 +1255,7 @@ async fn download(
     option: &ApplicationCommandInteractionDataOption,
     trace: LogTrace,
 ) -> Result<()> {
-    let guild_id = match ctx.data.read().await.get::<data::ConfigValuesData>() {
-        Some(conf) => conf.main_guild_id,
-        None => {
-            bail!("Guild configuration could not be loaded");
-        }
-    };
+    let guild_id = guild_id(aci)?;
 
     let guild = PartialGuild::get(ctx, guild_id).await?;
 
@@ -723,7 +1277,7 @@ async fn download(
         .and_then(|d| d.as_str())
     {
         trainings.append(
-            &mut trainings_from_days(ctx, days)
+            &mut trainings_from_days(ctx, guild_id, days)
                 .await
                 .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
                 .await?,
@@ -737,7 +1291,7 @@ async fn download(
         .and_then(|d| d.as_str())
     {
         trainings.append(
-            &mut trainings_from_ids(ctx, ids)
+            &mut trainings_from_ids(ctx, guild_id, ids)
                 .await
                 .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
                 .await?,
@@ -771,6 +1325,12 @@ async fn download(
         DonwloadFormat::Csv // Default
     };
 
+    let sort_by_account = cmds
+        .get("sort")
+        .and_then(|d| d.value.as_ref())
+        .and_then(|d| d.as_str())
+        == Some("account");
+
     // Check if we filter out finished training's
     if !cmds
         .get("include-finished")
@@ -780,6 +1340,24 @@ async fn download(
             trainings.retain(|t| t.state != TrainingState::Finished)
     }
 
+    trace.step("Loading boss completions");
+    let missing_boss = match cmds
+        .get("missing_boss")
+        .and_then(|d| d.value.as_ref())
+        .and_then(|d| d.as_str())
+    {
+        Some(repr) => {
+            let boss = db::TrainingBoss::by_repr(ctx, guild_id, repr.to_string())
+                .await
+                .with_context(|| format!("Unknown boss: {}", repr))
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?;
+            let completed_by = boss.completed_by(ctx).await?;
+            Some((boss, completed_by))
+        }
+        None => None,
+    };
+
     aci.create_quick_info(ctx, "Parsing training data...", true)
         .await?;
 
@@ -792,9 +1370,16 @@ async fn download(
         let signups = t.get_signups(ctx).await?;
         let mut sds: Vec<SignupData> = Vec::with_capacity(signups.len());
 
-        for s in signups {
+        // Signups are loaded in the order they signed up
+        for (position, s) in (1..).zip(signups) {
             let user = s.get_user(ctx).await?;
 
+            if let Some((_, completed_by)) = &missing_boss {
+                if completed_by.contains(&user.id) {
+                    continue;
+                }
+            }
+
             let member = match guild.member(ctx, user.discord_id()).await {
                 Ok(du) => du,
                 Err(_) => {
@@ -818,8 +1403,14 @@ async fn download(
                 member,
                 roles,
                 comment: s.comment,
+                position,
+                signed_up: s.created_at,
+                last_change: s.updated_at,
             });
         }
+        if sort_by_account {
+            sds.sort_by_key(|s| s.user.gw2_id.to_lowercase());
+        }
 
         let available_roles = t.all_roles(ctx).await?;
 
@@ -830,7 +1421,7 @@ async fn download(
         });
     }
 
-    let dbtiers = db::Tier::all(ctx).await?;
+    let dbtiers = db::Tier::all(ctx, guild_id).await?;
     let mut tiers: Vec<TierData> = Vec::with_capacity(dbtiers.len());
 
     for t in dbtiers {
@@ -894,15 +1485,15 @@ async fn download(
         .send_message(ctx, |m| {
             m.embed(|e| {
                 e.title("Download");
-                e.field(
-                    "Details",
-                    format!(
-                        "Format: {}\nCreated: <t:{}>",
-                        data.output,
-                        data.created.timestamp()
-                    ),
-                    false,
+                let mut details = format!(
+                    "Format: {}\nCreated: <t:{}>",
+                    data.output,
+                    data.created.timestamp()
                 );
+                if let Some((boss, _)) = &missing_boss {
+                    details.push_str(&format!("\nMissing boss: {} {}", boss.emoji, boss.name));
+                }
+                e.field("Details", details, false);
                 e.field(
                     "Trainings",
                     data.trainings
@@ -945,6 +1536,94 @@ async fn download(
     Ok(())
 }
 
+async fn clone(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = command_map(option);
+    let guild_id = guild_id(aci)?;
+
+    trace.step("Parsing command");
+    let id = cmds
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .context("Expected id field")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    let day: NaiveDate = cmds
+        .get("day")
+        .and_then(|n| n.as_str())
+        .context("day not set")?
+        .parse()
+        .context("Could not parse date")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    let time: NaiveTime = cmds
+        .get("time")
+        .and_then(|n| n.as_str())
+        .context("time not set")?
+        .parse()
+        .context("Could not parse time")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    let copy_signups = cmds
+        .get("signups")
+        .and_then(|v| v.as_str())
+        .unwrap_or("none");
+
+    trace.step("Loading training");
+    let training = db::Training::by_id(ctx, guild_id, id as i32)
+        .await
+        .with_context(|| format!("Failed to load training with id: {}", id))
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    // Sign-ups are loaded in the order they signed up
+    let signups = training.get_signups(ctx).await?;
+    let signup_ids = match copy_signups {
+        "all" => signups.iter().map(|s| s.id).collect(),
+        "waitlisted" => match training.max_participants {
+            Some(max) => signups.iter().skip(max as usize).map(|s| s.id).collect(),
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    trace.step("Saving copy");
+    let copy = training
+        .clone_to(ctx, day.and_time(time), signup_ids.clone())
+        .await
+        .context("Failed to copy the training. Nothing was created")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    signup_board::SignupBoard::invalidate_trainings(ctx, guild_id).await;
+
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Training copied");
+    emb.field("Training", &copy.title, false);
+    emb.field("Id", copy.id, true);
+    emb.field("Copied from", training.id, true);
+    emb.field("Date/Time", format!("<t:{}>", copy.date.timestamp()), false);
+    emb.field("State", &copy.state, true);
+    emb.field("Copied sign-ups", signup_ids.len(), true);
+
+    aci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource);
+        r.interaction_response_data(|d| {
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            d.add_embed(emb)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
 async fn info(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
@@ -952,6 +1631,7 @@ async fn info(
     trace: LogTrace,
 ) -> Result<()> {
     let cmds = command_map(option);
+    let guild_id = guild_id(aci)?;
 
     let id = cmds
         .get("id")
@@ -967,7 +1647,7 @@ async fn info(
 
     trace.step("Loading training information");
 
-    let training = db::Training::by_id(ctx, id as i32)
+    let training = db::Training::by_id(ctx, guild_id, id as i32)
         .await
         .with_context(|| format!("Failed to load training with id: {}", id))
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
@@ -975,21 +1655,126 @@ async fn info(
 
     let bosses = training.all_training_bosses(ctx).await?;
 
-    let roles = training.all_roles(ctx).await?;
+    trace.step("Loading signups to calculate role count");
+    let roster = Roster::load(ctx, &training).await?;
+    let roles = &roster.roles;
 
-    // HashMap with Role id as key and value to keep count
-    let mut roles_count = roles.iter().map(|r| (r.id, 0)).collect::<HashMap<_, _>>();
+    // HashMap with Role id as key and value to keep count of main and all picks
+    let mut roles_count = roles
+        .iter()
+        .map(|r| (r.id, (0, 0)))
+        .collect::<HashMap<_, _>>();
 
-    trace.step("Loading signups to calculate role count");
-    let signups = training.get_signups(ctx).await?;
+    let signup_roles = roster
+        .entries
+        .iter()
+        .map(|e| e.role_ids.clone())
+        .collect::<Vec<_>>();
+    for ids in &signup_roles {
+        for (rank, id) in ids.iter().enumerate() {
+            roles_count.entry(*id).and_modify(|(main, all)| {
+                if rank == 0 {
+                    *main += 1;
+                }
+                *all += 1;
+            });
+        }
+    }
 
-    future::try_join_all(signups.iter().map(|s| s.get_roles(ctx)))
-        .await?
-        .into_iter()
-        .flatten()
-        .for_each(|sr| {
-            roles_count.entry(sr.id).and_modify(|e| *e += 1);
-        });
+    let signup_order = roster
+        .entries
+        .iter()
+        .map(|e| {
+            let preference = e
+                .role_ids
+                .iter()
+                .filter_map(|id| roles.iter().find(|r| r.id == *id))
+                .map(|r| r.repr.as_str())
+                .join(" > ");
+            format!(
+                "{}. {} ({}) <t:{}:R>{}",
+                e.position,
+                e.user.gw2_id,
+                preference,
+                e.signup.created_at.timestamp(),
+                if e.waitlisted { " ⏳" } else { "" }
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let requirements = training.all_requirements(ctx).await?;
+    let selected = roster
+        .entries
+        .iter()
+        .filter(|e| !e.waitlisted)
+        .map(|e| e.role_ids.clone())
+        .collect::<Vec<_>>();
+    let composition = db::RequirementStatus::compute(&requirements, roles, &selected);
+
+    let mut emb = CreateEmbed::xdefault();
+    emb.field("Training", &training.title, false);
+    emb.field("State", &training.state, false);
+    emb.field(
+        "Date/Time",
+        format!("<t:{}>", training.date.timestamp()),
+        false,
+    );
+
+    embed_add_bosses(&mut emb, &bosses, boss_link);
+    emb.fields_chunked_fmt(
+        roles,
+        |r| {
+            let (main, all) = roles_count.get(&r.id).unwrap();
+            format!("{} |{:>3} /{:>3}| {}", r.emoji, main, all, r.title)
+        },
+        "Sign-up Count (main / all)",
+        true,
+        10,
+    );
+    if !composition.is_empty() {
+        emb.field("Composition", composition.iter().join("\n"), false);
+    }
+    if let Some(max) = training.max_participants {
+        let count = roster.entries.len() as i32;
+        emb.field(
+            "Participants",
+            format!(
+                "{}/{}, {} waitlisted",
+                count.min(max),
+                max,
+                (count - max).max(0)
+            ),
+            false,
+        );
+    }
+
+    // The roster and sign-up order can get long. They go on their own pages
+    let mut pages = vec![emb];
+    for (title, fields) in [
+        ("Roster", roster.fields()),
+        (
+            "Sign-up Order",
+            roster::chunk_lines(&signup_order)
+                .into_iter()
+                .map(|v| (String::from("Sign-ups"), v))
+                .collect(),
+        ),
+    ] {
+        for page in roster::paginate(fields) {
+            let mut emb = CreateEmbed::xdefault();
+            emb.title(format!("{} | {}", training.title, title));
+            for (name, value) in page {
+                emb.field(name, value, false);
+            }
+            pages.push(emb);
+        }
+    }
+    let page_count = pages.len();
+    if page_count > 1 {
+        for (i, p) in pages.iter_mut().enumerate() {
+            p.footer(|f| f.text(format!("Page {}/{}", i + 1, page_count)));
+        }
+    }
 
     trace.step("Replying to user");
     aci.create_interaction_response(ctx, |r| {
@@ -998,56 +1783,70 @@ async fn info(
             if !public {
                 d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
             }
-            let mut emb = CreateEmbed::xdefault();
-            emb.field("Training", training.title, false);
-            emb.field("State", training.state, false);
-            emb.field(
-                "Date/Time",
-                format!("<t:{}>", training.date.timestamp()),
-                false,
-            );
-
-            if !bosses.is_empty() {
-                emb.fields_chunked_fmt(
-                    &bosses,
-                    |b| {
-                        let boss_link = match &b.url {
-                            Some(l) => format!("[{}]({})", b.name, l),
-                            None => b.name.to_string(),
-                        };
-                        format!(
-                            "{} | {}",
-                            Mention::from(EmojiId::from(b.emoji as u64)),
-                            boss_link
-                        )
-                    },
-                    "Boss Pool",
-                    false,
-                    8,
-                );
+            d.add_embed(pages[0].clone());
+            if page_count > 1 {
+                d.components(|c| c.add_action_row(page_buttons(0, page_count)));
             }
-            emb.fields_chunked_fmt(
-                &roles,
-                |r| {
-                    format!(
-                        "{} |{:>3}| {}",
-                        Mention::from(EmojiId::from(r.emoji as u64)),
-                        roles_count.get(&r.id).unwrap(),
-                        r.title
-                    )
-                },
-                "Sign-up Count",
-                true,
-                10,
-            );
-            d.add_embed(emb)
+            d
         })
     })
     .await?;
 
+    if page_count == 1 {
+        return Ok(());
+    }
+
+    trace.step("Paging");
+    let msg = aci.get_interaction_response(ctx).await?;
+    let mut page: usize = 0;
+    while let Some(mci) = msg
+        .await_component_interaction(ctx)
+        .author_id(aci.user.id)
+        .timeout(Duration::from_secs(60 * 5))
+        .await
+    {
+        page = match mci.data.custom_id.as_str() {
+            PAGE_PREVIOUS => page.saturating_sub(1),
+            PAGE_NEXT => (page + 1).min(page_count - 1),
+            _ => continue,
+        };
+        mci.create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::UpdateMessage);
+            r.interaction_response_data(|d| {
+                d.set_embeds(vec![pages[page].clone()]);
+                d.components(|c| c.add_action_row(page_buttons(page, page_count)))
+            })
+        })
+        .await?;
+    }
+
+    // Buttons stop working once nobody listens anymore
+    aci.edit_original_interaction_response(ctx, |d| d.components(|c| c))
+        .await?;
+
     Ok(())
 }
 
+const PAGE_PREVIOUS: &str = "page_previous";
+const PAGE_NEXT: &str = "page_next";
+
+fn page_buttons(page: usize, page_count: usize) -> CreateActionRow {
+    let mut previous = CreateButton::default();
+    previous.style(ButtonStyle::Secondary);
+    previous.label("◀ Previous");
+    previous.custom_id(PAGE_PREVIOUS);
+    previous.disabled(page == 0);
+    let mut next = CreateButton::default();
+    next.style(ButtonStyle::Secondary);
+    next.label("Next ▶");
+    next.custom_id(PAGE_NEXT);
+    next.disabled(page + 1 >= page_count);
+    let mut ar = CreateActionRow::default();
+    ar.add_button(previous);
+    ar.add_button(next);
+    ar
+}
+
 async fn list(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
@@ -1055,12 +1854,14 @@ async fn list(
     trace: LogTrace,
 ) -> Result<()> {
     let cmds = command_map(option);
+    let guild_id = guild_id(aci)?;
 
     trace.step("Loading training's");
-    let mut trainings = trainings_from_days(ctx, cmds.get("day").unwrap().as_str().unwrap())
-        .await
-        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-        .await?;
+    let mut trainings =
+        trainings_from_days(ctx, guild_id, cmds.get("day").unwrap().as_str().unwrap())
+            .await
+            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+            .await?;
     trainings.sort_by_key(|t| t.date);
 
     let mut embeds: Vec<CreateEmbed> = Vec::new();
@@ -1092,3 +1893,248 @@ async fn list(
     .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TestContext};
+    use serenity::model::id::EmojiId;
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn update_states_changes_all_trainings() {
+        let ctx = TestContext::new();
+        let first = testing::training(&ctx, TrainingState::Created, None, &[]).await;
+        let second = testing::training(&ctx, TrainingState::Created, None, &[]).await;
+
+        let updated = update_states(&ctx, vec![first, second], TrainingState::Open)
+            .await
+            .unwrap();
+        assert_eq!(updated.len(), 2);
+
+        for t in updated {
+            let loaded = db::Training::by_id(&ctx, testing::GUILD_ID, t.id)
+                .await
+                .unwrap();
+            assert_eq!(loaded.state, TrainingState::Open);
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn finished_trainings_record_boss_completions() {
+        let ctx = TestContext::new();
+        testing::guild(&ctx).await;
+        let repr = testing::unique("boss");
+        let boss = db::TrainingBoss::insert(
+            &ctx,
+            testing::GUILD_ID,
+            repr.clone(),
+            repr,
+            db::BossCategory::Raid,
+            1,
+            1,
+            EmojiId(1).into(),
+            None,
+        )
+        .await
+        .unwrap();
+        let training = testing::training(&ctx, TrainingState::Created, None, &[]).await;
+        training.add_training_boss(&ctx, boss.id).await.unwrap();
+        let training = training.set_max_participants(&ctx, Some(1)).await.unwrap();
+        let attendee = testing::user(&ctx, 2301).await;
+        let waitlisted = testing::user(&ctx, 2302).await;
+        for user in [&attendee, &waitlisted] {
+            db::Signup::insert(&ctx, user, &training).await.unwrap();
+        }
+
+        assert_eq!(training.record_boss_completions(&ctx).await.unwrap(), 1);
+        // Recording again does not count the boss twice
+        assert_eq!(training.record_boss_completions(&ctx).await.unwrap(), 0);
+        assert_eq!(boss.completed_by(&ctx).await.unwrap(), vec![attendee.id]);
+
+        let progress = attendee
+            .boss_progress(&ctx, testing::GUILD_ID)
+            .await
+            .unwrap();
+        assert_eq!(progress.len(), 1);
+        assert_eq!((progress[0].0.id, progress[0].1), (boss.id, 1));
+        assert!(waitlisted
+            .boss_progress(&ctx, testing::GUILD_ID)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn clone_copies_setup_and_given_signups() {
+        let ctx = TestContext::new();
+        let (tank, heal) = (testing::role(&ctx).await, testing::role(&ctx).await);
+        let repr = testing::unique("boss");
+        let boss = db::TrainingBoss::insert(
+            &ctx,
+            testing::GUILD_ID,
+            repr.clone(),
+            repr,
+            db::BossCategory::Raid,
+            1,
+            1,
+            EmojiId(1).into(),
+            None,
+        )
+        .await
+        .unwrap();
+        let requirements = vec![
+            db::RequirementDraft {
+                role_id: Some(tank.id),
+                category: None,
+                min_count: 1,
+                max_count: Some(2),
+            },
+            db::RequirementDraft {
+                role_id: None,
+                category: Some(String::from("Support")),
+                min_count: 2,
+                max_count: None,
+            },
+        ];
+        let training = db::Training::insert_drafts(
+            &ctx,
+            testing::GUILD_ID,
+            vec![db::TrainingDraft {
+                title: testing::unique("Training"),
+                date: testing::tomorrow(),
+                tier_id: None,
+                max_participants: Some(2),
+                role_ids: vec![tank.id, heal.id],
+                boss_ids: vec![boss.id],
+                requirements: requirements.clone(),
+            }],
+        )
+        .await
+        .unwrap()
+        .remove(0);
+
+        let mut signups = Vec::new();
+        for discord_id in [2401, 2402, 2403] {
+            let user = testing::user(&ctx, discord_id).await;
+            let signup = db::Signup::insert(&ctx, &user, &training).await.unwrap();
+            signup.add_role(&ctx, &heal, 1).await.unwrap();
+            signup.add_role(&ctx, &tank, 2).await.unwrap();
+            signups.push(signup);
+        }
+
+        let date = NaiveDate::from_ymd(2030, 1, 1).and_hms(20, 0, 0);
+        let copy = training
+            .clone_to(&ctx, date, vec![signups[0].id, signups[2].id])
+            .await
+            .unwrap();
+        assert_ne!(copy.id, training.id);
+        assert_eq!(copy.title, training.title);
+        assert_eq!(copy.date, date);
+        assert_eq!(copy.state, TrainingState::Created);
+        assert_eq!(copy.max_participants, Some(2));
+
+        let mut roles = copy
+            .all_roles(&ctx)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.id)
+            .collect::<Vec<_>>();
+        roles.sort_unstable();
+        assert_eq!(roles, vec![tank.id, heal.id]);
+        let bosses = copy.all_training_bosses(&ctx).await.unwrap();
+        assert_eq!(
+            bosses.iter().map(|b| b.id).collect::<Vec<_>>(),
+            vec![boss.id]
+        );
+        let copied_requirements = copy
+            .all_requirements(&ctx)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| db::RequirementDraft {
+                role_id: r.role_id,
+                category: r.category,
+                min_count: r.min_count,
+                max_count: r.max_count,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(copied_requirements, requirements);
+
+        // Only the selected ones, in the order they signed up in and with their ranks
+        let copied = copy.get_signups(&ctx).await.unwrap();
+        assert_eq!(
+            copied.iter().map(|s| s.user_id).collect::<Vec<_>>(),
+            vec![signups[0].user_id, signups[2].user_id]
+        );
+        for signup in &copied {
+            let ranked = signup
+                .get_roles(&ctx)
+                .await
+                .unwrap()
+                .iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>();
+            assert_eq!(ranked, vec![heal.id, tank.id]);
+        }
+        // The original keeps its sign-ups
+        assert_eq!(training.get_signups(&ctx).await.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn composition_names_roles_and_categories() {
+        let role = |id, repr: &str, category: &str| db::Role {
+            id,
+            title: repr.to_string(),
+            repr: repr.to_string(),
+            emoji: db::Emoji::Unicode(String::from("🛡")),
+            active: true,
+            priority: 2,
+            guild_id: 1,
+            category: Some(category.to_string()),
+        };
+        let roles = [role(1, "druid", "healer"), role(2, "qfb", "dps")];
+
+        let parsed = parse_composition("druid:1, Healer:2,DPS:0-6", &roles).unwrap();
+        assert_eq!(
+            parsed,
+            [
+                db::RequirementDraft {
+                    role_id: Some(1),
+                    category: None,
+                    min_count: 1,
+                    max_count: None,
+                },
+                db::RequirementDraft {
+                    role_id: None,
+                    category: Some(String::from("healer")),
+                    min_count: 2,
+                    max_count: None,
+                },
+                db::RequirementDraft {
+                    role_id: None,
+                    category: Some(String::from("dps")),
+                    min_count: 0,
+                    max_count: Some(6),
+                },
+            ]
+        );
+        assert!(parse_composition("tank:1", &roles).is_err());
+        assert!(parse_composition("healer:3-2", &roles).is_err());
+    }
+
+    #[test]
+    fn import_accepts_any_case_and_repeated_reprs() {
+        let csv = "title,date,time,roles,bosses,tier\nRaid,2022-05-01,20:00,\"dps, heal, dps\",\"vg,vg\",\n";
+        let rows = import_parse("trainings.CSV", csv.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 1);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!(split_reprs(&row.roles), ["dps", "heal"]);
+        assert_eq!(split_reprs(&row.bosses), ["vg"]);
+
+        assert!(import_parse("trainings.txt", csv.as_bytes()).is_err());
+    }
+}
@@ -6,7 +6,6 @@ use serenity::{
     builder::{CreateApplicationCommand, CreateEmbed},
     client::Context,
     model::{
-        guild::Guild,
         interactions::{
             application_command::{
                 ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
@@ -14,17 +13,16 @@ use serenity::{
             },
             InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
         },
-        mention::Mention,
         Permissions,
     },
 };
 use url::Url;
 
 use crate::{
-    data::ConfigValuesData,
-    db::{self, TrainingBoss},
+    db::{self, BossCategory, TrainingBoss},
     embeds::CrossroadsEmbeds,
     logging::*,
+    signup_board::SignupBoard,
 };
 
 use serenity_tools::{
@@ -34,10 +32,25 @@ use serenity_tools::{
     interactions::{ApplicationCommandInteractionExt, MessageComponentInteractionExt},
 };
 
-use super::helpers::command_map;
+use super::helpers::{command_map, guild_id, resolve_emoji};
 
 pub(super) const CMD_TRAINING_BOSS: &str = "training_boss";
 
+/// Values of the category option. Raid wings and strikes come with their number
+const CATEGORY_CHOICES: &[(&str, BossCategory, i32)] = &[
+    ("wing1", BossCategory::Raid, 1),
+    ("wing2", BossCategory::Raid, 2),
+    ("wing3", BossCategory::Raid, 3),
+    ("wing4", BossCategory::Raid, 4),
+    ("wing5", BossCategory::Raid, 5),
+    ("wing6", BossCategory::Raid, 6),
+    ("wing7", BossCategory::Raid, 7),
+    ("strike_ibs", BossCategory::Strike, 1),
+    ("strike_eod", BossCategory::Strike, 2),
+    ("fractal", BossCategory::Fractal, 0),
+    ("other", BossCategory::Other, 0),
+];
+
 pub fn create() -> CreateApplicationCommand {
     let mut app = CreateApplicationCommand::default();
     app.name(CMD_TRAINING_BOSS);
@@ -63,36 +76,68 @@ pub fn create() -> CreateApplicationCommand {
             o.required(true)
         });
         o.create_sub_option(|o| {
-            o.kind(ApplicationCommandOptionType::Integer);
-            o.name("wing");
-            o.description("The wing the boss belongs to");
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("category");
+            o.description("The raid wing, strikes or fractals the boss belongs to");
             o.required(true);
-            o.add_int_choice("Wing 1", 1);
-            o.add_int_choice("Wing 2", 2);
-            o.add_int_choice("Wing 3", 3);
-            o.add_int_choice("Wing 4", 4);
-            o.add_int_choice("Wing 5", 5);
-            o.add_int_choice("Wing 6", 6);
-            o.add_int_choice("Wing 7", 7);
-            o.add_int_choice("Strikes IBS", 101);
-            o.add_int_choice("Strikes EoD", 102)
+            for (value, category, number) in CATEGORY_CHOICES {
+                o.add_string_choice(category.group_name(*number), value);
+            }
+            o
         });
         o.create_sub_option(|o| {
             o.kind(ApplicationCommandOptionType::Integer);
             o.name("position");
-            o.description("Which boss it is in the specified wing/strike");
+            o.description("Which boss it is in the specified category, starting at 1");
             o.required(true);
-            o.add_int_choice("Boss 1", 1);
-            o.add_int_choice("Boss 2", 2);
-            o.add_int_choice("Boss 3", 3);
-            o.add_int_choice("Boss 4", 4);
-            o.add_int_choice("Boss 5", 5)
+            o.min_int_value(1)
         });
         o.create_sub_option(|o| {
             o.kind(ApplicationCommandOptionType::String);
             o.name("emoji");
             o.required(true);
-            o.description("The emoji for the boss")
+            o.description("A unicode emoji or one from \"/config emoji_list\"")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("link");
+            o.description("A Link to more information about the boss. Eg the wiki")
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("edit");
+        o.description("Change a boss");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("repr");
+            o.description("The unique identifier of the boss");
+            o.required(true)
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("name");
+            o.description("The new full name of the boss")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("category");
+            o.description("The raid wing, strikes or fractals the boss belongs to");
+            for (value, category, number) in CATEGORY_CHOICES {
+                o.add_string_choice(category.group_name(*number), value);
+            }
+            o
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Integer);
+            o.name("position");
+            o.description("Which boss it is in the category, starting at 1");
+            o.min_int_value(1)
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("emoji");
+            o.description("A unicode emoji or one from \"/config emoji_list\"")
         });
         o.create_sub_option(|o| {
             o.kind(ApplicationCommandOptionType::String);
@@ -125,6 +170,7 @@ pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
         if let Some(sub) = aci.data.options.get(0) {
             match sub.name.as_ref() {
                 "add" => add(ctx, aci, sub, trace).await,
+                "edit" => edit(ctx, aci, sub, trace).await,
                 "remove" => remove(ctx, aci, sub, trace).await,
                 "list" => list(ctx, aci, sub, trace).await,
                 _ => bail!("{} not yet available", sub.name),
@@ -136,6 +182,21 @@ pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
     .await;
 }
 
+fn parse_category(value: &str) -> Option<(BossCategory, i32)> {
+    CATEGORY_CHOICES
+        .iter()
+        .find(|(v, _, _)| *v == value)
+        .map(|(_, category, number)| (*category, *number))
+}
+
+fn parse_link(link: &str) -> Result<Url> {
+    let url = link.parse::<Url>().context("Could not parse Url")?;
+    if url.scheme() != "https" {
+        bail!("Only https is allowed: {}", url);
+    }
+    Ok(url)
+}
+
 async fn add(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
@@ -160,13 +221,13 @@ async fn add(
         .await?
         .to_owned();
 
-    let wing: i32 = cmds
-        .get("wing")
-        .and_then(|d| d.as_i64())
-        .context("wing is required")
+    let (category, category_number) = cmds
+        .get("category")
+        .and_then(|d| d.as_str())
+        .and_then(parse_category)
+        .context("category is required")
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-        .await?
-        .try_into()?;
+        .await?;
 
     let position: i32 = cmds
         .get("position")
@@ -176,27 +237,13 @@ async fn add(
         .await?
         .try_into()?;
 
-    let url = cmds
-        .get("link")
-        .and_then(|d| d.as_str())
-        .map(|s| s.parse::<Url>());
-
-    let url = match url {
+    let url = match cmds.get("link").and_then(|d| d.as_str()) {
         None => None,
-        Some(url) => {
-            let u = url
-                .context("Could not parse Url")
+        Some(link) => Some(
+            parse_link(link)
                 .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-                .await?;
-
-            if u.scheme() != "https" {
-                Err(anyhow!("Only https is allowed: {}", u))
-                    .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-                    .await?;
-            }
-
-            Some(u)
-        }
+                .await?,
+        ),
     };
 
     let emoji_str = cmds
@@ -206,33 +253,11 @@ async fn add(
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
 
-    // load all emojis from discord emoji guild
-    let gid = ctx
-        .data
-        .read()
+    let guild_id = guild_id(aci)?;
+    let emoji = resolve_emoji(ctx, guild_id, emoji_str)
         .await
-        .get::<ConfigValuesData>()
-        .unwrap()
-        .emoji_guild_id;
-    let emoji_guild = Guild::get(ctx, gid).await?;
-
-    let emoji_id = match emoji_guild
-        .emojis(ctx)
-        .await?
-        .into_iter()
-        .find(|e| e.name == emoji_str)
-    {
-        Some(e) => e.id,
-        None => {
-            Err(anyhow!(
-                "The emoji {} was not found in the emoji guild",
-                emoji_str
-            ))
-            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-            .await?;
-            return Ok(());
-        }
-    };
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
 
     trace.step("Replying with data");
     aci.create_interaction_response(ctx, |r| {
@@ -243,9 +268,9 @@ async fn add(
             emb.title("New Training Boss");
             emb.field("Name", &name, false);
             emb.field("Repr", &repr, true);
-            emb.field("Wing", wing, true);
+            emb.field("Category", category.group_name(category_number), true);
             emb.field("Boss", position, true);
-            emb.field("Emoji", Mention::from(emoji_id), true);
+            emb.field("Emoji", &emoji, true);
             if let Some(url) = &url {
                 emb.field("Url", url, false);
             } else {
@@ -269,10 +294,20 @@ async fn add(
         match react.parse_button()? {
             Button::Confirm => {
                 trace.step("Confirmed, inserting to database");
-                let boss = db::TrainingBoss::insert(ctx, name, repr, wing, position, emoji_id, url)
-                    .await
-                    .map_err_reply(|what| aci.edit_quick_error(ctx, what))
-                    .await?;
+                let boss = db::TrainingBoss::insert(
+                    ctx,
+                    guild_id,
+                    name,
+                    repr,
+                    category,
+                    category_number,
+                    position,
+                    emoji,
+                    url,
+                )
+                .await
+                .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+                .await?;
                 aci.edit_quick_info(ctx, format!("Created boss:\n{}", boss))
                     .await?;
             }
@@ -291,6 +326,95 @@ async fn add(
     Ok(())
 }
 
+async fn edit(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = command_map(option);
+
+    let repr = cmds
+        .get("repr")
+        .and_then(|d| d.as_str())
+        .context("repr is required")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    let mut changes = db::TrainingBossChanges {
+        name: cmds
+            .get("name")
+            .and_then(|d| d.as_str())
+            .map(|n| n.to_string()),
+        ..Default::default()
+    };
+
+    if let Some((category, number)) = cmds
+        .get("category")
+        .and_then(|d| d.as_str())
+        .and_then(parse_category)
+    {
+        changes.category = Some(category);
+        changes.category_number = Some(number);
+    }
+
+    if let Some(position) = cmds.get("position").and_then(|d| d.as_i64()) {
+        changes.position = Some(position.try_into()?);
+    }
+
+    if let Some(link) = cmds.get("link").and_then(|d| d.as_str()) {
+        let url = parse_link(link)
+            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+            .await?;
+        changes.url = Some(url.to_string());
+    }
+
+    let guild_id = guild_id(aci)?;
+    if let Some(emoji_str) = cmds.get("emoji").and_then(|d| d.as_str()) {
+        changes.emoji = Some(
+            resolve_emoji(ctx, guild_id, emoji_str)
+                .await
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?,
+        );
+    }
+
+    if changes.name.is_none()
+        && changes.category.is_none()
+        && changes.position.is_none()
+        && changes.url.is_none()
+        && changes.emoji.is_none()
+    {
+        Err(anyhow!("Nothing to change"))
+            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+            .await?;
+    }
+
+    trace.step("Loading boss");
+    let boss = db::TrainingBoss::by_repr(ctx, guild_id, repr.to_string())
+        .await
+        .with_context(|| format!("Boss {} does not exist", repr))
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    trace.step("Saving boss");
+    let boss = boss
+        .edit(ctx, changes)
+        .await
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    SignupBoard::request_update(ctx, guild_id).await;
+
+    aci.create_quick_success(
+        ctx,
+        format!("Updated boss:\n{}\n{}", boss, boss.group_name()),
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
 async fn remove(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
@@ -307,7 +431,7 @@ async fn remove(
         .await?;
 
     trace.step("Loading boss");
-    let boss = match db::TrainingBoss::by_repr(ctx, boss_repr.to_string()).await {
+    let boss = match db::TrainingBoss::by_repr(ctx, guild_id(aci)?, boss_repr.to_string()).await {
         Ok(o) => o,
         Err(diesel::NotFound) => {
             Err(diesel::NotFound)
@@ -342,7 +466,7 @@ async fn list(
     trace: LogTrace,
 ) -> Result<()> {
     trace.step("Loading training bosses");
-    let mut bosses = db::TrainingBoss::all(ctx)
+    let mut bosses = db::TrainingBoss::all(ctx, guild_id(aci)?)
         .await
         .context("Failed to load training bosses =(")
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
@@ -350,12 +474,11 @@ async fn list(
 
     trace.step("Sorting bosses");
 
-    bosses.sort_by_key(|b| b.wing);
-    let mut bosses_grouped: Vec<(i32, Vec<TrainingBoss>)> = Vec::new();
-    for (w, b) in &bosses.into_iter().group_by(|b| b.wing) {
-        bosses_grouped.push((w, b.collect()));
+    bosses.sort_by_key(|b| b.sort_key());
+    let mut bosses_grouped: Vec<(String, Vec<TrainingBoss>)> = Vec::new();
+    for ((c, n), b) in &bosses.into_iter().group_by(|b| b.group()) {
+        bosses_grouped.push((c.group_name(n), b.collect()));
     }
-    bosses_grouped.sort_by_key(|(k, _)| *k);
 
     trace.step("Replying with data");
     aci.create_interaction_response(ctx, |r| {
@@ -363,13 +486,9 @@ async fn list(
         r.interaction_response_data(|d| {
             d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
             d.embed(|e| {
-                for (w, b) in bosses_grouped {
+                for (name, b) in bosses_grouped {
                     e.field(
-                        if w < 100 {
-                            format!("Wing {}", w)
-                        } else {
-                            format!("Strike Pool {}", w - 100)
-                        },
+                        name,
                         b.iter()
                             .map(|b| b.to_string())
                             .collect::<Vec<_>>()
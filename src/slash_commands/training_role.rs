@@ -3,8 +3,6 @@ use serenity::{
     builder::{CreateApplicationCommand, CreateEmbed},
     client::Context,
     model::{
-        guild::Guild,
-        id::EmojiId,
         interactions::{
             application_command::{
                 ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
@@ -12,14 +10,14 @@ use serenity::{
             },
             InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
         },
-        mention::Mention,
         Permissions,
     },
 };
 
 use serenity_tools::{builder::CreateEmbedExt, interactions::ApplicationCommandInteractionExt};
 
-use crate::{data::ConfigValuesData, db, embeds::CrossroadsEmbeds, logging::*};
+use super::helpers::{guild_id, resolve_emoji};
+use crate::{db, embeds::CrossroadsEmbeds, logging::*, signup_board::SignupBoard};
 
 pub(super) const CMD_TRAINING_ROLE: &str = "training_role";
 pub fn create() -> CreateApplicationCommand {
@@ -49,7 +47,7 @@ pub fn create() -> CreateApplicationCommand {
         o.create_sub_option(|o| {
             o.kind(ApplicationCommandOptionType::String);
             o.name("emoji");
-            o.description("Use \"/config emoji_list\" for a list of options");
+            o.description("A unicode emoji or one from \"/config emoji_list\"");
             o.required(true)
         });
         o.create_sub_option(|o| {
@@ -62,6 +60,42 @@ pub fn create() -> CreateApplicationCommand {
             o.add_string_choice("Normal", "normal");
             o.add_string_choice("Low Priority", "low");
             o.add_string_choice("Very Low Priority", "very_low")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("category");
+            o.description("Groups roles for training requirements. Example: healer, quickness, dps")
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("edit");
+        o.description("Change a role. Editing a removed role activates it again");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("repr");
+            o.description("The short identifier for the role.");
+            o.required(true)
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("name");
+            o.description("The new full name of the role")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("emoji");
+            o.description("A unicode emoji or one from \"/config emoji_list\"")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("priority");
+            o.description("Higher priorities are listed first in select menus");
+            o.add_string_choice("Very High Priority", "very_high");
+            o.add_string_choice("High Priority", "high");
+            o.add_string_choice("Normal", "normal");
+            o.add_string_choice("Low Priority", "low");
+            o.add_string_choice("Very Low Priority", "very_low")
         })
     });
     app.create_option(|o| {
@@ -89,6 +123,7 @@ pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
         if let Some(sub) = aci.data.options.get(0) {
             match sub.name.as_ref() {
                 "add" => add(ctx, aci, sub, trace).await,
+                "edit" => edit(ctx, aci, sub, trace).await,
                 "remove" => remove(ctx, aci, sub, trace).await,
                 "list" => list(ctx, aci, trace).await,
                 _ => bail!("{} not yet available", sub.name),
@@ -100,6 +135,17 @@ pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
     .await;
 }
 
+fn parse_priority(value: &str) -> Result<i16> {
+    match value {
+        "very_high" => Ok(4),
+        "high" => Ok(3),
+        "normal" => Ok(2),
+        "low" => Ok(1),
+        "very_low" => Ok(0),
+        s => Err(anyhow!("Unexpected priority value: {}", s)),
+    }
+}
+
 async fn add(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
@@ -128,22 +174,13 @@ async fn add(
             .await?;
     }
 
-    let priority = match cmds
+    let priority = cmds
         .get("priority")
         .and_then(|v| v.as_str())
         .context("Unexpected missing field priority")
+        .and_then(parse_priority)
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-        .await?
-    {
-        "very_high" => Ok(4),
-        "high" => Ok(3),
-        "normal" => Ok(2),
-        "low" => Ok(1),
-        "very_low" => Ok(0),
-        s => Err(anyhow!("Unexpected priority value: {}", s)),
-    }
-    .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-    .await?;
+        .await?;
 
     let emoji_str = cmds
         .get("emoji")
@@ -152,42 +189,36 @@ async fn add(
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
 
+    let category = cmds
+        .get("category")
+        .and_then(|v| v.as_str())
+        .map(|c| c.trim().to_lowercase())
+        .filter(|c| !c.is_empty());
+
+    if let Some(c) = &category {
+        if c.contains(|ch: char| ch.is_whitespace() || ch == ',' || ch == ':') {
+            Err(anyhow!("category may not contain spaces, commas or colons"))
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?;
+        }
+    }
+
     trace.step("Searching for emoji");
-    // load all emojis from discord emoji guild
-    let gid = ctx
-        .data
-        .read()
+    let guild_id = guild_id(aci)?;
+    let emoji = resolve_emoji(ctx, guild_id, emoji_str)
         .await
-        .get::<ConfigValuesData>()
-        .unwrap()
-        .emoji_guild_id;
-    let emoji_guild = Guild::get(ctx, gid).await?;
-
-    let emoji_id = match emoji_guild
-        .emojis(ctx)
-        .await?
-        .into_iter()
-        .find(|e| e.name == emoji_str)
-    {
-        Some(e) => e.id,
-        None => {
-            Err(anyhow!(
-                "The emoji {} was not found in the emoji guild",
-                emoji_str
-            ))
-            .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
-            .await?;
-            return Ok(());
-        }
-    };
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
 
     trace.step("Saving role");
     let training_role = db::Role::insert(
         ctx,
+        guild_id,
         name.to_string(),
         repr.to_string(),
-        emoji_id.0,
+        emoji,
         Some(priority),
+        category,
     )
     .await
     .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
@@ -199,6 +230,70 @@ async fn add(
     Ok(())
 }
 
+async fn edit(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = super::helpers::command_map(option);
+    let repr = cmds
+        .get("repr")
+        .and_then(|v| v.as_str())
+        .context("Unexpected missing field repr")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    let mut changes = db::RoleChanges {
+        title: cmds
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(|n| n.to_string()),
+        // Editing a removed role brings it back
+        active: Some(true),
+        ..Default::default()
+    };
+
+    if let Some(p) = cmds.get("priority").and_then(|v| v.as_str()) {
+        changes.priority = Some(
+            parse_priority(p)
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?,
+        );
+    }
+
+    let guild_id = guild_id(aci)?;
+    if let Some(emoji_str) = cmds.get("emoji").and_then(|v| v.as_str()) {
+        trace.step("Searching for emoji");
+        changes.emoji = Some(
+            resolve_emoji(ctx, guild_id, emoji_str)
+                .await
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?,
+        );
+    }
+
+    trace.step("Load role");
+    let role = db::Role::by_repr_latest(ctx, guild_id, repr.to_string())
+        .await
+        .with_context(|| format!("The role with the repr {} does not exist", repr))
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    trace.step("Saving role");
+    let role = role
+        .edit(ctx, changes)
+        .await
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    SignupBoard::request_update(ctx, guild_id).await;
+
+    aci.create_quick_success(ctx, format!("Role updated {}", role), true)
+        .await?;
+
+    Ok(())
+}
+
 async fn remove(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
@@ -214,7 +309,7 @@ async fn remove(
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
 
-    let role = match db::Role::by_repr(ctx, repr.to_string()).await {
+    let role = match db::Role::by_repr(ctx, guild_id(aci)?, repr.to_string()).await {
         Ok(r) => r,
         Err(diesel::NotFound) => {
             Err(diesel::NotFound)
@@ -245,20 +340,16 @@ async fn remove(
 
 async fn list(ctx: &Context, aci: &ApplicationCommandInteraction, trace: LogTrace) -> Result<()> {
     trace.step("Load roles from db");
-    let mut roles = db::Role::all_active(ctx).await?;
+    let mut roles = db::Role::all_active(ctx, guild_id(aci)?).await?;
     roles.sort_by_key(|r| r.title.clone());
     roles.sort_by_key(|r| r.priority);
 
     let mut emb = CreateEmbed::xdefault();
     emb.fields_chunked_fmt(
         &roles,
-        |r| {
-            format!(
-                "{} | {} | {}",
-                Mention::from(EmojiId::from(r.emoji as u64)),
-                r.repr,
-                r.title
-            )
+        |r| match &r.category {
+            Some(c) => format!("{} | {} | {} | {}", r.emoji, r.repr, r.title, c),
+            None => format!("{} | {} | {}", r.emoji, r.repr, r.title),
         },
         "Roles",
         true,
@@ -276,3 +367,38 @@ async fn list(ctx: &Context, aci: &ApplicationCommandInteraction, trace: LogTrac
     .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TestContext, GUILD_ID};
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn editing_a_removed_role_activates_it() {
+        let ctx = TestContext::new();
+        let role = testing::role(&ctx).await;
+        let repr = role.repr.clone();
+        role.deactivate(&ctx).await.unwrap();
+        assert!(db::Role::by_repr(&ctx, GUILD_ID, repr.clone())
+            .await
+            .is_err());
+
+        let removed = db::Role::by_repr_latest(&ctx, GUILD_ID, repr.clone())
+            .await
+            .unwrap();
+        assert!(!removed.active);
+        let changes = db::RoleChanges {
+            title: Some(String::from("Renamed")),
+            priority: Some(parse_priority("high").unwrap()),
+            active: Some(true),
+            ..Default::default()
+        };
+        let edited = removed.edit(&ctx, changes).await.unwrap();
+        assert_eq!(edited.title, "Renamed");
+        assert_eq!(edited.priority, 3);
+
+        let active = db::Role::by_repr(&ctx, GUILD_ID, repr).await.unwrap();
+        assert_eq!(active.id, edited.id);
+    }
+}
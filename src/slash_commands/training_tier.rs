@@ -1,4 +1,4 @@
-use anyhow::{bail, Context as ErrContext, Result};
+use anyhow::{anyhow, bail, Context as ErrContext, Result};
 use diesel::QueryResult;
 use serenity::{
     builder::{CreateApplicationCommand, CreateEmbed},
@@ -16,12 +16,21 @@ use serenity::{
         Permissions,
     },
 };
-use serenity_tools::interactions::ApplicationCommandInteractionExt;
+use serenity_tools::{
+    builder::{CreateComponentsExt, CreateEmbedExt},
+    collectors::MessageCollectorExt,
+    components::Button,
+    interactions::{ApplicationCommandInteractionExt, MessageComponentInteractionExt},
+};
+use std::time::Duration;
 
+use super::helpers::guild_id;
 use crate::{
     db,
     embeds::CrossroadsEmbeds,
     logging::{log_discord, LogTrace, ReplyHelper},
+    promotions,
+    signup_board::SignupBoard,
 };
 
 pub(super) const CMD_TRAINING_TIER: &str = "training_tier";
@@ -43,6 +52,41 @@ pub fn create() -> CreateApplicationCommand {
             o.description("Name of the tier")
         })
     });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("rename");
+        o.description("Rename a tier");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.required(true);
+            o.name("name");
+            o.description("Current name of the tier")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.required(true);
+            o.name("new_name");
+            o.description("New name of the tier")
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("delete");
+        o.description(
+            "Delete a tier. Its trainings are moved to another tier or opened to everyone",
+        );
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.required(true);
+            o.name("name");
+            o.description("Name of the tier to delete")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("reassign");
+            o.description("Tier the trainings are moved to. Leave empty to open them to everyone")
+        })
+    });
     app.create_option(|o| {
         o.kind(ApplicationCommandOptionType::SubCommand);
         o.name("add");
@@ -77,6 +121,78 @@ pub fn create() -> CreateApplicationCommand {
             o.description("The discord role to remove from the tier")
         })
     });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("parent");
+        o.description("Place the tier below another one. Members of the higher tier qualify too");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.required(true);
+            o.name("name");
+            o.description("Name of the tier")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("parent");
+            o.description("Name of the next higher tier. Leave empty to make it a top tier")
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("promotion_add");
+        o.description("Promote members that attended enough finished trainings of a tier");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.required(true);
+            o.name("name");
+            o.description("Name of the tier to promote to")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Role);
+            o.required(true);
+            o.name("role");
+            o.description("The discord role of the tier that is assigned")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.required(true);
+            o.name("from");
+            o.description("Name of the tier whose trainings are counted")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Integer);
+            o.required(true);
+            o.name("count");
+            o.description("Number of finished trainings required");
+            o.min_int_value(1)
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::String);
+            o.name("main_role");
+            o.description("Repr of the role. Only sign-ups with it as main role are counted")
+        });
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Boolean);
+            o.name("automatic");
+            o.description("Assign the role without approval. Defaults to false")
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("promotion_remove");
+        o.description("Remove a promotion rule");
+        o.create_sub_option(|o| {
+            o.kind(ApplicationCommandOptionType::Integer);
+            o.required(true);
+            o.name("id");
+            o.description("Id of the rule as shown by promotion_list")
+        })
+    });
+    app.create_option(|o| {
+        o.kind(ApplicationCommandOptionType::SubCommand);
+        o.name("promotion_list");
+        o.description("List all promotion rules")
+    });
     app.create_option(|o| {
         o.kind(ApplicationCommandOptionType::SubCommand);
         o.name("list");
@@ -91,8 +207,14 @@ pub async fn handle(ctx: &Context, aci: &ApplicationCommandInteraction) {
         if let Some(sub) = aci.data.options.get(0) {
             match sub.name.as_ref() {
                 "new" => new(ctx, aci, sub, trace).await,
+                "rename" => rename(ctx, aci, sub, trace).await,
+                "delete" => delete(ctx, aci, sub, trace).await,
                 "add" => add(ctx, aci, sub, trace).await,
                 "remove" => remove(ctx, aci, sub, trace).await,
+                "parent" => parent(ctx, aci, sub, trace).await,
+                "promotion_add" => promotion_add(ctx, aci, sub, trace).await,
+                "promotion_remove" => promotion_remove(ctx, aci, sub, trace).await,
+                "promotion_list" => promotion_list(ctx, aci, trace).await,
                 "list" => list(ctx, aci, trace).await,
                 _ => bail!("{} not yet available", sub.name),
             }
@@ -118,7 +240,7 @@ async fn new(
         .await?;
 
     trace.step("Saving new tier");
-    let tier = db::Tier::insert(ctx, name.to_string())
+    let tier = db::Tier::insert(ctx, guild_id(aci)?, name.to_string())
         .await
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
@@ -133,6 +255,231 @@ async fn new(
     Ok(())
 }
 
+async fn rename(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = super::helpers::command_map(option);
+    let name = cmds
+        .get("name")
+        .and_then(|v| v.as_str())
+        .context("Unexpected missing field name")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let new_name = cmds
+        .get("new_name")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .context("The new name may not be empty")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    trace.step("Loading tier");
+    let guild_id = guild_id(aci)?;
+    let tier = db::Tier::by_name(ctx, guild_id, name.to_string())
+        .await
+        .with_context(|| format!("Tier **{}** does not exist", name))
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    trace.step("Saving new name");
+    let tier = tier
+        .rename(ctx, new_name.to_string())
+        .await
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    SignupBoard::invalidate_trainings(ctx, guild_id).await;
+    SignupBoard::request_update(ctx, guild_id).await;
+
+    aci.create_quick_success(
+        ctx,
+        format!("Renamed **{}** to **{}**", name, tier.name),
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn delete(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = super::helpers::command_map(option);
+    let name = cmds
+        .get("name")
+        .and_then(|v| v.as_str())
+        .context("Unexpected missing field name")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let reassign_name = cmds.get("reassign").and_then(|v| v.as_str());
+
+    trace.step("Loading tiers");
+    let guild_id = guild_id(aci)?;
+    let tiers = db::Tier::all(ctx, guild_id).await?;
+    let tier = tiers
+        .iter()
+        .find(|t| t.name == name)
+        .with_context(|| format!("Tier **{}** does not exist", name))
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let reassign = match reassign_name {
+        None => None,
+        Some(reassign_name) => {
+            let reassign = tiers
+                .iter()
+                .find(|t| t.name == reassign_name)
+                .with_context(|| format!("Tier **{}** does not exist", reassign_name))
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?;
+            if reassign.id == tier.id {
+                Err(anyhow!("Trainings can not be moved to the deleted tier"))
+                    .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                    .await?;
+            }
+            Some(reassign)
+        }
+    };
+
+    trace.step("Loading trainings");
+    let mut trainings = tier.get_trainings(ctx).await?;
+    trainings.sort_by_key(|t| t.date);
+
+    trace.step("Loading rules and requests");
+    // Rules counting trainings of the tier or promoting to one of its discord
+    // roles are removed along with it, so are open requests for it
+    let roles = db::Role::all_active(ctx, guild_id).await?;
+    let rules = db::PromotionRule::all(ctx, guild_id)
+        .await?
+        .into_iter()
+        .filter(|r| r.from_tier_id == tier.id || r.tier_id == tier.id)
+        .map(|r| promotions::describe_rule(&r, &tiers, &roles))
+        .collect::<Vec<_>>();
+    let requests = tier
+        .pending_requests(ctx)
+        .await?
+        .into_iter()
+        .map(|(r, u)| format!("{} | <t:{}:d>", u.gw2_id, r.created_at.timestamp()))
+        .collect::<Vec<_>>();
+
+    let mut emb = CreateEmbed::xdefault();
+    emb.title(format!("Delete tier {}", tier.name));
+    if trainings.is_empty() {
+        emb.description("No training uses this tier");
+    } else {
+        emb.description(match reassign {
+            Some(r) => format!(
+                "{} training(s) use this tier and will require **{}**",
+                trainings.len(),
+                r.name
+            ),
+            None => format!(
+                "{} training(s) use this tier and will be open to everyone",
+                trainings.len()
+            ),
+        });
+        emb.fields_chunked_fmt(
+            &trainings,
+            |t| {
+                format!(
+                    "{} | {} | <t:{}:d> | {}",
+                    t.id,
+                    t.title,
+                    t.date.timestamp(),
+                    t.state
+                )
+            },
+            "Trainings",
+            false,
+            10,
+        );
+    }
+    let children = tiers
+        .iter()
+        .filter(|t| t.parent_id == Some(tier.id))
+        .collect::<Vec<_>>();
+    if !children.is_empty() {
+        let title = match tier
+            .parent_id
+            .and_then(|id| tiers.iter().find(|t| t.id == id))
+        {
+            Some(parent) => format!("Tiers that move below {}", parent.name),
+            None => String::from("Tiers that become top tiers"),
+        };
+        emb.fields_chunked_fmt(&children, |t| t.name.clone(), &title, false, 20);
+    }
+    if !rules.is_empty() {
+        emb.fields_chunked_fmt(
+            &rules,
+            |r| r.clone(),
+            "Promotion rules that are removed",
+            false,
+            10,
+        );
+    }
+    if !requests.is_empty() {
+        emb.fields_chunked_fmt(
+            &requests,
+            |r| r.clone(),
+            "Open tier requests that are removed",
+            false,
+            20,
+        );
+    }
+
+    aci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource);
+        r.interaction_response_data(|d| {
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+            d.add_embed(emb);
+            d.components(|c| c.confirm_abort_row())
+        })
+    })
+    .await?;
+
+    let msg = aci.get_interaction_response(ctx).await?;
+    trace.step("Waiting for confirm");
+
+    if let Some(react) = msg
+        .await_confirm_abort_interaction(ctx)
+        .timeout(Duration::from_secs(60))
+        .await
+    {
+        react.defer(ctx).await?;
+        match react.parse_button()? {
+            Button::Confirm => {
+                trace.step("Confirmed, deleting tier");
+                tier.clone()
+                    .delete(ctx, reassign)
+                    .await
+                    .context("Failed to delete the tier. Nothing was changed")
+                    .map_err_reply(|what| aci.edit_quick_error(ctx, what))
+                    .await?;
+                SignupBoard::invalidate_trainings(ctx, guild_id).await;
+                SignupBoard::request_update(ctx, guild_id).await;
+                aci.edit_quick_success(ctx, format!("Deleted tier **{}**", tier.name))
+                    .await?;
+            }
+            Button::Abort => {
+                trace.step("Aborted");
+                aci.edit_quick_info(ctx, "Aborted").await?;
+            }
+            _ => bail!("Unexpected interaction"),
+        }
+    } else {
+        Err(anyhow!("Timed out"))
+            .map_err_reply(|what| aci.edit_quick_info(ctx, what))
+            .await?;
+    }
+
+    Ok(())
+}
+
 async fn add(
     ctx: &Context,
     aci: &ApplicationCommandInteraction,
@@ -158,7 +505,8 @@ async fn add(
     );
 
     trace.step("Loading tier");
-    let tier = match db::Tier::by_name(ctx, name.to_string()).await {
+    let guild_id = guild_id(aci)?;
+    let tier = match db::Tier::by_name(ctx, guild_id, name.to_string()).await {
         Ok(t) => t,
         Err(diesel::NotFound) => {
             Err(diesel::NotFound)
@@ -179,6 +527,8 @@ async fn add(
         .await
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
+    SignupBoard::invalidate_trainings(ctx, guild_id).await;
+    SignupBoard::request_update(ctx, guild_id).await;
 
     aci.create_quick_success(
         ctx,
@@ -215,7 +565,8 @@ async fn remove(
     );
 
     trace.step("Loading tier");
-    let tier = match db::Tier::by_name(ctx, name.to_string()).await {
+    let guild_id = guild_id(aci)?;
+    let tier = match db::Tier::by_name(ctx, guild_id, name.to_string()).await {
         Ok(t) => t,
         Err(diesel::NotFound) => {
             Err(diesel::NotFound)
@@ -245,6 +596,8 @@ async fn remove(
         .await
         .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
         .await?;
+    SignupBoard::invalidate_trainings(ctx, guild_id).await;
+    SignupBoard::request_update(ctx, guild_id).await;
 
     aci.create_quick_success(
         ctx,
@@ -256,9 +609,238 @@ async fn remove(
     Ok(())
 }
 
+async fn parent(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = super::helpers::command_map(option);
+    let name = cmds
+        .get("name")
+        .and_then(|v| v.as_str())
+        .context("Unexpected missing field name")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let parent_name = cmds.get("parent").and_then(|v| v.as_str());
+
+    trace.step("Loading tiers");
+    let guild_id = guild_id(aci)?;
+    let tiers = db::Tier::all(ctx, guild_id).await?;
+    let tier = tiers
+        .iter()
+        .find(|t| t.name == name)
+        .with_context(|| format!("Tier **{}** does not exist", name))
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let parent = match parent_name {
+        None => None,
+        Some(parent_name) => {
+            let parent = tiers
+                .iter()
+                .find(|t| t.name == parent_name)
+                .with_context(|| format!("Tier **{}** does not exist", parent_name))
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?;
+            if parent.with_parents(&tiers).iter().any(|t| t.id == tier.id) {
+                Err(anyhow!(
+                    "**{}** is already above **{}**",
+                    tier.name,
+                    parent.name
+                ))
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?;
+            }
+            Some(parent)
+        }
+    };
+
+    trace.step("Saving parent");
+    let tier = tier
+        .clone()
+        .set_parent(ctx, parent.map(|p| p.id))
+        .await
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    SignupBoard::invalidate_trainings(ctx, guild_id).await;
+    SignupBoard::request_update(ctx, guild_id).await;
+
+    let msg = match parent {
+        Some(p) => format!("**{}** is now below **{}**", tier.name, p.name),
+        None => format!("**{}** is now a top tier", tier.name),
+    };
+    aci.create_quick_success(ctx, msg, true).await?;
+
+    Ok(())
+}
+
+async fn promotion_add(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = super::helpers::command_map(option);
+    let name = cmds
+        .get("name")
+        .and_then(|v| v.as_str())
+        .context("Unexpected missing field name")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let from_name = cmds
+        .get("from")
+        .and_then(|v| v.as_str())
+        .context("Unexpected missing field from")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let role = cmds
+        .get("role")
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .context("Unexpected missing field role")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let count = cmds
+        .get("count")
+        .and_then(|v| v.as_i64())
+        .context("Unexpected missing field count")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await? as i32;
+    let automatic = cmds
+        .get("automatic")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    trace.step("Loading tiers");
+    let guild_id = guild_id(aci)?;
+    let tiers = db::Tier::all(ctx, guild_id).await?;
+    let find_tier = |name: &str| {
+        tiers
+            .iter()
+            .find(|t| t.name == name)
+            .with_context(|| format!("Tier **{}** does not exist", name))
+    };
+    let tier = find_tier(name)
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let from = find_tier(from_name)
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let mapping = tier
+        .get_tier_mapping_by_discord_role(ctx, role)
+        .await
+        .with_context(|| format!("The role is not linked to {}", tier.name))
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    let main_role = match cmds.get("main_role").and_then(|v| v.as_str()) {
+        Some(repr) => Some(
+            db::Role::by_repr(ctx, guild_id, repr.to_string())
+                .await
+                .with_context(|| format!("Unknown role: {}", repr))
+                .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+                .await?,
+        ),
+        None => None,
+    };
+
+    trace.step("Saving promotion rule");
+    let rule = db::PromotionRule::insert(ctx, &mapping, from, main_role.as_ref(), count, automatic)
+        .await
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+    let roles = main_role.into_iter().collect::<Vec<_>>();
+
+    aci.create_quick_success(
+        ctx,
+        format!(
+            "Added promotion rule {}: {}",
+            rule.id,
+            promotions::describe_rule(&rule, &tiers, &roles)
+        ),
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn promotion_remove(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    option: &ApplicationCommandInteractionDataOption,
+    trace: LogTrace,
+) -> Result<()> {
+    let cmds = super::helpers::command_map(option);
+    let id = cmds
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .context("Unexpected missing field id")
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await? as i32;
+
+    trace.step("Loading promotion rule");
+    let rule = db::PromotionRule::by_id(ctx, guild_id(aci)?, id)
+        .await
+        .with_context(|| format!("Promotion rule {} does not exist", id))
+        .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
+        .await?;
+
+    trace.step("Removing promotion rule");
+    rule.delete(ctx).await?;
+
+    aci.create_quick_success(ctx, format!("Removed promotion rule {}", id), true)
+        .await?;
+
+    Ok(())
+}
+
+async fn promotion_list(
+    ctx: &Context,
+    aci: &ApplicationCommandInteraction,
+    trace: LogTrace,
+) -> Result<()> {
+    trace.step("Loading promotion rules");
+    let guild_id = guild_id(aci)?;
+    let rules = db::PromotionRule::all(ctx, guild_id).await?;
+    let tiers = db::Tier::all(ctx, guild_id).await?;
+    let roles = db::Role::all_active(ctx, guild_id).await?;
+
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Promotion rules");
+    emb.description(
+        rules
+            .iter()
+            .map(|r| {
+                format!(
+                    "`{}` {}",
+                    r.id,
+                    promotions::describe_rule(r, &tiers, &roles)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+
+    aci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource);
+        r.interaction_response_data(|d| {
+            if !rules.is_empty() {
+                d.add_embed(emb);
+            } else {
+                d.content("There are no promotion rules set up");
+            }
+            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
 async fn list(ctx: &Context, aci: &ApplicationCommandInteraction, trace: LogTrace) -> Result<()> {
     trace.step("Loading tiers");
-    let tiers = db::Tier::all(ctx).await?;
+    let tiers = db::Tier::all(ctx, guild_id(aci)?).await?;
     trace.step("Loading roles");
     let tiers = serenity::futures::future::join_all(tiers.into_iter().map(|t| async {
         let r = t.get_discord_roles(ctx).await?;
@@ -271,22 +853,33 @@ async fn list(ctx: &Context, aci: &ApplicationCommandInteraction, trace: LogTrac
     .map_err_reply(|what| aci.create_quick_error(ctx, what, true))
     .await?;
 
+    // Top tiers first
+    let all = tiers.iter().map(|(t, _)| t.clone()).collect::<Vec<_>>();
+    let mut tiers = tiers;
+    tiers.sort_by_key(|(t, _)| (t.with_parents(&all).len(), t.name.clone()));
+
     let mut emb = CreateEmbed::xdefault();
     emb.title("Tiers");
     for (tier, roles) in &tiers {
-        emb.field(
-            &tier.name,
-            if !roles.is_empty() {
-                roles
-                    .iter()
-                    .map(|r| Mention::from(RoleId::from(r.discord_role_id as u64)).to_string())
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            } else {
-                String::from("_None_")
-            },
-            true,
-        );
+        let mut value = if !roles.is_empty() {
+            roles
+                .iter()
+                .map(|r| Mention::from(RoleId::from(r.discord_role_id as u64)).to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            String::from("_None_")
+        };
+        let above = tier
+            .with_parents(&all)
+            .iter()
+            .skip(1)
+            .map(|t| t.name.as_str())
+            .collect::<Vec<_>>();
+        if !above.is_empty() {
+            value.push_str(&format!("\n_Below {}_", above.join(" < ")));
+        }
+        emb.field(&tier.name, value, true);
     }
 
     aci.create_interaction_response(ctx, |r| {
@@ -304,3 +897,76 @@ async fn list(ctx: &Context, aci: &ApplicationCommandInteraction, trace: LogTrac
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        db,
+        testing::{self, TestContext, GUILD_ID},
+    };
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn deleting_a_tier_moves_its_trainings() {
+        let ctx = TestContext::new();
+        testing::guild(&ctx).await;
+        let deleted = db::Tier::insert(&ctx, GUILD_ID, testing::unique("deleted"))
+            .await
+            .unwrap();
+        deleted.add_discord_role(&ctx, 3301).await.unwrap();
+        let other = db::Tier::insert(&ctx, GUILD_ID, testing::unique("other"))
+            .await
+            .unwrap();
+        let moved = testing::training(&ctx, db::TrainingState::Open, Some(&deleted), &[]).await;
+        let opened = testing::training(&ctx, db::TrainingState::Open, Some(&other), &[]).await;
+
+        // Shown before deleting since they go with the tier
+        let mapping = other.add_discord_role(&ctx, 3302).await.unwrap();
+        db::PromotionRule::insert(&ctx, &mapping, &deleted, None, 3, false)
+            .await
+            .unwrap();
+        let user = testing::user(&ctx, 2501).await;
+        db::TierRequest::insert(&ctx, &user, &deleted, None, None)
+            .await
+            .unwrap();
+        let requests = deleted.pending_requests(&ctx).await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].1.id, user.id);
+
+        // Tiers below the deleted one move up to the tier above it
+        let top = db::Tier::insert(&ctx, GUILD_ID, testing::unique("top"))
+            .await
+            .unwrap();
+        let below = db::Tier::insert(&ctx, GUILD_ID, testing::unique("below"))
+            .await
+            .unwrap();
+        let deleted = deleted.set_parent(&ctx, Some(top.id)).await.unwrap();
+        below
+            .clone()
+            .set_parent(&ctx, Some(deleted.id))
+            .await
+            .unwrap();
+
+        deleted.clone().delete(&ctx, Some(&other)).await.unwrap();
+        let below = db::Tier::by_name(&ctx, GUILD_ID, below.name).await.unwrap();
+        assert_eq!(below.parent_id, Some(top.id));
+        assert!(db::PromotionRule::all(&ctx, GUILD_ID)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(deleted.pending_requests(&ctx).await.unwrap().is_empty());
+        let moved = db::Training::by_id(&ctx, GUILD_ID, moved.id).await.unwrap();
+        assert_eq!(moved.tier_id, Some(other.id));
+        assert!(db::TierMapping::all(&ctx, GUILD_ID)
+            .await
+            .unwrap()
+            .iter()
+            .all(|m| m.discord_role_id != 3301));
+
+        other.delete(&ctx, None).await.unwrap();
+        for t in [moved, opened] {
+            let t = db::Training::by_id(&ctx, GUILD_ID, t.id).await.unwrap();
+            assert_eq!(t.tier_id, None);
+        }
+    }
+}
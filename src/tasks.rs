@@ -1,26 +1,53 @@
 // automatic task trigger by specific events or constantly running in the background
 use crate::{logging::*, signup_board::SignupBoard};
-use serenity::client::Context;
-use std::time::Duration;
+use serenity::{client::Context, model::id::GuildId, prelude::RwLock};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tokio::sync::mpsc;
 
-pub async fn signup_board_task(ctx: Context) {
+// Time to wait for more changes before updating the board
+const BOARD_DEBOUNCE: Duration = Duration::from_secs(3);
+// Refresh even without changes, in case one was not reported
+const BOARD_REFRESH: Duration = Duration::from_secs(60 * 30);
+
+async fn update_board(ctx: &Context, board: Arc<RwLock<SignupBoard>>) {
+    let guild_id = board.read().await.guild_id();
+    let mut info = LogInfo::automatic("Update Signup Board");
+    info.add_guild(guild_id);
+    log_discord_err_only(ctx, info, |trace| async move {
+        trace.step("Updating board");
+        board.read().await.update_overview(ctx, trace).await?;
+        Ok(())
+    })
+    .await;
+}
+
+pub async fn signup_board_task(ctx: Context, mut changes: mpsc::UnboundedReceiver<GuildId>) {
     let ctx = &ctx;
+    let mut boards = SignupBoard::all(ctx).await;
     loop {
-        log_discord_err_only(
-            ctx,
-            LogInfo::automatic("Update Signup Board"),
-            |trace| async move {
-                trace.step("Updating board");
-                SignupBoard::get(ctx)
-                    .await
-                    .read()
-                    .await
-                    .update_overview(ctx, trace)
-                    .await?;
-                Ok(())
-            },
-        )
-        .await;
-        tokio::time::sleep(Duration::from_secs(60 * 5)).await;
+        for board in boards {
+            update_board(ctx, board).await;
+        }
+
+        boards = match tokio::time::timeout(BOARD_REFRESH, changes.recv()).await {
+            Ok(Some(guild_id)) => {
+                tokio::time::sleep(BOARD_DEBOUNCE).await;
+                let mut changed = HashSet::from([guild_id]);
+                while let Ok(guild_id) = changes.try_recv() {
+                    changed.insert(guild_id);
+                }
+                let mut boards = Vec::with_capacity(changed.len());
+                for guild_id in changed {
+                    boards.push(SignupBoard::get(ctx, guild_id).await);
+                }
+                boards
+            }
+            // All senders are gone, only refresh periodically
+            Ok(None) => {
+                tokio::time::sleep(BOARD_REFRESH).await;
+                SignupBoard::all(ctx).await
+            }
+            Err(_) => SignupBoard::all(ctx).await,
+        };
     }
 }
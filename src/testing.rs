@@ -0,0 +1,174 @@
+//! # testing
+//! Shared setup for tests that talk to the database. They need a postgres reachable
+//! through DATABASE_URL (the .env file is sourced) and are therefore ignored by default.
+//! Run them with `cargo test -- --ignored`
+
+use crate::{
+    db::{self, DBPool, DBPoolProvider},
+    discord::{mock::RecordingDiscord, DiscordFacade, MessageContent},
+    logging::LogTrace,
+};
+use anyhow::Result;
+use chrono::{Duration, NaiveDateTime, Utc};
+use serenity::{
+    async_trait,
+    model::id::{ChannelId, EmojiId, GuildId, MessageId, RoleId, UserId},
+};
+use std::sync::Arc;
+
+pub const GUILD_ID: GuildId = GuildId(1);
+
+/// Stands in for the serenity Context. Database changes are rolled back once it is dropped
+pub struct TestContext {
+    pub pool: Arc<DBPool>,
+    pub discord: RecordingDiscord,
+}
+
+impl TestContext {
+    pub fn new() -> Self {
+        Self::with_discord(RecordingDiscord::default())
+    }
+
+    pub fn with_discord(discord: RecordingDiscord) -> Self {
+        TestContext {
+            pool: Arc::new(DBPool::test()),
+            discord,
+        }
+    }
+
+    pub fn trace(&self) -> LogTrace {
+        LogTrace::new()
+    }
+}
+
+#[async_trait]
+impl DBPoolProvider for TestContext {
+    async fn db_pool(&self) -> Arc<DBPool> {
+        self.pool.clone()
+    }
+}
+
+#[async_trait]
+impl DiscordFacade for TestContext {
+    async fn member_roles(&self, guild_id: GuildId, user_id: UserId) -> Result<Vec<RoleId>> {
+        self.discord.member_roles(guild_id, user_id).await
+    }
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        content: MessageContent,
+    ) -> Result<MessageId> {
+        self.discord.send_message(channel_id, content).await
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: MessageContent,
+    ) -> Result<()> {
+        self.discord
+            .edit_message(channel_id, message_id, content)
+            .await
+    }
+
+    async fn send_dm(&self, user_id: UserId, content: MessageContent) -> Result<()> {
+        self.discord.send_dm(user_id, content).await
+    }
+
+    async fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()> {
+        self.discord
+            .add_member_role(guild_id, user_id, role_id)
+            .await
+    }
+
+    async fn create_thread(
+        &self,
+        channel_id: ChannelId,
+        name: String,
+        content: MessageContent,
+    ) -> Result<ChannelId> {
+        self.discord.create_thread(channel_id, name, content).await
+    }
+
+    async fn add_thread_member(&self, thread_id: ChannelId, user_id: UserId) -> Result<()> {
+        self.discord.add_thread_member(thread_id, user_id).await
+    }
+
+    async fn archive_thread(&self, thread_id: ChannelId) -> Result<()> {
+        self.discord.archive_thread(thread_id).await
+    }
+}
+
+// Names and reprs are unique in the database. Tests run concurrently
+// so every fixture gets its own
+pub fn unique(prefix: &str) -> String {
+    format!(
+        "{}_{}",
+        prefix,
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    )
+}
+
+pub fn tomorrow() -> NaiveDateTime {
+    (Utc::now() + Duration::days(1)).naive_utc()
+}
+
+/// Everything owned by a guild references it, so fixtures add it first
+pub async fn guild(ctx: &TestContext) -> db::Guild {
+    db::Guild::insert(ctx, GUILD_ID).await.unwrap()
+}
+
+pub async fn user(ctx: &TestContext, discord_id: u64) -> db::User {
+    db::User::upsert(
+        ctx,
+        discord_id,
+        format!("Test Account.{:04}", discord_id % 10000),
+    )
+    .await
+    .unwrap()
+}
+
+pub async fn role(ctx: &TestContext) -> db::Role {
+    guild(ctx).await;
+    let repr = unique("role");
+    db::Role::insert(
+        ctx,
+        GUILD_ID,
+        repr.clone(),
+        repr,
+        EmojiId(1).into(),
+        None,
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+pub async fn training(
+    ctx: &TestContext,
+    state: db::TrainingState,
+    tier: Option<&db::Tier>,
+    roles: &[&db::Role],
+) -> db::Training {
+    guild(ctx).await;
+    let training = db::Training::insert(
+        ctx,
+        GUILD_ID,
+        unique("Training"),
+        tomorrow(),
+        tier.map(|t| t.id),
+    )
+    .await
+    .unwrap();
+    for r in roles {
+        training.add_role(ctx, r.id).await.unwrap();
+    }
+    training.set_state(ctx, state).await.unwrap()
+}
@@ -0,0 +1,181 @@
+//! # threads
+//! Every training gets a discussion thread once it opens, so questions about it do not
+//! get lost in general chat. Everyone signing up is added to it, state changes are
+//! posted there and the thread is archived once the training finished
+
+use crate::{
+    db::{self, DBPoolProvider},
+    discord::{DiscordFacade, MessageContent},
+    embeds::{self, CrossroadsEmbeds},
+};
+use anyhow::Result;
+use serenity::{
+    builder::CreateEmbed,
+    model::id::{ChannelId, UserId},
+};
+use serenity_tools::builder::CreateEmbedExt;
+use tracing::warn;
+
+/// Discord allows 100 characters per thread name
+const NAME_LIMIT: usize = 100;
+
+fn thread_name(training: &db::Training) -> String {
+    format!("{} | {}", training.title, training.date.format("%a, %d %b"))
+        .chars()
+        .take(NAME_LIMIT)
+        .collect()
+}
+
+fn starter_message(training: &db::Training) -> MessageContent {
+    let mut emb = CreateEmbed::xdefault();
+    emb.title(&training.title);
+    emb.description(
+        "Questions about this training go here. Everyone signing up is added to this thread",
+    );
+    let (a, b, c) = embeds::field_training_date(training);
+    emb.field(a, b, c);
+    MessageContent {
+        embeds: vec![emb],
+        ..Default::default()
+    }
+}
+
+/// Creates the thread in the channel and adds everyone already signed up. Members
+/// that can not be added do not stop the others
+async fn create(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    training: &db::Training,
+    channel_id: ChannelId,
+) -> Result<ChannelId> {
+    let thread = ctx
+        .create_thread(channel_id, thread_name(training), starter_message(training))
+        .await?;
+    training.set_thread(ctx, Some(thread)).await?;
+
+    for signup in training.get_signups(ctx).await? {
+        let user = signup.get_user(ctx).await?;
+        if let Err(e) = ctx
+            .add_thread_member(thread, UserId::from(user.discord_id()))
+            .await
+        {
+            warn!("Failed to add {} to thread: {}", user.gw2_id, e);
+        }
+    }
+    Ok(thread)
+}
+
+/// Keeps the thread of the training in line with its new state. Opening a training
+/// creates the thread if it has none and a channel is set
+pub(crate) async fn state_changed(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    training: &db::Training,
+    channel_id: Option<ChannelId>,
+) -> Result<()> {
+    let thread = match (training.thread(), channel_id) {
+        (Some(thread), _) => {
+            let msg = MessageContent {
+                embeds: vec![CreateEmbed::info_box(format!(
+                    "**{}** is now **{}**",
+                    training.title, training.state
+                ))],
+                ..Default::default()
+            };
+            // Sending also opens the thread again if it was archived
+            if let Err(e) = ctx.send_message(thread, msg).await {
+                // Most likely deleted by hand. Forgetting it allows a new one
+                warn!("Failed to post to thread of {}: {}", training.id, e);
+                training.set_thread(ctx, None).await?;
+                return Ok(());
+            }
+            thread
+        }
+        (None, Some(channel_id)) if training.state == db::TrainingState::Open => {
+            create(ctx, training, channel_id).await?
+        }
+        (None, _) => return Ok(()),
+    };
+
+    if training.state == db::TrainingState::Finished {
+        ctx.archive_thread(thread).await?;
+    }
+    Ok(())
+}
+
+/// Adds the user to the thread of the training, if it has one
+pub(crate) async fn join(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    training: &db::Training,
+    user: &db::User,
+) -> Result<()> {
+    // The caller might hold a copy from before the thread was created
+    let training = db::Training::by_id(ctx, training.guild(), training.id).await?;
+    if let Some(thread) = training.thread() {
+        ctx.add_thread_member(thread, UserId::from(user.discord_id()))
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        discord::mock::DiscordCall,
+        testing::{self, TestContext, GUILD_ID},
+    };
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn thread_follows_the_training() {
+        let ctx = TestContext::new();
+        let role = testing::role(&ctx).await;
+        let training = testing::training(&ctx, db::TrainingState::Open, None, &[&role]).await;
+        let (early, late) = (
+            testing::user(&ctx, 2201).await,
+            testing::user(&ctx, 2202).await,
+        );
+        db::Signup::insert(&ctx, &early, &training).await.unwrap();
+
+        // Nothing happens without a channel
+        state_changed(&ctx, &training, None).await.unwrap();
+        assert!(ctx.discord.calls().is_empty());
+
+        let channel = ChannelId(4101);
+        state_changed(&ctx, &training, Some(channel)).await.unwrap();
+        let training = db::Training::by_id(&ctx, GUILD_ID, training.id)
+            .await
+            .unwrap();
+        let thread = training.thread().unwrap();
+
+        db::Signup::insert(&ctx, &late, &training).await.unwrap();
+        join(&ctx, &training, &late).await.unwrap();
+
+        let training = training
+            .set_state(&ctx, db::TrainingState::Finished)
+            .await
+            .unwrap();
+        state_changed(&ctx, &training, Some(channel)).await.unwrap();
+
+        let calls = ctx.discord.calls();
+        match &calls[0] {
+            DiscordCall::CreateThread(c, name, starter) => {
+                assert_eq!(*c, channel);
+                assert!(name.starts_with(&training.title));
+                assert_eq!(starter.embeds.len(), 1);
+            }
+            call => panic!("unexpected call: {:?}", call),
+        }
+        let members = calls
+            .iter()
+            .filter_map(|c| match c {
+                DiscordCall::AddThreadMember(t, u) if *t == thread => Some(u.0),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(members, vec![early.discord_id(), late.discord_id()]);
+        assert!(calls
+            .iter()
+            .any(|c| matches!(c, DiscordCall::SendMessage(t, _) if *t == thread)));
+        assert!(matches!(calls.last(), Some(DiscordCall::ArchiveThread(t)) if *t == thread));
+    }
+}
@@ -0,0 +1,253 @@
+//! # tier_requests
+//! Members can ask for a tier themselves. Requests are posted to the review channel
+//! with buttons for a squadmaker to decide. Approving grants the discord role of the
+//! tier the squadmaker picked
+
+use crate::{
+    db::{self, DBPoolProvider},
+    discord::{DiscordFacade, MessageContent},
+    embeds::CrossroadsEmbeds,
+    interactions::{review_request, ReviewKind},
+};
+use anyhow::{bail, Context as ErrContext, Result};
+use serenity::{
+    builder::CreateEmbed,
+    model::{
+        id::{GuildId, RoleId, UserId},
+        mention::Mention,
+    },
+};
+use tracing::warn;
+
+/// The tiers with at least one discord role the member does not qualify for yet,
+/// including through a tier above
+pub(crate) fn requestable_tiers<'a>(
+    tiers: &'a [db::Tier],
+    mappings: &[db::TierMapping],
+    member_roles: &[RoleId],
+) -> Vec<&'a db::Tier> {
+    tiers
+        .iter()
+        .filter(|t| {
+            let own = mappings.iter().any(|m| m.tier_id == t.id);
+            let qualified = t
+                .inherited_mappings(tiers, mappings)
+                .iter()
+                .any(|m| member_roles.contains(&RoleId::from(m.discord_role_id as u64)));
+            own && !qualified
+        })
+        .collect()
+}
+
+/// The request with one approve button per discord role of the tier, named as given
+pub(crate) fn request_message(
+    request: &db::TierRequest,
+    user: &db::User,
+    tier: &db::Tier,
+    roles: &[(RoleId, String)],
+) -> MessageContent {
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Tier request");
+    emb.description(format!(
+        "{} | {} asks for **{}**",
+        Mention::from(UserId::from(user.discord_id())),
+        user.gw2_id,
+        tier.name
+    ));
+    if let Some(justification) = &request.justification {
+        emb.field("Justification", justification, false);
+    }
+    if let Some(logs) = &request.logs {
+        emb.field("Logs", logs, false);
+    }
+    review_request(ReviewKind::TierRequest, request.id, emb, roles)
+}
+
+async fn notify(ctx: &impl DiscordFacade, user: &db::User, tier: &db::Tier, approved: bool) {
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("Tier request");
+    emb.description(if approved {
+        format!("Your request for **{}** was approved", tier.name)
+    } else {
+        format!("Your request for **{}** was denied", tier.name)
+    });
+    let content = MessageContent {
+        embeds: vec![emb],
+        ..Default::default()
+    };
+    // Users can have DMs disabled. The decision stands anyway
+    if let Err(e) = ctx.send_dm(UserId::from(user.discord_id()), content).await {
+        warn!("Failed to notify {} about tier request: {}", user.gw2_id, e);
+    }
+}
+
+/// Approves or denies a pending request of the guild. Approving grants the picked
+/// discord role of the tier. Requests posted without a pick only work as long as
+/// the tier has a single discord role
+pub(crate) async fn decide(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    guild_id: GuildId,
+    request_id: i32,
+    approve: bool,
+    role_id: Option<RoleId>,
+    decided_by: UserId,
+) -> Result<db::TierRequest> {
+    let request = db::TierRequest::by_id(ctx, request_id)
+        .await
+        .context("The request does not exist anymore")?;
+    let tier = request
+        .tier(ctx)
+        .await
+        .context("The tier does not exist anymore")?;
+    if tier.guild_id != guild_id.0 as i64 {
+        bail!("The request belongs to another server");
+    }
+    if request.state != db::PromotionState::Pending {
+        bail!("The request was already decided on");
+    }
+    let user = request.user(ctx).await?;
+
+    let role_id = if approve {
+        let roles = tier
+            .get_discord_roles(ctx)
+            .await?
+            .iter()
+            .map(|m| RoleId::from(m.discord_role_id as u64))
+            .collect::<Vec<_>>();
+        Some(match (role_id, &roles[..]) {
+            (Some(role_id), _) if roles.contains(&role_id) => role_id,
+            (Some(_), _) => bail!("The role is not part of the tier anymore"),
+            (None, [role_id]) => *role_id,
+            (None, []) => bail!("The tier has no discord role anymore"),
+            (None, _) => bail!("The tier has several discord roles now. Assign one by hand"),
+        })
+    } else {
+        None
+    };
+
+    let state = if approve {
+        db::PromotionState::Approved
+    } else {
+        db::PromotionState::Denied
+    };
+    // Decided before the role is assigned, so only one of two squadmakers
+    // clicking at the same time gets through
+    let request = request
+        .decide(ctx, state, decided_by)
+        .await?
+        .context("The request was already decided on")?;
+    if let Some(role_id) = role_id {
+        if let Err(e) = ctx
+            .add_member_role(guild_id, UserId::from(user.discord_id()), role_id)
+            .await
+        {
+            request.reopen(ctx).await?;
+            return Err(e.context("Failed to assign the role"));
+        }
+    }
+    notify(ctx, &user, &tier, approve).await;
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        discord::mock::DiscordCall,
+        testing::{self, TestContext, GUILD_ID},
+    };
+
+    #[test]
+    fn requestable_tiers_skip_qualified_ones() {
+        let tier = |id, parent_id| db::Tier {
+            id,
+            name: format!("tier{}", id),
+            guild_id: GUILD_ID.0 as i64,
+            parent_id,
+        };
+        let mapping = |tier_id, discord_role_id| db::TierMapping {
+            tier_id,
+            discord_role_id,
+        };
+        // 3 is below 2 which is below 1. 4 has no discord role
+        let tiers = vec![
+            tier(1, None),
+            tier(2, Some(1)),
+            tier(3, Some(2)),
+            tier(4, None),
+        ];
+        let mappings = vec![mapping(1, 11), mapping(2, 12), mapping(3, 13)];
+
+        let ids = |roles: &[RoleId]| {
+            requestable_tiers(&tiers, &mappings, roles)
+                .iter()
+                .map(|t| t.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&[]), vec![1, 2, 3]);
+        assert_eq!(ids(&[RoleId(12)]), vec![1]);
+        assert_eq!(ids(&[RoleId(11)]), Vec::<i32>::new());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn approving_grants_the_tier_role() {
+        let (member, squadmaker) = (UserId(2201), UserId(2202));
+        let ctx = TestContext::new();
+        testing::guild(&ctx).await;
+        let tier = db::Tier::insert(&ctx, GUILD_ID, testing::unique("tier"))
+            .await
+            .unwrap();
+        tier.add_discord_role(&ctx, 3201).await.unwrap();
+        tier.add_discord_role(&ctx, 3202).await.unwrap();
+        let user = testing::user(&ctx, member.0).await;
+
+        let request = db::TierRequest::insert(&ctx, &user, &tier, Some("pls".into()), None)
+            .await
+            .unwrap();
+        // The squadmaker has to pick one of the roles
+        assert!(decide(&ctx, GUILD_ID, request.id, true, None, squadmaker)
+            .await
+            .is_err());
+        assert!(decide(
+            &ctx,
+            GUILD_ID,
+            request.id,
+            true,
+            Some(RoleId(3299)),
+            squadmaker
+        )
+        .await
+        .is_err());
+        let stale = request.clone();
+        let request = decide(
+            &ctx,
+            GUILD_ID,
+            request.id,
+            true,
+            Some(RoleId(3202)),
+            squadmaker,
+        )
+        .await
+        .unwrap();
+        // A second squadmaker that loaded it before the first one decided
+        assert!(stale
+            .decide(&ctx, db::PromotionState::Denied, squadmaker)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(request.state, db::PromotionState::Approved);
+        assert_eq!(request.decided_by, Some(squadmaker.0 as i64));
+        assert!(request.decided_at.is_some());
+        assert!(ctx.discord.calls().into_iter().any(|c| matches!(
+            c,
+            DiscordCall::AddMemberRole(g, u, RoleId(3202)) if g == GUILD_ID && u == member
+        )));
+        assert!(decide(&ctx, GUILD_ID, request.id, false, None, squadmaker)
+            .await
+            .is_err());
+
+        let requests = user.tier_requests(&ctx, GUILD_ID).await.unwrap();
+        assert_eq!(requests.len(), 1);
+    }
+}
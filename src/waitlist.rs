@@ -0,0 +1,116 @@
+//! # waitlist
+//! Sign-ups past the participant limit of a training wait for a spot. However sign-ups
+//! are removed, by leaving, unregistering or leaving the server, everyone that moves up
+//! gets a DM
+
+use crate::{
+    announcements,
+    db::{self, DBPoolProvider},
+    discord::{DiscordFacade, MessageContent},
+    embeds::{self, CrossroadsEmbeds},
+};
+use anyhow::Result;
+use diesel::QueryResult;
+use serenity::{builder::CreateEmbed, model::id::UserId};
+use std::future::Future;
+use tracing::warn;
+
+async fn notify(ctx: &impl DiscordFacade, user: &db::User, training: &db::Training) {
+    let mut emb = CreateEmbed::xdefault();
+    emb.title("You moved up from the waitlist");
+    emb.description(format!(
+        "A spot opened up and you are now participating in **{}**",
+        training.title
+    ));
+    let (a, b, c) = embeds::field_training_date(training);
+    emb.field(a, b, c);
+    let content = MessageContent {
+        embeds: vec![emb],
+        ..Default::default()
+    };
+    // Users can have DMs disabled. They are promoted anyway
+    if let Err(e) = ctx.send_dm(UserId::from(user.discord_id()), content).await {
+        warn!("Failed to notify {} about promotion: {}", user.gw2_id, e);
+    }
+}
+
+/// Runs the removal and notifies everyone who moved up from the waitlist of the
+/// given trainings because of it, the announcements of the trainings are refreshed.
+/// Returns the promoted users with their training
+pub(crate) async fn remove_signups(
+    ctx: &(impl DBPoolProvider + DiscordFacade),
+    trainings: &[db::Training],
+    removal: impl Future<Output = QueryResult<usize>>,
+) -> Result<Vec<(db::User, db::Training)>> {
+    let mut waitlists = Vec::with_capacity(trainings.len());
+    for t in trainings {
+        waitlists.push(t.waitlist(ctx).await?);
+    }
+    removal.await?;
+
+    let mut promoted = Vec::new();
+    for (t, waitlist) in trainings.iter().zip(waitlists) {
+        let signups = t.get_signups(ctx).await?;
+        let remaining = t.waitlist(ctx).await?;
+        // Still signed up but no longer waiting
+        let mut users = Vec::new();
+        for s in waitlist.into_iter().filter(|s| {
+            signups.iter().any(|o| o.id == s.id) && !remaining.iter().any(|r| r.id == s.id)
+        }) {
+            let user = s.get_user(ctx).await?;
+            notify(ctx, &user, t).await;
+            users.push(user);
+        }
+        if let Err(e) = announcements::refresh(ctx, t, &users).await {
+            warn!("Failed to refresh announcement of {}: {}", t.id, e);
+        }
+        promoted.extend(users.into_iter().map(|u| (u, t.clone())));
+    }
+    Ok(promoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        discord::mock::DiscordCall,
+        testing::{self, TestContext, GUILD_ID},
+    };
+
+    #[tokio::test]
+    #[ignore = "requires postgres"]
+    async fn unregistering_promotes_the_waitlist() {
+        let ctx = TestContext::new();
+        let training = testing::training(&ctx, db::TrainingState::Open, None, &[]).await;
+        let training = training.set_max_participants(&ctx, Some(1)).await.unwrap();
+        let (leaving, waiting) = (
+            testing::user(&ctx, 2301).await,
+            testing::user(&ctx, 2302).await,
+        );
+        for user in [&leaving, &waiting] {
+            db::Signup::insert(&ctx, user, &training).await.unwrap();
+        }
+
+        let joined = leaving.joined_active_trainings(&ctx).await.unwrap();
+        let promoted = remove_signups(&ctx, &joined, leaving.delete(&ctx))
+            .await
+            .unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].0.id, waiting.id);
+        assert_eq!(promoted[0].1.id, training.id);
+        assert!(ctx.discord.calls().iter().any(|c| matches!(
+            c,
+            DiscordCall::SendDm(u, _) if u.0 == waiting.discord_id()
+        )));
+
+        // Leaving the server works the same, the waitlist is empty by now
+        let promoted = remove_signups(
+            &ctx,
+            &[training],
+            waiting.remove_guild_signups(&ctx, GUILD_ID),
+        )
+        .await
+        .unwrap();
+        assert!(promoted.is_empty());
+    }
+}